@@ -0,0 +1,172 @@
+//! Derive macros for the `redis` crate.  Use them through the `derive`
+//! feature of `redis` which re-exports them.
+
+#![deny(non_camel_case_types)]
+
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+extern crate syn;
+
+use proc_macro::TokenStream;
+use syn::{Data, DeriveInput, Fields, LitStr, Type};
+
+
+/// Derives `ToRedisArgs` and `FromRedisValue` for a struct with named
+/// fields that is stored as a hash.
+///
+/// As arguments the struct becomes the field and value pairs `HSET`
+/// expects, and it is read from the reply of `HGETALL`.  Fields are named
+/// like the struct fields unless renamed with `#[redis(rename = "...")]`.
+/// `Option` fields are left out if they are `None` and are `None` if the
+/// hash lacks them, all other fields have to be in the hash.
+///
+/// ```rust,ignore
+/// #[derive(RedisHash)]
+/// struct User {
+///     name: String,
+///     #[redis(rename = "mail")]
+///     email: Option<String>,
+/// }
+///
+/// let user = User { name: "Jane".to_string(), email: None };
+/// redis::cmd("HSET").arg("user:1").arg(&user).execute(&con);
+/// let user: User = con.hgetall("user:1").unwrap();
+/// ```
+#[proc_macro_derive(RedisHash, attributes(redis))]
+pub fn derive_redis_hash(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    match expand_redis_hash(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+struct HashField {
+    ident: syn::Ident,
+    name: String,
+    optional: bool,
+}
+
+fn expand_redis_hash(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let fields = match input.data {
+        Data::Struct(ref data) => {
+            match data.fields {
+                Fields::Named(ref fields) => &fields.named,
+                _ => {
+                    return Err(syn::Error::new_spanned(input,
+                                                       "RedisHash needs named fields"));
+                }
+            }
+        }
+        _ => return Err(syn::Error::new_spanned(input, "RedisHash only supports structs")),
+    };
+
+    let mut hash_fields = vec![];
+    for field in fields.iter() {
+        let ident = field.ident.clone().unwrap();
+        let mut name = ident.to_string();
+        for attr in field.attrs.iter() {
+            if !attr.path().is_ident("redis") {
+                continue;
+            }
+            try!(attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    let value: LitStr = try!(try!(meta.value()).parse());
+                    name = value.value();
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown redis attribute, expected `rename`"))
+                }
+            }));
+        }
+        hash_fields.push(HashField {
+            ident: ident,
+            name: name,
+            optional: is_option(&field.ty),
+        });
+    }
+
+    let ty = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let to_args = hash_fields.iter().map(|field| {
+        let ident = &field.ident;
+        let name = &field.name;
+        if field.optional {
+            quote! {
+                if let ::std::option::Option::Some(ref value) = self.#ident {
+                    rv.push(#name.as_bytes().to_vec());
+                    rv.extend(::redis::ToRedisArgs::to_redis_args(value));
+                }
+            }
+        } else {
+            quote! {
+                rv.push(#name.as_bytes().to_vec());
+                rv.extend(::redis::ToRedisArgs::to_redis_args(&self.#ident));
+            }
+        }
+    });
+
+    let from_value = hash_fields.iter().map(|field| {
+        let ident = &field.ident;
+        let name = &field.name;
+        if field.optional {
+            quote! {
+                #ident: match map.get(#name) {
+                    ::std::option::Option::Some(value) => ::redis::from_redis_value(value)?,
+                    ::std::option::Option::None => ::std::option::Option::None,
+                }
+            }
+        } else {
+            quote! {
+                #ident: match map.get(#name) {
+                    ::std::option::Option::Some(value) => ::redis::from_redis_value(value)?,
+                    ::std::option::Option::None => {
+                        return ::std::result::Result::Err(::redis::RedisError::from((
+                            ::redis::ErrorKind::TypeError,
+                            "Missing field in hash",
+                            #name.to_string(),
+                        )));
+                    }
+                }
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::redis::ToRedisArgs for #ty #ty_generics #where_clause {
+            fn to_redis_args(&self) -> ::std::vec::Vec<::std::vec::Vec<u8>> {
+                let mut rv = ::std::vec::Vec::new();
+                #(#to_args)*
+                rv
+            }
+
+            fn is_single_arg(&self) -> bool {
+                false
+            }
+        }
+
+        impl #impl_generics ::redis::FromRedisValue for #ty #ty_generics #where_clause {
+            fn from_redis_value(v: &::redis::Value) -> ::redis::RedisResult<#ty #ty_generics> {
+                let map: ::std::collections::HashMap<::std::string::String, ::redis::Value> =
+                    ::redis::from_redis_value(v)?;
+                ::std::result::Result::Ok(#ty {
+                    #(#from_value,)*
+                })
+            }
+        }
+    })
+}
+
+/// Tells whether a field is an `Option`, which is left out of the hash if
+/// it is `None`.  This goes by the name as types are not resolved yet.
+fn is_option(ty: &Type) -> bool {
+    match *ty {
+        Type::Path(ref path) if path.qself.is_none() => {
+            path.path.segments.last().map_or(false, |segment| segment.ident == "Option")
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+
+use types::{FromRedisValue, ToRedisArgs, RedisResult, Value, from_redis_value};
+
+
+/// The rules `ACL SETUSER` applies to a user.  They are sent in the
+/// order they were added, later rules override earlier ones:
+///
+/// ```rust,no_run
+/// use redis::{AclRules, Commands};
+/// # let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+/// # let con = client.get_connection().unwrap();
+/// let rules = AclRules::new()
+///     .reset()
+///     .on()
+///     .password("secret")
+///     .key_pattern("app:*")
+///     .channel_pattern("app-events")
+///     .category("read")
+///     .category("write")
+///     .deny_category("dangerous");
+/// let _: () = con.acl_setuser("app", rules).unwrap();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AclRules {
+    args: Vec<Vec<u8>>,
+}
+
+impl AclRules {
+    /// Creates an empty list of rules.  Applied to a new user it creates
+    /// a disabled user without any permissions.
+    pub fn new() -> AclRules {
+        AclRules::default()
+    }
+
+    /// Enables the user.
+    pub fn on(self) -> AclRules {
+        self.push("on".to_string())
+    }
+
+    /// Disables the user, existing connections stay authenticated.
+    pub fn off(self) -> AclRules {
+        self.push("off".to_string())
+    }
+
+    /// Resets the user to a disabled user without passwords and
+    /// permissions.
+    pub fn reset(self) -> AclRules {
+        self.push("reset".to_string())
+    }
+
+    /// Adds a password of the user.
+    pub fn password(self, password: &str) -> AclRules {
+        self.push(format!(">{}", password))
+    }
+
+    /// Removes a password of the user.
+    pub fn remove_password(self, password: &str) -> AclRules {
+        self.push(format!("<{}", password))
+    }
+
+    /// Adds a password as its hex encoded SHA-256 hash.
+    pub fn hashed_password(self, hash: &str) -> AclRules {
+        self.push(format!("#{}", hash))
+    }
+
+    /// Lets the user authenticate with any password.
+    pub fn nopass(self) -> AclRules {
+        self.push("nopass".to_string())
+    }
+
+    /// Removes all passwords of the user.
+    pub fn reset_passwords(self) -> AclRules {
+        self.push("resetpass".to_string())
+    }
+
+    /// Allows access to the keys matching the glob-style pattern.
+    pub fn key_pattern(self, pattern: &str) -> AclRules {
+        self.push(format!("~{}", pattern))
+    }
+
+    /// Allows reading the keys matching the glob-style pattern.
+    pub fn read_key_pattern(self, pattern: &str) -> AclRules {
+        self.push(format!("%R~{}", pattern))
+    }
+
+    /// Allows writing the keys matching the glob-style pattern.
+    pub fn write_key_pattern(self, pattern: &str) -> AclRules {
+        self.push(format!("%W~{}", pattern))
+    }
+
+    /// Allows access to all keys.
+    pub fn all_keys(self) -> AclRules {
+        self.push("allkeys".to_string())
+    }
+
+    /// Removes all key patterns of the user.
+    pub fn reset_keys(self) -> AclRules {
+        self.push("resetkeys".to_string())
+    }
+
+    /// Allows access to the pubsub channels matching the glob-style
+    /// pattern.
+    pub fn channel_pattern(self, pattern: &str) -> AclRules {
+        self.push(format!("&{}", pattern))
+    }
+
+    /// Allows access to all pubsub channels.
+    pub fn all_channels(self) -> AclRules {
+        self.push("allchannels".to_string())
+    }
+
+    /// Removes all channel patterns of the user.
+    pub fn reset_channels(self) -> AclRules {
+        self.push("resetchannels".to_string())
+    }
+
+    /// Allows a command, or a subcommand as `command|subcommand`.
+    pub fn command(self, command: &str) -> AclRules {
+        self.push(format!("+{}", command))
+    }
+
+    /// Denies a command, or a subcommand as `command|subcommand`.
+    pub fn deny_command(self, command: &str) -> AclRules {
+        self.push(format!("-{}", command))
+    }
+
+    /// Allows the commands of a category, see `acl_cat`.
+    pub fn category(self, category: &str) -> AclRules {
+        self.push(format!("+@{}", category))
+    }
+
+    /// Denies the commands of a category.
+    pub fn deny_category(self, category: &str) -> AclRules {
+        self.push(format!("-@{}", category))
+    }
+
+    /// Allows all commands.
+    pub fn all_commands(self) -> AclRules {
+        self.push("allcommands".to_string())
+    }
+
+    /// Denies all commands.
+    pub fn no_commands(self) -> AclRules {
+        self.push("nocommands".to_string())
+    }
+
+    fn push(mut self, rule: String) -> AclRules {
+        self.args.push(rule.into_bytes());
+        self
+    }
+}
+
+impl ToRedisArgs for AclRules {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        self.args.clone()
+    }
+}
+
+
+/// A user as reported by `ACL GETUSER`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AclUserInfo {
+    /// The flags of the user, like `on`, `off` or `nopass`.
+    pub flags: Vec<String>,
+    /// The SHA-256 hashes of the passwords of the user.
+    pub passwords: Vec<String>,
+    /// The allowed and denied commands and categories, as a rule string
+    /// like `+@all -debug`.
+    pub commands: String,
+    /// The key patterns of the user, including their `~` or `%R~` prefix.
+    pub keys: Vec<String>,
+    /// The channel patterns of the user, including their `&` prefix.
+    pub channels: Vec<String>,
+    fields: HashMap<String, Value>,
+}
+
+impl AclUserInfo {
+    /// Looks up any field of the reply, like `selectors`, and converts it
+    /// into the given type.
+    pub fn get<T: FromRedisValue>(&self, field: &str) -> Option<T> {
+        self.fields.get(field).and_then(|v| from_redis_value(v).ok())
+    }
+
+    /// Checks if the user has the given flag.
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.flags.iter().any(|f| f == flag)
+    }
+}
+
+// the keys and channels are lists before redis 7 and space separated
+// strings since then.
+fn patterns(v: Option<&Value>) -> RedisResult<Vec<String>> {
+    let v = unwrap_or!(v, return Ok(vec![]));
+    match *v {
+        Value::Bulk(_) | Value::Set(_) => from_redis_value(v),
+        _ => {
+            let s: String = try!(from_redis_value(v));
+            Ok(s.split_whitespace().map(|p| p.to_string()).collect())
+        }
+    }
+}
+
+impl FromRedisValue for AclUserInfo {
+    fn from_redis_value(v: &Value) -> RedisResult<AclUserInfo> {
+        let fields: HashMap<String, Value> = try!(from_redis_value(v));
+        let nil = Value::Nil;
+        Ok(AclUserInfo {
+            flags: try!(from_redis_value(fields.get("flags").unwrap_or(&nil))),
+            passwords: try!(from_redis_value(fields.get("passwords").unwrap_or(&nil))),
+            commands: match fields.get("commands") {
+                Some(commands) => try!(from_redis_value(commands)),
+                None => String::new(),
+            },
+            keys: try!(patterns(fields.get("keys"))),
+            channels: try!(patterns(fields.get("channels"))),
+            fields: fields,
+        })
+    }
+}
@@ -0,0 +1,133 @@
+use types::ToRedisArgs;
+
+
+/// The integer type of a `BITFIELD` field.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BitFieldType {
+    /// A signed integer of 1 to 64 bits.
+    Signed(u8),
+    /// An unsigned integer of 1 to 63 bits.
+    Unsigned(u8),
+}
+
+impl ToRedisArgs for BitFieldType {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        let ty = match *self {
+            BitFieldType::Signed(bits) => format!("i{}", bits),
+            BitFieldType::Unsigned(bits) => format!("u{}", bits),
+        };
+        vec![ty.into_bytes()]
+    }
+}
+
+/// The position of a `BITFIELD` field.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BitFieldOffset {
+    /// The offset in bits.
+    Bits(usize),
+    /// The index of the field if the value is seen as an array of
+    /// fields of its type.
+    Index(usize),
+}
+
+impl From<usize> for BitFieldOffset {
+    fn from(bits: usize) -> BitFieldOffset {
+        BitFieldOffset::Bits(bits)
+    }
+}
+
+impl ToRedisArgs for BitFieldOffset {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        let offset = match *self {
+            BitFieldOffset::Bits(bits) => bits.to_string(),
+            BitFieldOffset::Index(idx) => format!("#{}", idx),
+        };
+        vec![offset.into_bytes()]
+    }
+}
+
+/// What `SET` and `INCRBY` operations of a `BITFIELD` do if the value
+/// does not fit the field.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BitFieldOverflow {
+    /// Wraps around, this is the default.
+    Wrap,
+    /// Saturates at the minimum or maximum value of the type.
+    Sat,
+    /// Does nothing and returns nil for the operation.
+    Fail,
+}
+
+
+/// The operations of a `BITFIELD` command.  They are sent in the order
+/// they were added, an overflow policy applies to the operations added
+/// after it.
+///
+/// The reply has one item per `get`, `set` and `incr_by` operation.
+/// Decode it as `Vec<Option<i64>>`, failed operations are `None`:
+///
+/// ```rust,no_run
+/// use redis::{BitFieldOperations, BitFieldOverflow, BitFieldType, Commands};
+/// # let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+/// # let con = client.get_connection().unwrap();
+/// let ops = BitFieldOperations::new()
+///     .overflow(BitFieldOverflow::Fail)
+///     .incr_by(BitFieldType::Unsigned(8), 0, 10)
+///     .get(BitFieldType::Signed(4), 8);
+/// let results: Vec<Option<i64>> = con.bitfield("counters", ops).unwrap();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BitFieldOperations {
+    args: Vec<Vec<u8>>,
+}
+
+impl BitFieldOperations {
+    /// Creates an empty list of operations.
+    pub fn new() -> BitFieldOperations {
+        BitFieldOperations::default()
+    }
+
+    /// Reads a field.
+    pub fn get<O: Into<BitFieldOffset>>(self, ty: BitFieldType, offset: O) -> BitFieldOperations {
+        self.push(("GET", ty, offset.into()))
+    }
+
+    /// Sets a field and returns its old value.
+    pub fn set<O: Into<BitFieldOffset>>(self,
+                                        ty: BitFieldType,
+                                        offset: O,
+                                        value: i64)
+                                        -> BitFieldOperations {
+        self.push(("SET", ty, offset.into(), value))
+    }
+
+    /// Increments a field and returns its new value.
+    pub fn incr_by<O: Into<BitFieldOffset>>(self,
+                                            ty: BitFieldType,
+                                            offset: O,
+                                            increment: i64)
+                                            -> BitFieldOperations {
+        self.push(("INCRBY", ty, offset.into(), increment))
+    }
+
+    /// Sets the overflow policy of the following operations.
+    pub fn overflow(self, overflow: BitFieldOverflow) -> BitFieldOperations {
+        let overflow = match overflow {
+            BitFieldOverflow::Wrap => "WRAP",
+            BitFieldOverflow::Sat => "SAT",
+            BitFieldOverflow::Fail => "FAIL",
+        };
+        self.push(("OVERFLOW", overflow))
+    }
+
+    fn push<T: ToRedisArgs>(mut self, op: T) -> BitFieldOperations {
+        self.args.extend(op.to_redis_args());
+        self
+    }
+}
+
+impl ToRedisArgs for BitFieldOperations {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        self.args.clone()
+    }
+}
@@ -1,5 +1,7 @@
 use connection::{ConnectionInfo, IntoConnectionInfo, Connection, connect, PubSub, connect_pubsub,
-                 ConnectionLike};
+                 ConnectionLike, ConnectionAddr};
+use std::time::Duration;
+
 use types::{RedisResult, Value};
 
 
@@ -42,6 +44,15 @@ impl Client {
         Ok(try!(connect(&self.connection_info)))
     }
 
+    /// Like `get_connection` but establishing the connection fails with
+    /// an `IoError` if it takes longer than the given timeout.  This
+    /// overrides the `connect_timeout` of the connection info.
+    pub fn get_connection_with_timeout(&self, timeout: Duration) -> RedisResult<Connection> {
+        let mut connection_info = self.connection_info.clone();
+        connection_info.connect_timeout = Some(timeout);
+        Ok(try!(connect(&connection_info)))
+    }
+
     /// Returns a PubSub connection.  A pubsub connection can be used to
     /// listen to messages coming in through the redis publish/subscribe
     /// system.
@@ -68,4 +79,8 @@ impl ConnectionLike for Client {
     fn get_db(&self) -> i64 {
         self.connection_info.db
     }
+
+    fn get_addr(&self) -> Option<ConnectionAddr> {
+        Some((*self.connection_info.addr).clone())
+    }
 }
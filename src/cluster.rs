@@ -0,0 +1,1393 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use cmd::{cmd, pack_command};
+use connection::{connect, connect_pubsub, Connection, ConnectionAddr, ConnectionInfo,
+                 ConnectionLike, IntoConnectionInfo, PubSub};
+use parser::{Parser, parse_redis_value};
+use types::{RedisResult, RedisError, ErrorKind, Value, FromRedisValue, ToRedisArgs, InfoDict,
+            from_redis_value};
+
+
+/// The number of hash slots a redis cluster is divided into.
+pub const SLOT_SIZE: u16 = 16384;
+
+/// How often a command is redirected or retried before giving up.
+const MAX_REDIRECTIONS: usize = 16;
+
+/// The minimum time between two fetches of the slot layout.  A random
+/// delay of up to the same length is added so that many clients do not
+/// hit the cluster at the same time after a topology change.
+const SLOT_REFRESH_INTERVAL_MS: u64 = 100;
+
+/// Returns the hash slot a key belongs to in a redis cluster.
+///
+/// If the key contains a hash tag (a non-empty part in curly braces)
+/// only that part is hashed.  This allows putting related keys into the
+/// same slot, for instance `{user1000}.following` and
+/// `{user1000}.followers`.
+pub fn key_slot(key: &[u8]) -> u16 {
+    crc16(hash_tag(key)) % SLOT_SIZE
+}
+
+fn hash_tag(key: &[u8]) -> &[u8] {
+    if let Some(open) = key.iter().position(|&b| b == b'{') {
+        if let Some(close) = key[open + 1..].iter().position(|&b| b == b'}') {
+            if close > 0 {
+                return &key[open + 1..open + 1 + close];
+            }
+        }
+    }
+    key
+}
+
+/// CRC16 with the XMODEM polynomial as used by redis cluster.
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0;
+    for &byte in data {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            if crc & 0x8000 != 0 {
+                crc = (crc << 1) ^ 0x1021;
+            } else {
+                crc <<= 1;
+            }
+        }
+    }
+    crc
+}
+
+
+/// Where a command needs to be sent to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RoutingInfo {
+    /// The command affects the data of every node.
+    AllMasters,
+    /// The command has no key so any node can answer it.
+    Random,
+    /// The command operates on keys of this slot.
+    Slot(u16),
+}
+
+fn get_arg(args: &[Value], idx: usize) -> Option<&[u8]> {
+    match args.get(idx) {
+        Some(&Value::Data(ref data)) => Some(&data[..]),
+        _ => None,
+    }
+}
+
+fn get_count(args: &[Value], idx: usize) -> usize {
+    get_arg(args, idx)
+        .and_then(|n| String::from_utf8_lossy(n).parse().ok())
+        .unwrap_or(0)
+}
+
+fn get_args(args: &[Value], from: usize, to: usize) -> Vec<&[u8]> {
+    (from..to).filter_map(|idx| get_arg(args, idx)).collect()
+}
+
+/// Returns the keys of a command.  Commands that are not known to take
+/// several keys are assumed to have their key as the first argument.
+fn command_keys<'a>(name: &str, args: &'a [Value]) -> Vec<&'a [u8]> {
+    let len = args.len();
+    match name {
+        "MGET" | "DEL" | "UNLINK" | "EXISTS" | "TOUCH" | "WATCH" | "SDIFF" | "SINTER" |
+        "SUNION" | "SDIFFSTORE" | "SINTERSTORE" | "SUNIONSTORE" | "PFCOUNT" | "PFMERGE" => {
+            get_args(args, 1, len)
+        }
+        "MSET" | "MSETNX" => (1..len).step_by(2).filter_map(|idx| get_arg(args, idx)).collect(),
+        "RENAME" | "RENAMENX" | "RPOPLPUSH" | "BRPOPLPUSH" | "SMOVE" | "LMOVE" | "BLMOVE" |
+        "GEOSEARCHSTORE" | "COPY" | "ZRANGESTORE" | "LCS" => get_args(args, 1, 3),
+        "BLPOP" | "BRPOP" | "BZPOPMIN" | "BZPOPMAX" => get_args(args, 1, len.saturating_sub(1)),
+        "BITOP" => get_args(args, 2, len),
+        "ZUNIONSTORE" | "ZINTERSTORE" => {
+            let mut keys = get_args(args, 1, 2);
+            keys.extend(get_args(args, 3, 3 + get_count(args, 2)));
+            keys
+        }
+        "EVAL" | "EVALSHA" | "EVAL_RO" | "EVALSHA_RO" | "FCALL" | "FCALL_RO" | "BLMPOP" |
+        "BZMPOP" => {
+            get_args(args, 3, 3 + get_count(args, 2))
+        }
+        "LMPOP" | "ZMPOP" | "SINTERCARD" | "ZINTERCARD" => {
+            get_args(args, 2, 2 + get_count(args, 1))
+        }
+        "XGROUP" | "XINFO" | "OBJECT" | "MEMORY" => get_args(args, 2, 3),
+        "MIGRATE" => {
+            match get_arg(args, 3) {
+                Some(key) if !key.is_empty() => vec![key],
+                _ => {
+                    let keys = args.iter().position(|arg| match *arg {
+                        Value::Data(ref data) => data.eq_ignore_ascii_case(b"KEYS"),
+                        _ => false,
+                    });
+                    keys.map_or(vec![], |idx| get_args(args, idx + 1, len))
+                }
+            }
+        }
+        "XREAD" | "XREADGROUP" => {
+            let streams = args.iter().position(|arg| match *arg {
+                Value::Data(ref data) => data.eq_ignore_ascii_case(b"STREAMS"),
+                _ => false,
+            });
+            match streams {
+                // the keys are followed by one id per key
+                Some(idx) => get_args(args, idx + 1, idx + 1 + (len - idx - 1) / 2),
+                None => vec![],
+            }
+        }
+        "PING" | "ECHO" | "INFO" | "TIME" | "CLUSTER" | "CLIENT" | "CONFIG" | "READONLY" |
+        "READWRITE" | "ASKING" | "MULTI" | "EXEC" | "DISCARD" | "SCAN" | "KEYS" | "RANDOMKEY" |
+        "DBSIZE" | "PUBSUB" | "SLOWLOG" | "ACL" | "FUNCTION" | "SCRIPT" | "WAIT" |
+        "FAILOVER" | "FLUSHDB" | "FLUSHALL" | "LATENCY" | "COMMAND" |
+        "REPLICAOF" | "SHUTDOWN" => vec![],
+        _ => get_args(args, 1, 2),
+    }
+}
+
+/// Returns whether a command only reads data and can be answered by a
+/// replica.
+fn is_readonly_command(name: &str) -> bool {
+    match name {
+        "GET" | "MGET" | "STRLEN" | "GETRANGE" | "SUBSTR" | "GETBIT" | "BITCOUNT" | "BITPOS" |
+        "EXISTS" | "TYPE" | "TTL" | "PTTL" | "DUMP" | "OBJECT" | "HGET" | "HMGET" | "HGETALL" |
+        "HKEYS" | "HVALS" | "HLEN" | "HEXISTS" | "HSTRLEN" | "HSCAN" | "LRANGE" | "LLEN" |
+        "LINDEX" | "SMEMBERS" | "SISMEMBER" | "SCARD" | "SRANDMEMBER" | "SSCAN" | "SDIFF" |
+        "SINTER" | "SUNION" | "ZRANGE" | "ZREVRANGE" | "ZRANGEBYSCORE" | "ZREVRANGEBYSCORE" |
+        "ZRANGEBYLEX" | "ZREVRANGEBYLEX" | "ZSCORE" | "ZCARD" | "ZCOUNT" | "ZLEXCOUNT" |
+        "ZRANK" | "ZREVRANK" | "ZSCAN" | "PFCOUNT" | "GEOPOS" | "GEODIST" | "GEOHASH" |
+        "GEOSEARCH" | "XRANGE" | "XREVRANGE" | "XLEN" | "XREAD" | "XINFO" | "SCAN" | "KEYS" |
+        "RANDOMKEY" | "DBSIZE" | "EXPIRETIME" | "PEXPIRETIME" | "SINTERCARD" |
+        "ZINTERCARD" | "LCS" | "HRANDFIELD" | "ZRANDMEMBER" | "MEMORY" | "FCALL_RO" |
+        "EVAL_RO" | "EVALSHA_RO" => true,
+        _ => false,
+    }
+}
+
+impl RoutingInfo {
+    /// Figures out the routing of a command from its arguments.  Fails
+    /// with a `CrossSlot` error if its keys belong to different slots.
+    fn for_command(args: &[Value]) -> RedisResult<RoutingInfo> {
+        let name = match get_arg(args, 0) {
+            Some(name) => String::from_utf8_lossy(name).to_uppercase(),
+            None => return Ok(RoutingInfo::Random),
+        };
+        match &*name {
+            "FLUSHALL" | "FLUSHDB" | "SCRIPT" => return Ok(RoutingInfo::AllMasters),
+            // functions have to be loaded on every master, only the
+            // commands that inspect them can go to any node
+            "FUNCTION" => {
+                let sub = get_arg(args, 1).map(|sub| String::from_utf8_lossy(sub).to_uppercase());
+                match sub.as_ref().map(|sub| &sub[..]) {
+                    Some("LIST") | Some("STATS") | Some("DUMP") => {}
+                    _ => return Ok(RoutingInfo::AllMasters),
+                }
+            }
+            _ => {}
+        }
+        let mut rv = RoutingInfo::Random;
+        for key in command_keys(&name, args) {
+            rv = try!(rv.combine(RoutingInfo::Slot(key_slot(key))));
+        }
+        Ok(rv)
+    }
+
+    /// Merges the routing of two commands that have to be sent to the
+    /// same node.
+    fn combine(self, other: RoutingInfo) -> RedisResult<RoutingInfo> {
+        match (self, other) {
+            (RoutingInfo::Slot(a), RoutingInfo::Slot(b)) if a != b => {
+                fail!((ErrorKind::CrossSlot,
+                       "Keys in request don't hash to the same slot",
+                       format!("slots {} and {}", a, b)))
+            }
+            (RoutingInfo::Slot(slot), _) |
+            (_, RoutingInfo::Slot(slot)) => Ok(RoutingInfo::Slot(slot)),
+            (RoutingInfo::AllMasters, _) |
+            (_, RoutingInfo::AllMasters) => Ok(RoutingInfo::AllMasters),
+            _ => Ok(RoutingInfo::Random),
+        }
+    }
+}
+
+/// A single command out of packed commands.
+struct PackedCommand<'a> {
+    bytes: &'a [u8],
+    routing: RoutingInfo,
+    readonly: bool,
+}
+
+/// Splits packed commands into the single commands.
+fn split_packed_commands(cmd: &[u8]) -> RedisResult<Vec<PackedCommand>> {
+    let mut rv = vec![];
+    let mut remaining = cmd;
+    while !remaining.is_empty() {
+        let before = remaining;
+        let (routing, readonly) = match try!(Parser::new(&mut remaining).parse_value()) {
+            Value::Bulk(ref args) => {
+                let readonly = get_arg(args, 0).map_or(false, |name| {
+                    is_readonly_command(&String::from_utf8_lossy(name).to_uppercase())
+                });
+                (try!(RoutingInfo::for_command(args)), readonly)
+            }
+            _ => fail!((ErrorKind::TypeError, "Invalid packed command")),
+        };
+        rv.push(PackedCommand {
+            bytes: &before[..before.len() - remaining.len()],
+            routing: routing,
+            readonly: readonly,
+        });
+    }
+    Ok(rv)
+}
+
+/// Figures out where packed commands that have to go to a single node
+/// are sent to and whether they only read data.
+fn combined_routing(commands: &[PackedCommand]) -> RedisResult<(RoutingInfo, bool)> {
+    let mut rv = RoutingInfo::Random;
+    for command in commands {
+        rv = try!(rv.combine(command.routing));
+    }
+    Ok((rv, commands.iter().all(|command| command.readonly)))
+}
+
+fn is_redirect(err: &RedisError) -> bool {
+    match err.extension_error_code() {
+        Some("MOVED") | Some("ASK") => true,
+        _ => false,
+    }
+}
+
+
+/// A node as listed by `CLUSTER NODES`.
+///
+/// ```rust,no_run
+/// # let client = redis::Client::open("redis://127.0.0.1:7000/").unwrap();
+/// # let con = client.get_connection().unwrap();
+/// use redis::Commands;
+///
+/// let nodes: Vec<redis::ClusterNode> = con.cluster_nodes().unwrap();
+/// for node in nodes.iter().filter(|node| node.is_master()) {
+///     println!("{} serves {:?}", node.addr, node.slots);
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClusterNode {
+    /// The id of the node.
+    pub id: String,
+    /// The address clients connect to as `ip:port`.
+    pub addr: String,
+    /// The port of the cluster bus if it is reported.
+    pub bus_port: Option<u16>,
+    /// The announced hostname of the node if there is one.
+    pub hostname: Option<String>,
+    /// Flags like `myself`, `master`, `slave` or `fail`.
+    pub flags: Vec<String>,
+    /// The id of the master if the node is a replica.
+    pub master_id: Option<String>,
+    /// Milliseconds since the epoch when the last ping was sent, zero
+    /// if there is no pending ping.
+    pub ping_sent: u64,
+    /// Milliseconds since the epoch when the last pong was received.
+    pub pong_received: u64,
+    /// The configuration epoch of the node.
+    pub config_epoch: u64,
+    /// The state of the link to the node, `connected` or `disconnected`.
+    pub link_state: String,
+    /// The slot ranges the node serves, both ends inclusive.
+    pub slots: Vec<(u16, u16)>,
+}
+
+impl ClusterNode {
+    /// Parses a single line of the reply of `CLUSTER NODES`.
+    pub fn parse(line: &str) -> RedisResult<ClusterNode> {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 8 {
+            fail!((ErrorKind::TypeError, "Invalid CLUSTER NODES line", line.to_string()));
+        }
+        // the address looks like "ip:port@cport[,hostname]"
+        let mut addr_pieces = fields[1].splitn(2, ',');
+        let addr = addr_pieces.next().unwrap_or("");
+        let hostname = addr_pieces.next()
+            .filter(|host| !host.is_empty())
+            .map(|host| host.to_string());
+        let mut addr_pieces = addr.splitn(2, '@');
+        let addr = addr_pieces.next().unwrap_or("").to_string();
+        let bus_port = addr_pieces.next().and_then(|port| port.parse().ok());
+
+        let mut slots = vec![];
+        for slot in &fields[8..] {
+            // slots that are being imported or migrated look like
+            // "[slot->-id]" and are skipped
+            if slot.starts_with('[') {
+                continue;
+            }
+            let mut bounds = slot.splitn(2, '-');
+            let start = try!(parse_field(bounds.next().unwrap_or(""), line));
+            let end = match bounds.next() {
+                Some(end) => try!(parse_field(end, line)),
+                None => start,
+            };
+            slots.push((start, end));
+        }
+
+        Ok(ClusterNode {
+            id: fields[0].to_string(),
+            addr: addr,
+            bus_port: bus_port,
+            hostname: hostname,
+            flags: fields[2].split(',').map(|flag| flag.to_string()).collect(),
+            master_id: if fields[3] == "-" { None } else { Some(fields[3].to_string()) },
+            ping_sent: try!(parse_field(fields[4], line)),
+            pong_received: try!(parse_field(fields[5], line)),
+            config_epoch: try!(parse_field(fields[6], line)),
+            link_state: fields[7].to_string(),
+            slots: slots,
+        })
+    }
+
+    /// Checks if the node has the given flag.
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.flags.iter().any(|f| f == flag)
+    }
+
+    /// Checks if the node is a master.
+    pub fn is_master(&self) -> bool {
+        self.has_flag("master")
+    }
+
+    /// Checks if this is the node that answered the command.
+    pub fn is_myself(&self) -> bool {
+        self.has_flag("myself")
+    }
+}
+
+fn parse_field<T: ::std::str::FromStr>(field: &str, line: &str) -> RedisResult<T> {
+    match field.parse() {
+        Ok(rv) => Ok(rv),
+        Err(_) => fail!((ErrorKind::TypeError, "Invalid CLUSTER NODES line", line.to_string())),
+    }
+}
+
+fn parse_nodes(text: &str) -> RedisResult<Vec<ClusterNode>> {
+    text.lines().filter(|line| !line.trim().is_empty()).map(ClusterNode::parse).collect()
+}
+
+impl FromRedisValue for ClusterNode {
+    fn from_redis_value(v: &Value) -> RedisResult<ClusterNode> {
+        let line: String = try!(from_redis_value(v));
+        ClusterNode::parse(&line)
+    }
+
+    fn from_byte_vec(vec: &[u8]) -> Option<Vec<ClusterNode>> {
+        parse_nodes(&String::from_utf8_lossy(vec)).ok()
+    }
+}
+
+
+/// A node serving a slot range as reported by `CLUSTER SLOTS`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClusterSlotNode {
+    /// The preferred endpoint of the node.  It is empty if the node
+    /// does not know its address, clients then use the address of the
+    /// node they asked.
+    pub host: String,
+    /// The port of the node.
+    pub port: u16,
+    /// The id of the node.  Old servers do not report it.
+    pub id: Option<String>,
+}
+
+impl FromRedisValue for ClusterSlotNode {
+    fn from_redis_value(v: &Value) -> RedisResult<ClusterSlotNode> {
+        let items: Vec<Value> = try!(from_redis_value(v));
+        if items.len() < 2 {
+            fail!((ErrorKind::TypeError, "Invalid CLUSTER SLOTS node"));
+        }
+        Ok(ClusterSlotNode {
+            host: try!(from_redis_value(&items[0])),
+            port: try!(from_redis_value(&items[1])),
+            id: match items.get(2) {
+                Some(id) => Some(try!(from_redis_value(id))),
+                None => None,
+            },
+        })
+    }
+}
+
+/// A slot range and the nodes serving it as reported by `CLUSTER SLOTS`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClusterSlot {
+    /// The first slot of the range.
+    pub start: u16,
+    /// The last slot of the range.
+    pub end: u16,
+    /// The master serving the range.
+    pub master: ClusterSlotNode,
+    /// The replicas of the master.
+    pub replicas: Vec<ClusterSlotNode>,
+}
+
+impl FromRedisValue for ClusterSlot {
+    fn from_redis_value(v: &Value) -> RedisResult<ClusterSlot> {
+        let items: Vec<Value> = try!(from_redis_value(v));
+        if items.len() < 3 {
+            fail!((ErrorKind::TypeError, "Invalid CLUSTER SLOTS reply"));
+        }
+        let mut replicas = vec![];
+        for replica in &items[3..] {
+            replicas.push(try!(from_redis_value(replica)));
+        }
+        Ok(ClusterSlot {
+            start: try!(from_redis_value(&items[0])),
+            end: try!(from_redis_value(&items[1])),
+            master: try!(from_redis_value(&items[2])),
+            replicas: replicas,
+        })
+    }
+}
+
+
+/// A node of a shard as reported by `CLUSTER SHARDS`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClusterShardNode {
+    /// The id of the node.
+    pub id: String,
+    /// The preferred endpoint clients connect to.
+    pub endpoint: String,
+    /// The IP address of the node.
+    pub ip: String,
+    /// The announced hostname of the node if there is one.
+    pub hostname: Option<String>,
+    /// The port for plain connections if there is one.
+    pub port: Option<u16>,
+    /// The port for TLS connections if there is one.
+    pub tls_port: Option<u16>,
+    /// Either `master` or `replica`.
+    pub role: String,
+    /// The replication offset of the node.
+    pub replication_offset: i64,
+    /// Either `online`, `failed` or `loading`.
+    pub health: String,
+}
+
+impl FromRedisValue for ClusterShardNode {
+    fn from_redis_value(v: &Value) -> RedisResult<ClusterShardNode> {
+        let map: HashMap<String, Value> = try!(from_redis_value(v));
+        Ok(ClusterShardNode {
+            id: try!(get_field(&map, "id")),
+            endpoint: try!(get_field(&map, "endpoint")),
+            ip: try!(get_field(&map, "ip")),
+            hostname: try!(get_field(&map, "hostname")),
+            port: try!(get_field(&map, "port")),
+            tls_port: try!(get_field(&map, "tls-port")),
+            role: try!(get_field(&map, "role")),
+            replication_offset: try!(get_field(&map, "replication-offset")),
+            health: try!(get_field(&map, "health")),
+        })
+    }
+}
+
+/// A master and its replicas as reported by `CLUSTER SHARDS`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClusterShard {
+    /// The slot ranges of the shard, both ends inclusive.
+    pub slots: Vec<(u16, u16)>,
+    /// The nodes of the shard.
+    pub nodes: Vec<ClusterShardNode>,
+}
+
+impl FromRedisValue for ClusterShard {
+    fn from_redis_value(v: &Value) -> RedisResult<ClusterShard> {
+        let map: HashMap<String, Value> = try!(from_redis_value(v));
+        // the slots are a flat list of range bounds
+        let bounds: Vec<u16> = try!(get_field(&map, "slots"));
+        Ok(ClusterShard {
+            slots: bounds.chunks(2)
+                .filter(|pair| pair.len() == 2)
+                .map(|pair| (pair[0], pair[1]))
+                .collect(),
+            nodes: try!(get_field(&map, "nodes")),
+        })
+    }
+}
+
+/// Reads a field of a map reply.  Missing fields are treated like nil.
+fn get_field<T: FromRedisValue>(map: &HashMap<String, Value>, key: &str) -> RedisResult<T> {
+    from_redis_value(map.get(key).unwrap_or(&Value::Nil))
+}
+
+
+fn info_field<T: FromRedisValue>(info: &InfoDict, key: &str) -> RedisResult<T> {
+    match info.get(key) {
+        Some(value) => Ok(value),
+        None => fail!((ErrorKind::TypeError, "Invalid CLUSTER INFO reply", key.to_string())),
+    }
+}
+
+/// The state of the cluster as reported by `CLUSTER INFO`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClusterInfo {
+    /// Whether the cluster can serve queries.
+    pub state_ok: bool,
+    /// The number of slots that are assigned to a node.
+    pub slots_assigned: usize,
+    /// The number of slots whose node is reachable.
+    pub slots_ok: usize,
+    /// The number of slots whose node is possibly failing.
+    pub slots_pfail: usize,
+    /// The number of slots whose node is failing.
+    pub slots_fail: usize,
+    /// The number of nodes in the cluster.
+    pub known_nodes: usize,
+    /// The number of masters serving at least one slot.
+    pub size: usize,
+    /// The highest configuration epoch in the cluster.
+    pub current_epoch: u64,
+    /// The configuration epoch of the node that answered.
+    pub my_epoch: u64,
+}
+
+impl FromRedisValue for ClusterInfo {
+    fn from_redis_value(v: &Value) -> RedisResult<ClusterInfo> {
+        let info: InfoDict = try!(from_redis_value(v));
+        let state: String = try!(info_field(&info, "cluster_state"));
+        Ok(ClusterInfo {
+            state_ok: state == "ok",
+            slots_assigned: try!(info_field(&info, "cluster_slots_assigned")),
+            slots_ok: try!(info_field(&info, "cluster_slots_ok")),
+            slots_pfail: try!(info_field(&info, "cluster_slots_pfail")),
+            slots_fail: try!(info_field(&info, "cluster_slots_fail")),
+            known_nodes: try!(info_field(&info, "cluster_known_nodes")),
+            size: try!(info_field(&info, "cluster_size")),
+            current_epoch: try!(info_field(&info, "cluster_current_epoch")),
+            my_epoch: try!(info_field(&info, "cluster_my_epoch")),
+        })
+    }
+}
+
+
+/// The nodes serving a slot range.
+#[derive(Debug, Clone)]
+struct SlotAddrs {
+    master: String,
+    replicas: Vec<String>,
+}
+
+/// Maps the last slot of every range to the nodes serving it.
+type SlotMap = BTreeMap<u16, SlotAddrs>;
+
+/// Asks the nodes in order for the slot layout and returns the first
+/// answer.
+fn fetch_slots(nodes: &[ConnectionInfo]) -> RedisResult<SlotMap> {
+    let mut last_err = None;
+    for info in nodes {
+        let reply = connect(info)
+            .and_then(|con| cmd("CLUSTER").arg("SLOTS").query::<Vec<ClusterSlot>>(&con));
+        match reply {
+            Ok(slots) => {
+                // nodes without an address are reachable under the host
+                // of the node that was asked
+                let name = |node: ClusterSlotNode| if node.host.is_empty() {
+                    format!("{}:{}", host_of(info), node.port)
+                } else {
+                    format!("{}:{}", node.host, node.port)
+                };
+                let mut map = BTreeMap::new();
+                for slot in slots {
+                    map.insert(slot.end,
+                               SlotAddrs {
+                                   master: name(slot.master),
+                                   replicas: slot.replicas.into_iter().map(&name).collect(),
+                               });
+                }
+                return Ok(map);
+            }
+            Err(err) => last_err = Some(err),
+        }
+    }
+    Err(last_err.unwrap())
+}
+
+/// Returns the time until the slot layout may be fetched again.
+fn refresh_interval() -> Duration {
+    let random = RandomState::new().build_hasher().finish();
+    Duration::from_millis(SLOT_REFRESH_INTERVAL_MS + random % (SLOT_REFRESH_INTERVAL_MS + 1))
+}
+
+/// Builds the connection info for a node given as `host:port` from the
+/// settings of `template`.
+fn node_connection_info(template: &ConnectionInfo, name: &str) -> Option<ConnectionInfo> {
+    let idx = match name.rfind(':') {
+        Some(idx) => idx,
+        None => return None,
+    };
+    let port = match name[idx + 1..].parse() {
+        Ok(port) => port,
+        Err(_) => return None,
+    };
+    let host = name[..idx].to_string();
+    let mut info = template.clone();
+    info.fallback_addrs = vec![];
+    info.addr = Box::new(match *info.addr {
+        ConnectionAddr::TcpTls { insecure, .. } => {
+            ConnectionAddr::TcpTls {
+                host: host,
+                port: port,
+                insecure: insecure,
+            }
+        }
+        _ => ConnectionAddr::Tcp(host, port),
+    });
+    Some(info)
+}
+
+fn host_of(info: &ConnectionInfo) -> &str {
+    match *info.addr {
+        ConnectionAddr::Tcp(ref host, _) |
+        ConnectionAddr::TcpTls { ref host, .. } => host,
+        ConnectionAddr::Unix(_) => "",
+    }
+}
+
+fn node_name(info: &ConnectionInfo) -> String {
+    match *info.addr {
+        ConnectionAddr::Tcp(ref host, port) |
+        ConnectionAddr::TcpTls { ref host, port, .. } => format!("{}:{}", host, port),
+        ConnectionAddr::Unix(ref path) => path.display().to_string(),
+    }
+}
+
+
+/// Decides which node of a slot answers read-only commands like `GET`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReadFromReplica {
+    /// All commands are sent to the master.  This is the default.
+    MasterOnly,
+    /// Read-only commands are spread over the replicas of the slot.
+    RoundRobin,
+    /// Read-only commands go to the node of the slot, master included,
+    /// that was the fastest to connect to.
+    Nearest,
+}
+
+/// Decides how often and how fast commands are retried when the cluster
+/// is briefly unable to serve them.  This covers `TRYAGAIN` and
+/// `CLUSTERDOWN` errors as well as nodes that refuse connections, which
+/// are typical during a failover or resharding.  A command that was
+/// written to a node is never sent again after a connection error, as the
+/// node may have run it before the connection broke.
+///
+/// The delay doubles with every retry, starting at `min_delay` and
+/// capped at `max_delay`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// How often a command is retried before the error is returned.
+    pub max_retries: u32,
+    /// The delay before the first retry.
+    pub min_delay: Duration,
+    /// The longest delay between two retries.
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// A policy that returns errors right away.
+    pub fn none() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 0,
+            min_delay: Duration::from_millis(0),
+            max_delay: Duration::from_millis(0),
+        }
+    }
+
+    /// Returns the delay before the given retry, counting from zero.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = 1u32.checked_shl(retry).unwrap_or(u32::max_value());
+        match self.min_delay.checked_mul(factor) {
+            Some(delay) if delay < self.max_delay => delay,
+            _ => self.max_delay,
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    /// Retries up to five times, waiting between 50 milliseconds and one
+    /// second.
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 5,
+            min_delay: Duration::from_millis(50),
+            max_delay: Duration::from_secs(1),
+        }
+    }
+}
+
+fn is_transient(err: &RedisError) -> bool {
+    match err.extension_error_code() {
+        Some("TRYAGAIN") | Some("CLUSTERDOWN") => true,
+        _ => err.is_connection_refusal(),
+    }
+}
+
+/// A client for a redis cluster.
+///
+/// The client is given a few nodes of the cluster.  Connections ask them
+/// for the slot layout of the cluster and send commands directly to the
+/// node that serves the keys of a command.
+///
+/// Example usage:
+///
+/// ```rust,no_run
+/// use redis::Commands;
+///
+/// let client = redis::ClusterClient::open(vec!["redis://127.0.0.1:7000/",
+///                                              "redis://127.0.0.1:7001/"]).unwrap();
+/// let con = client.get_connection().unwrap();
+/// let _: () = con.set("foo", 42).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct ClusterClient {
+    initial_nodes: Vec<ConnectionInfo>,
+    read_from_replica: ReadFromReplica,
+    split_multi_key_commands: bool,
+    retry_policy: RetryPolicy,
+}
+
+impl ClusterClient {
+    /// Creates a client for the cluster the given nodes belong to.  This
+    /// does not connect yet.  The settings of the first node, like the
+    /// password, are used for connections to all nodes.
+    pub fn open<T: IntoConnectionInfo>(initial_nodes: Vec<T>) -> RedisResult<ClusterClient> {
+        let mut nodes = vec![];
+        for node in initial_nodes {
+            let info = try!(node.into_connection_info());
+            if let ConnectionAddr::Unix(_) = *info.addr {
+                fail!((ErrorKind::InvalidClientConfig,
+                       "Cluster nodes need to be reachable over TCP"));
+            }
+            if info.db != 0 {
+                fail!((ErrorKind::InvalidClientConfig,
+                       "Redis cluster only supports database 0"));
+            }
+            nodes.push(info);
+        }
+        if nodes.is_empty() {
+            fail!((ErrorKind::InvalidClientConfig, "At least one cluster node is required"));
+        }
+        Ok(ClusterClient {
+            initial_nodes: nodes,
+            read_from_replica: ReadFromReplica::MasterOnly,
+            split_multi_key_commands: false,
+            retry_policy: RetryPolicy::default(),
+        })
+    }
+
+    /// Sets where read-only commands are sent to.  Connections to
+    /// replicas are switched to `READONLY` mode.  If a replica fails to
+    /// answer the command is sent to the master instead.
+    ///
+    /// Note that replicas can lag behind the master.
+    pub fn set_read_from_replica(&mut self, policy: ReadFromReplica) {
+        self.read_from_replica = policy;
+    }
+
+    /// Enables splitting up `MGET`, `MSET`, `DEL`, `UNLINK`, `EXISTS`
+    /// and `TOUCH` with keys in different slots.  One command per slot is
+    /// sent and the replies are merged in the order of the keys.  This is
+    /// disabled by default as the command is no longer atomic: some of the
+    /// sub-commands can fail while others succeed.
+    pub fn set_split_multi_key_commands(&mut self, enabled: bool) {
+        self.split_multi_key_commands = enabled;
+    }
+
+    /// Sets how commands are retried on transient cluster errors.  The
+    /// default is `RetryPolicy::default()`.
+    pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        self.retry_policy = policy;
+    }
+
+    /// Connects to the cluster and fetches its slot layout.
+    pub fn get_connection(&self) -> RedisResult<ClusterConnection> {
+        let con = ClusterConnection {
+            initial_nodes: self.initial_nodes.clone(),
+            connections: RefCell::new(HashMap::new()),
+            slots: Arc::new(Mutex::new(BTreeMap::new())),
+            refreshing: Arc::new(AtomicBool::new(false)),
+            next_refresh: Cell::new(Instant::now()),
+            read_from_replica: self.read_from_replica,
+            next_replica: Cell::new(0),
+            latencies: RefCell::new(HashMap::new()),
+            split_multi_key_commands: self.split_multi_key_commands,
+            retry_policy: self.retry_policy,
+        };
+        try!(con.refresh_slots());
+        Ok(con)
+    }
+}
+
+
+/// Where a redirected command has to be sent to next.
+enum Redirect {
+    Moved(String),
+    Ask(String),
+}
+
+/// A connection to a redis cluster.
+///
+/// Commands are routed by the slot of their key.  Commands without a key
+/// go to any node, `FLUSHALL`, `FLUSHDB` and `SCRIPT` go to all masters.
+/// `MOVED` and `ASK` redirections are followed.  A `MOVED` reply updates
+/// the slot right away and makes the connection fetch the whole slot
+/// layout again in the background.  If a node cannot be reached the
+/// layout is fetched before the command is retried.  Fetches are rate
+/// limited.
+///
+/// Pipelines are split up by node and the results are returned in the
+/// original order.  Transactions and commands with several keys need all
+/// keys in one slot, otherwise they fail with a `CrossSlot` error.  See
+/// `ClusterClient::set_split_multi_key_commands` for an exception.
+///
+/// Transient errors are retried according to the `RetryPolicy` of the
+/// client.
+pub struct ClusterConnection {
+    initial_nodes: Vec<ConnectionInfo>,
+    connections: RefCell<HashMap<String, Connection>>,
+    slots: Arc<Mutex<SlotMap>>,
+    refreshing: Arc<AtomicBool>,
+    next_refresh: Cell<Instant>,
+    read_from_replica: ReadFromReplica,
+    next_replica: Cell<usize>,
+    latencies: RefCell<HashMap<String, Duration>>,
+    split_multi_key_commands: bool,
+    retry_policy: RetryPolicy,
+}
+
+impl ClusterConnection {
+    /// Fetches the slot layout from the first node that answers and
+    /// replaces the routing table with it.
+    pub fn refresh_slots(&self) -> RedisResult<()> {
+        self.next_refresh.set(Instant::now() + refresh_interval());
+        let slots = try!(fetch_slots(&self.refresh_candidates()));
+        *self.slots.lock().unwrap() = slots;
+        Ok(())
+    }
+
+    /// Opens a pubsub connection to the master serving the slot of the
+    /// given sharded channel and subscribes to it with `SSUBSCRIBE`.
+    /// More channels can be subscribed to on the returned connection as
+    /// long as they belong to the same slot.
+    pub fn get_sharded_pubsub<T: ToRedisArgs>(&self, channel: T) -> RedisResult<PubSub> {
+        let mut chan = vec![];
+        for item in channel.to_redis_args() {
+            chan.extend(item);
+        }
+        let name = match self.slot_node(key_slot(&chan), false) {
+            Some((name, _)) => name,
+            None => try!(self.any_node()),
+        };
+        let info = match self.node_info(&name) {
+            Some(info) => info,
+            None => {
+                fail!((ErrorKind::ResponseError, "Invalid cluster node address", name.to_string()))
+            }
+        };
+        let mut pubsub = try!(connect_pubsub(&info));
+        try!(pubsub.ssubscribe(chan));
+        Ok(pubsub)
+    }
+
+    /// Fetches the slot layout in a background thread unless a fetch is
+    /// already pending.  If the last fetch was too recent the thread
+    /// waits until another one is allowed.
+    fn schedule_refresh(&self) {
+        if self.refreshing.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let now = Instant::now();
+        let delay = self.next_refresh.get().saturating_duration_since(now);
+        self.next_refresh.set(now + delay + refresh_interval());
+        let candidates = self.refresh_candidates();
+        let slots = self.slots.clone();
+        let refreshing = self.refreshing.clone();
+        thread::spawn(move || {
+            thread::sleep(delay);
+            if let Ok(map) = fetch_slots(&candidates) {
+                *slots.lock().unwrap() = map;
+            }
+            refreshing.store(false, Ordering::SeqCst);
+        });
+    }
+
+    /// The initial nodes and all nodes that are connected are asked for
+    /// the slot layout.
+    fn refresh_candidates(&self) -> Vec<ConnectionInfo> {
+        let mut rv = self.initial_nodes.clone();
+        for name in self.connections.borrow().keys() {
+            if let Some(info) = self.node_info(name) {
+                rv.push(info);
+            }
+        }
+        rv
+    }
+
+    /// Points a single slot to a new node, splitting up the range that
+    /// contained it.
+    fn set_slot_node(&self, slot: u16, name: String) {
+        let mut slots = self.slots.lock().unwrap();
+        let (end, old) = match slots.range(slot..).next() {
+            Some((&end, old)) => (end, old.clone()),
+            None => return,
+        };
+        let new = SlotAddrs {
+            master: name,
+            replicas: vec![],
+        };
+        let start = slots.range(..slot).next_back().map_or(0, |(&end, _)| end + 1);
+        if start < slot {
+            slots.insert(slot - 1, old.clone());
+        }
+        if end > slot {
+            slots.insert(end, old);
+        }
+        slots.insert(slot, new);
+    }
+
+    /// Builds the connection info for a node given as `host:port`.
+    fn node_info(&self, name: &str) -> Option<ConnectionInfo> {
+        node_connection_info(&self.initial_nodes[0], name)
+    }
+
+    fn slot_addrs(&self, slot: u16) -> Option<SlotAddrs> {
+        self.slots.lock().unwrap().range(slot..).next().map(|(_, addrs)| addrs.clone())
+    }
+
+    /// Returns the node a command for the slot is sent to and whether it
+    /// is a replica.
+    fn slot_node(&self, slot: u16, readonly: bool) -> Option<(String, bool)> {
+        let addrs = match self.slot_addrs(slot) {
+            Some(addrs) => addrs,
+            None => return None,
+        };
+        if readonly {
+            if let Some(replica) = self.pick_replica(&addrs) {
+                return Some((replica, true));
+            }
+        }
+        Some((addrs.master, false))
+    }
+
+    /// Picks the replica that answers a read-only command according to
+    /// the policy.  `None` means the master answers it.
+    fn pick_replica(&self, addrs: &SlotAddrs) -> Option<String> {
+        match self.read_from_replica {
+            ReadFromReplica::MasterOnly => None,
+            ReadFromReplica::RoundRobin => {
+                if addrs.replicas.is_empty() {
+                    return None;
+                }
+                let idx = self.next_replica.get();
+                self.next_replica.set(idx.wrapping_add(1));
+                Some(addrs.replicas[idx % addrs.replicas.len()].clone())
+            }
+            ReadFromReplica::Nearest => {
+                let mut nearest = None;
+                for name in Some(&addrs.master).into_iter().chain(addrs.replicas.iter()) {
+                    if self.connect_node(name).is_err() {
+                        continue;
+                    }
+                    let latency = self.latencies.borrow()[name];
+                    match nearest {
+                        Some((_, best)) if best <= latency => {}
+                        _ => nearest = Some((name, latency)),
+                    }
+                }
+                match nearest {
+                    Some((name, _)) if *name != addrs.master => Some(name.clone()),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    fn is_replica(&self, name: &str) -> bool {
+        self.slots.lock().unwrap().values().any(|addrs| addrs.replicas.iter().any(|r| r == name))
+    }
+
+    fn any_node(&self) -> RedisResult<String> {
+        if let Some(name) = self.connections.borrow().keys().next() {
+            return Ok(name.clone());
+        }
+        match self.slots.lock().unwrap().values().next() {
+            Some(addrs) => Ok(addrs.master.clone()),
+            None => Ok(node_name(&self.initial_nodes[0])),
+        }
+    }
+
+    fn master_nodes(&self) -> Vec<String> {
+        let mut rv: Vec<String> =
+            self.slots.lock().unwrap().values().map(|addrs| addrs.master.clone()).collect();
+        rv.sort();
+        rv.dedup();
+        rv
+    }
+
+    /// Connects to the given node unless there is a connection already.
+    /// Connections to replicas are switched to `READONLY` mode.
+    fn connect_node(&self, name: &str) -> RedisResult<()> {
+        if self.connections.borrow().contains_key(name) {
+            return Ok(());
+        }
+        let info = match self.node_info(name) {
+            Some(info) => info,
+            None => {
+                fail!((ErrorKind::ResponseError,
+                       "Invalid cluster node address",
+                       name.to_string()))
+            }
+        };
+        let started = Instant::now();
+        let con = try!(connect(&info));
+        self.latencies.borrow_mut().insert(name.to_string(), started.elapsed());
+        if self.is_replica(name) {
+            try!(cmd("READONLY").query::<()>(&con));
+        }
+        self.connections.borrow_mut().insert(name.to_string(), con);
+        Ok(())
+    }
+
+    /// Runs `func` with the connection to the given node, connecting
+    /// first if needed.
+    fn with_node<T, F>(&self, name: &str, func: F) -> RedisResult<T>
+        where F: FnOnce(&Connection) -> RedisResult<T>
+    {
+        try!(self.connect_node(name));
+        let connections = self.connections.borrow();
+        func(&connections[name])
+    }
+
+    fn execute_on_all_masters<T, F>(&self, func: F) -> RedisResult<T>
+        where F: Fn(&Connection) -> RedisResult<T>
+    {
+        let mut rv = None;
+        for name in self.master_nodes() {
+            rv = Some(try!(self.with_node(&name, &func)));
+        }
+        match rv {
+            Some(rv) => Ok(rv),
+            None => fail!((ErrorKind::ResponseError, "Cluster has no masters")),
+        }
+    }
+
+    /// Sends the packed command to the node of the routing and reads the
+    /// reply with `read`.  The command is only sent again if the node
+    /// rejected it or if it never left the client.  Once it was written
+    /// the node may have run it, so errors while reading are returned.
+    fn request<T, F>(&self,
+                     routing: RoutingInfo,
+                     readonly: bool,
+                     packed: &[u8],
+                     read: F)
+                     -> RedisResult<T>
+        where F: Fn(&Connection) -> RedisResult<T>
+    {
+        let slot = match routing {
+            RoutingInfo::AllMasters => {
+                return self.execute_on_all_masters(|con| {
+                    try!(con.send_packed_command(packed));
+                    read(con)
+                });
+            }
+            RoutingInfo::Random => None,
+            RoutingInfo::Slot(slot) => Some(slot),
+        };
+        let mut redirect = None;
+        let mut readonly = readonly;
+        let mut attempts = 0;
+        let mut retries = 0;
+        loop {
+            attempts += 1;
+            let (name, asking, replica) = match redirect.take() {
+                Some(Redirect::Moved(name)) => (name, false, false),
+                Some(Redirect::Ask(name)) => (name, true, false),
+                None => {
+                    match slot.and_then(|slot| self.slot_node(slot, readonly)) {
+                        Some((name, replica)) => (name, false, replica),
+                        None => (try!(self.any_node()), false, false),
+                    }
+                }
+            };
+            let mut sent = false;
+            let result = self.with_node(&name, |con| {
+                if asking {
+                    try!(cmd("ASKING").query::<()>(con));
+                }
+                try!(con.send_packed_command(packed));
+                sent = true;
+                read(con)
+            });
+            let err = match result {
+                Ok(rv) => return Ok(rv),
+                Err(err) => err,
+            };
+            if attempts >= MAX_REDIRECTIONS {
+                return Err(err);
+            }
+            if replica &&
+               (err.is_io_error() || is_redirect(&err) ||
+                err.kind() == ErrorKind::BusyLoadingError) {
+                // let the master answer instead, read-only commands can
+                // safely run twice
+                if err.is_io_error() {
+                    self.connections.borrow_mut().remove(&name);
+                }
+                readonly = false;
+                continue;
+            }
+            let unsent = !sent && err.is_io_error();
+            if (is_transient(&err) || unsent) && retries < self.retry_policy.max_retries {
+                thread::sleep(self.retry_policy.delay(retries));
+                retries += 1;
+                if err.is_io_error() {
+                    // the node may have been replaced in the meantime
+                    self.connections.borrow_mut().remove(&name);
+                    let _ = self.refresh_slots();
+                }
+                continue;
+            }
+            match err.extension_error_code() {
+                Some("MOVED") => {
+                    if let Some((slot, name)) = redirect_target(&err) {
+                        self.set_slot_node(slot, name.clone());
+                        redirect = Some(Redirect::Moved(name));
+                    }
+                    self.schedule_refresh();
+                    continue;
+                }
+                Some("ASK") => {
+                    redirect = redirect_target(&err).map(|(_, name)| Redirect::Ask(name));
+                    continue;
+                }
+                _ => {}
+            }
+            if err.is_io_error() {
+                // the node is gone, forget the connection and find out who
+                // took over its slots.  The command is not sent again as
+                // the node may have run it already.
+                self.connections.borrow_mut().remove(&name);
+                self.schedule_refresh();
+            }
+            return Err(err);
+        }
+    }
+}
+
+impl ClusterConnection {
+    /// Runs a pipeline by sending the commands of every node as one
+    /// sub-pipeline.  All sub-pipelines are written before any reply is
+    /// read so that the nodes work on them at the same time.  Commands
+    /// that were redirected or could not be sent are retried one by one.
+    /// A reply that got lost is returned as an error as the node may have
+    /// run the command.
+    fn execute_pipeline(&self, commands: &[PackedCommand]) -> RedisResult<Vec<Value>> {
+        let mut nodes: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        for (idx, command) in commands.iter().enumerate() {
+            let targets = match command.routing {
+                RoutingInfo::AllMasters => self.master_nodes(),
+                RoutingInfo::Slot(slot) => {
+                    match self.slot_node(slot, command.readonly) {
+                        Some((name, _)) => vec![name],
+                        None => vec![try!(self.any_node())],
+                    }
+                }
+                RoutingInfo::Random => vec![try!(self.any_node())],
+            };
+            for name in targets {
+                nodes.entry(name).or_insert_with(Vec::new).push(idx);
+            }
+        }
+
+        let mut sent = vec![];
+        for (name, indexes) in nodes {
+            let mut packed = vec![];
+            for &idx in &indexes {
+                packed.extend_from_slice(commands[idx].bytes);
+            }
+            if self.with_node(&name, |con| con.send_packed_command(&packed)).is_ok() {
+                sent.push((name, indexes));
+            } else {
+                self.connections.borrow_mut().remove(&name);
+            }
+        }
+
+        let mut results: Vec<Option<RedisResult<Value>>> = commands.iter().map(|_| None).collect();
+        for (name, indexes) in sent {
+            let mut lost = false;
+            for idx in indexes {
+                let result = if lost {
+                    Err(RedisError::from((ErrorKind::IoError,
+                                          "Connection lost before the reply arrived")))
+                } else {
+                    self.with_node(&name, |con| con.recv_response())
+                };
+                if let Err(ref err) = result {
+                    if err.is_io_error() && !lost {
+                        // the remaining replies of the node are lost
+                        self.connections.borrow_mut().remove(&name);
+                        lost = true;
+                    }
+                }
+                // a command sent to all masters reports the first error
+                if let Some(Err(_)) = results[idx] {
+                    continue;
+                }
+                results[idx] = Some(result);
+            }
+        }
+
+        let mut rv = vec![];
+        for (result, command) in results.into_iter().zip(commands) {
+            let retry = || {
+                self.request(command.routing,
+                             command.readonly,
+                             command.bytes,
+                             |con| con.recv_response())
+            };
+            rv.push(try!(match result {
+                Some(Err(ref err)) if is_redirect(err) || is_transient(err) => retry(),
+                // the node could not be reached when sending
+                None => retry(),
+                Some(result) => result,
+            }));
+        }
+        Ok(rv)
+    }
+}
+
+impl ClusterConnection {
+    /// Runs a multi-key command with keys in different slots as one
+    /// command per slot and merges the replies.
+    fn execute_split(&self, cmd: &[u8]) -> RedisResult<Value> {
+        let args: Vec<Vec<u8>> = try!(from_redis_value(&try!(parse_redis_value(cmd))));
+        let name = String::from_utf8_lossy(&args[0]).to_uppercase();
+        let step = match &*name {
+            "MSET" => 2,
+            "MGET" | "DEL" | "UNLINK" | "EXISTS" | "TOUCH" => 1,
+            _ => {
+                fail!((ErrorKind::CrossSlot,
+                       "Keys in request don't hash to the same slot",
+                       format!("{} cannot be split up", name)))
+            }
+        };
+        if (args.len() - 1) % step != 0 {
+            fail!((ErrorKind::ResponseError,
+                   "Wrong number of arguments",
+                   format!("{} expects key value pairs", name)));
+        }
+
+        // the positions of the keys of every slot in order of appearance
+        let mut slots: Vec<(u16, Vec<usize>)> = vec![];
+        for pos in (1..args.len()).step_by(step) {
+            let slot = key_slot(&args[pos]);
+            match slots.iter().position(|&(s, _)| s == slot) {
+                Some(idx) => slots[idx].1.push(pos),
+                None => slots.push((slot, vec![pos])),
+            }
+        }
+        let packed: Vec<Vec<u8>> = slots.iter()
+            .map(|&(_, ref positions)| {
+                let mut sub_args = vec![args[0].clone()];
+                for &pos in positions {
+                    sub_args.extend(args[pos..pos + step].iter().cloned());
+                }
+                pack_command(&sub_args)
+            })
+            .collect();
+        let commands: Vec<PackedCommand> = slots.iter()
+            .zip(packed.iter())
+            .map(|(&(slot, _), bytes)| {
+                PackedCommand {
+                    bytes: bytes,
+                    routing: RoutingInfo::Slot(slot),
+                    readonly: is_readonly_command(&name),
+                }
+            })
+            .collect();
+        let replies = try!(self.execute_pipeline(&commands));
+
+        match &*name {
+            "MGET" => {
+                let mut values = vec![Value::Nil; args.len() - 1];
+                for (reply, &(_, ref positions)) in replies.into_iter().zip(slots.iter()) {
+                    let items: Vec<Value> = try!(from_redis_value(&reply));
+                    for (item, &pos) in items.into_iter().zip(positions.iter()) {
+                        values[pos - 1] = item;
+                    }
+                }
+                Ok(Value::Bulk(values))
+            }
+            "MSET" => Ok(Value::Okay),
+            _ => {
+                let mut total = 0;
+                for reply in replies {
+                    total += try!(from_redis_value::<i64>(&reply));
+                }
+                Ok(Value::Int(total))
+            }
+        }
+    }
+}
+
+/// Extracts the slot and node from a `MOVED` or `ASK` error which look
+/// like `MOVED 3999 127.0.0.1:6381`.
+fn redirect_target(err: &RedisError) -> Option<(u16, String)> {
+    let mut pieces = match err.detail() {
+        Some(detail) => detail.split_whitespace(),
+        None => return None,
+    };
+    match (pieces.next().and_then(|slot| slot.parse().ok()), pieces.next()) {
+        (Some(slot), Some(node)) => Some((slot, node.to_string())),
+        _ => None,
+    }
+}
+
+impl ConnectionLike for ClusterConnection {
+    fn req_packed_command(&self, cmd: &[u8]) -> RedisResult<Value> {
+        let commands = match split_packed_commands(cmd) {
+            Err(ref err) if err.kind() == ErrorKind::CrossSlot &&
+                            self.split_multi_key_commands => {
+                return self.execute_split(cmd);
+            }
+            commands => try!(commands),
+        };
+        let (routing, readonly) = try!(combined_routing(&commands));
+        self.request(routing, readonly, cmd, |con| con.recv_response())
+    }
+
+    fn req_packed_commands(&self,
+                           cmd: &[u8],
+                           offset: usize,
+                           count: usize)
+                           -> RedisResult<Vec<Value>> {
+        let commands = try!(split_packed_commands(cmd));
+        if offset == 0 {
+            return self.execute_pipeline(&commands);
+        }
+        // transactions have to run on a single node
+        let (routing, readonly) = try!(combined_routing(&commands));
+        self.request(routing, readonly, cmd, |con| con.recv_responses(offset, count))
+    }
+
+    fn get_db(&self) -> i64 {
+        0
+    }
+
+    fn get_addr(&self) -> Option<ConnectionAddr> {
+        self.initial_nodes.first().map(|info| (*info.addr).clone())
+    }
+}
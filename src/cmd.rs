@@ -1,5 +1,7 @@
-use types::{ToRedisArgs, FromRedisValue, Value, RedisResult, ErrorKind, from_redis_value};
+use types::{ToRedisArgs, FromRedisValue, Value, RedisResult, ErrorKind, from_redis_value,
+            from_owned_redis_value, add_command_to_error};
 use connection::ConnectionLike;
+use script::{ScriptInvocation, pipeline_script_commands};
 
 #[derive(Clone)]
 enum Arg<'a> {
@@ -21,16 +23,33 @@ pub struct Cmd {
 pub struct Pipeline {
     commands: Vec<Cmd>,
     transaction_mode: bool,
+    scripts: Vec<(String, Cmd)>,
 }
 
 /// Represents a redis iterator.
 pub struct Iter<'a, T: FromRedisValue> {
     batch: Vec<T>,
+    batch_cursor: u64,
     cursor: u64,
     con: &'a (ConnectionLike + 'a),
     cmd: Cmd,
 }
 
+impl<'a, T: FromRedisValue> Iter<'a, T> {
+    /// Returns the cursor to resume the iteration from, for instance with
+    /// `Cmd::cursor_arg` or `Commands::scan_from`, or 0 if the iteration
+    /// is complete.  While items of a batch are left this is the cursor
+    /// the batch was fetched with, so resuming never skips items but may
+    /// return some of them again, which scans do anyway.
+    pub fn cursor(&self) -> u64 {
+        if self.batch.is_empty() {
+            self.cursor
+        } else {
+            self.batch_cursor
+        }
+    }
+}
+
 impl<'a, T: FromRedisValue> Iterator for Iter<'a, T> {
     type Item = T;
 
@@ -61,6 +80,7 @@ impl<'a, T: FromRedisValue> Iterator for Iter<'a, T> {
                                                              return None);
             batch.reverse();
 
+            self.batch_cursor = self.cursor;
             self.cursor = cur;
             self.batch = batch;
         }
@@ -232,6 +252,15 @@ impl Cmd {
         encode_command(&self.args, self.cursor.unwrap_or(0))
     }
 
+    /// Returns the name of the command, which is its first argument.
+    fn name(&self) -> &[u8] {
+        match self.args.first() {
+            Some(&Arg::Simple(ref bytes)) => bytes,
+            Some(&Arg::Borrowed(bytes)) => bytes,
+            _ => b"",
+        }
+    }
+
     /// Like `get_packed_command` but replaces the cursor with the
     /// provided value.  If the command is not in scan mode, `None`
     /// is returned.
@@ -257,7 +286,11 @@ impl Cmd {
     pub fn query<T: FromRedisValue>(&self, con: &ConnectionLike) -> RedisResult<T> {
         let pcmd = self.get_packed_command();
         match con.req_packed_command(&pcmd) {
-            Ok(val) => from_redis_value(&val),
+            Ok(val) => {
+                from_owned_redis_value(val).map_err(|err| {
+                    add_command_to_error(err, &String::from_utf8_lossy(self.name()))
+                })
+            }
             Err(e) => Err(e),
         }
     }
@@ -294,6 +327,7 @@ impl Cmd {
         batch.reverse();
         Ok(Iter {
             batch: batch,
+            batch_cursor: self.cursor.unwrap_or(0),
             cursor: cursor,
             con: con,
             cmd: self.clone(),
@@ -347,6 +381,7 @@ impl Pipeline {
         Pipeline {
             commands: vec![],
             transaction_mode: false,
+            scripts: vec![],
         }
     }
 
@@ -365,6 +400,48 @@ impl Pipeline {
         self
     }
 
+    /// Adds the invocation of a script to the pipeline, it is sent as
+    /// `EVALSHA`.  Before the pipeline is executed the server is asked
+    /// which of its scripts are missing and those are loaded, so the
+    /// invocations do not fail because of `NOSCRIPT`.
+    ///
+    /// ```rust,no_run
+    /// # let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+    /// # let con = client.get_connection().unwrap();
+    /// let script = redis::Script::new("return redis.call('INCRBY', KEYS[1], ARGV[1])");
+    /// let (a, b): (i32, i32) = redis::pipe()
+    ///     .invoke_script(script.key("counter").arg(1))
+    ///     .invoke_script(script.key("counter").arg(2))
+    ///     .query(&con).unwrap();
+    /// ```
+    pub fn invoke_script(&mut self, invocation: &ScriptInvocation) -> &mut Pipeline {
+        let (hash, load_cmd, eval_cmd) = pipeline_script_commands(invocation);
+        if !self.scripts.iter().any(|&(ref known, _)| *known == hash) {
+            self.scripts.push((hash, load_cmd));
+        }
+        self.commands.push(eval_cmd);
+        self
+    }
+
+    /// Loads the scripts invoked in the pipeline that the server does not
+    /// know.  This happens before every execution of the pipeline, but if
+    /// the scripts are flushed in between it fails with a `NoScriptError`
+    /// and can be retried after calling this.  The pipeline is never sent
+    /// again on its own as its other commands might have run already.
+    pub fn load_scripts(&self, con: &ConnectionLike) -> RedisResult<()> {
+        if self.scripts.is_empty() {
+            return Ok(());
+        }
+        let hashes: Vec<&str> = self.scripts.iter().map(|&(ref hash, _)| &hash[..]).collect();
+        let exists: Vec<bool> = try!(cmd("SCRIPT").arg("EXISTS").arg(hashes).query(con));
+        for (&(_, ref load_cmd), exists) in self.scripts.iter().zip(exists) {
+            if !exists {
+                let _: String = try!(load_cmd.query(con));
+            }
+        }
+        Ok(())
+    }
+
     #[inline]
     fn get_last_command(&mut self) -> &mut Cmd {
         let idx = match self.commands.len() {
@@ -449,6 +526,16 @@ impl Pipeline {
         }
     }
 
+    fn execute_commands(&self, con: &ConnectionLike) -> RedisResult<Value> {
+        if self.commands.len() == 0 {
+            Ok(Value::Bulk(vec![]))
+        } else if self.transaction_mode {
+            self.execute_transaction(con)
+        } else {
+            self.execute_pipelined(con)
+        }
+    }
+
     /// Executes the pipeline and fetches the return values.  Since most
     /// pipelines return different types it's recommended to use tuple
     /// matching to process the results:
@@ -464,13 +551,8 @@ impl Pipeline {
     /// ```
     #[inline]
     pub fn query<T: FromRedisValue>(&self, con: &ConnectionLike) -> RedisResult<T> {
-        from_redis_value(&(if self.commands.len() == 0 {
-            Value::Bulk(vec![])
-        } else if self.transaction_mode {
-            try!(self.execute_transaction(con))
-        } else {
-            try!(self.execute_pipelined(con))
-        }))
+        try!(self.load_scripts(con));
+        from_owned_redis_value(try!(self.execute_commands(con)))
     }
 
     /// This is a shortcut to `query()` that does not return a value and
@@ -525,6 +607,22 @@ pub fn pack_command(args: &[Vec<u8>]) -> Vec<u8> {
     encode_command(&args.iter().map(|x| Arg::Borrowed(x)).collect(), 0)
 }
 
+/// Packs a command from borrowed arguments without copying them first.
+pub fn pack_borrowed_command(args: &[&[u8]]) -> Vec<u8> {
+    encode_command(&args.iter().map(|&x| Arg::Borrowed(x)).collect(), 0)
+}
+
+/// Packs a command with room for one more argument of the given length.
+/// The data of the argument and a newline have to follow.
+pub fn pack_command_with_trailing_arg(cmd: &Cmd, len: u64) -> Vec<u8> {
+    let packed = cmd.get_packed_command();
+    let header_len = packed.iter().position(|&b| b == b'\n').map_or(0, |pos| pos + 1);
+    let mut rv = format!("*{}\r\n", cmd.args.len() + 1).into_bytes();
+    rv.extend(packed[header_len..].iter());
+    rv.extend(format!("${}\r\n", len).into_bytes());
+    rv
+}
+
 /// Shortcut for creating a new pipeline.
 pub fn pipe() -> Pipeline {
     Pipeline::new()
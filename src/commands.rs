@@ -1,16 +1,30 @@
 // can't use rustfmt here because it screws up the file.
 #![cfg_attr(rustfmt, rustfmt_skip)]
-use types::{FromRedisValue, ToRedisArgs, RedisResult, NumericBehavior};
+use types::{FromRedisValue, ToRedisArgs, RedisResult, NumericBehavior, Value};
 use client::Client;
 use connection::{Connection, ConnectionLike};
+use cluster::ClusterConnection;
+use sentinel::SentinelConnection;
+use streams::{StreamMaxlen, StreamReadOptions};
+use geo::{GeoSearch, Unit};
+use bitfield::BitFieldOperations;
+use server::{ClientKillFilter, PauseMode, FailoverOptions, ShutdownMode, TrackingOptions};
+use acl::AclRules;
+use function::FunctionRestorePolicy;
+use options::{SetOptions, ZAddOptions, GetExpiry, CopyOptions, Expiry, Direction, ZPopSide,
+              LposOptions, RangeQuery, LcsOptions, FlushMode, RestoreOptions,
+              MigrateOptions, ScanOptions, Ttl};
 use cmd::{cmd, Cmd, Pipeline, Iter};
 
+use std::borrow::Borrow;
+use std::time::Duration;
+
 
 macro_rules! implement_commands {
     (
         $(
             $(#[$attr:meta])+
-            fn $name:ident<$($tyargs:ident : $ty:ident),*>(
+            fn $name:ident$(<$($tyargs:ident : $ty:ident),*>)*(
                 $($argname:ident: $argty:ty),*) $body:block
         )*
     ) =>
@@ -46,7 +60,7 @@ macro_rules! implement_commands {
             $(
                 $(#[$attr])*
                 #[inline]
-                fn $name<$($tyargs: $ty,)* RV: FromRedisValue>(
+                fn $name<$($($tyargs: $ty,)*)* RV: FromRedisValue>(
                     &self $(, $argname: $argty)*) -> RedisResult<RV>
                     { ($body).query(self) }
             )*
@@ -63,6 +77,21 @@ macro_rules! implement_commands {
                 cmd("SCAN").cursor_arg(0).arg("MATCH").arg(pattern).iter(self)
             }
 
+            /// Incrementally iterate the keys space with a pattern, a count
+            /// hint or a type filter.
+            #[inline]
+            fn scan_options<RV: FromRedisValue>(&self, options: ScanOptions) -> RedisResult<Iter<RV>> {
+                cmd("SCAN").cursor_arg(0).arg(options).iter(self)
+            }
+
+            /// Incrementally iterate the keys space starting at a cursor
+            /// returned by `Iter::cursor`.
+            #[inline]
+            fn scan_from<RV: FromRedisValue>(&self, cursor: u64, options: ScanOptions)
+                    -> RedisResult<Iter<RV>> {
+                cmd("SCAN").cursor_arg(cursor).arg(options).iter(self)
+            }
+
             /// Incrementally iterate hash fields and associated values.
             #[inline]
             fn hscan<K: ToRedisArgs, RV: FromRedisValue>(&self, key: K) -> RedisResult<Iter<RV>> {
@@ -77,6 +106,15 @@ macro_rules! implement_commands {
                 cmd("HSCAN").arg(key).cursor_arg(0).arg("MATCH").arg(pattern).iter(self)
             }
 
+            /// Incrementally iterate hash fields and associated values
+            /// starting at a cursor returned by `Iter::cursor`.  The type
+            /// filter of the options is only supported by `SCAN`.
+            #[inline]
+            fn hscan_from<K: ToRedisArgs, RV: FromRedisValue>
+                    (&self, key: K, cursor: u64, options: ScanOptions) -> RedisResult<Iter<RV>> {
+                cmd("HSCAN").arg(key).cursor_arg(cursor).arg(options).iter(self)
+            }
+
             /// Incrementally iterate set elements.
             #[inline]
             fn sscan<K: ToRedisArgs, RV: FromRedisValue>(&self, key: K) -> RedisResult<Iter<RV>> {
@@ -90,6 +128,14 @@ macro_rules! implement_commands {
                 cmd("SSCAN").arg(key).cursor_arg(0).arg("MATCH").arg(pattern).iter(self)
             }
 
+            /// Incrementally iterate set elements starting at a cursor
+            /// returned by `Iter::cursor`.
+            #[inline]
+            fn sscan_from<K: ToRedisArgs, RV: FromRedisValue>
+                    (&self, key: K, cursor: u64, options: ScanOptions) -> RedisResult<Iter<RV>> {
+                cmd("SSCAN").arg(key).cursor_arg(cursor).arg(options).iter(self)
+            }
+
             /// Incrementally iterate sorted set elements.
             #[inline]
             fn zscan<K: ToRedisArgs, RV: FromRedisValue>(&self, key: K) -> RedisResult<Iter<RV>> {
@@ -102,6 +148,39 @@ macro_rules! implement_commands {
                     (&self, key: K, pattern: P) -> RedisResult<Iter<RV>> {
                 cmd("ZSCAN").arg(key).cursor_arg(0).arg("MATCH").arg(pattern).iter(self)
             }
+
+            /// Incrementally iterate sorted set elements starting at a
+            /// cursor returned by `Iter::cursor`.
+            #[inline]
+            fn zscan_from<K: ToRedisArgs, RV: FromRedisValue>
+                    (&self, key: K, cursor: u64, options: ScanOptions) -> RedisResult<Iter<RV>> {
+                cmd("ZSCAN").arg(key).cursor_arg(cursor).arg(options).iter(self)
+            }
+
+            /// Get the time to live of a key.
+            #[inline]
+            fn ttl_typed<K: ToRedisArgs>(&self, key: K) -> RedisResult<Ttl> {
+                let secs: i64 = try!(cmd("TTL").arg(key).query(self));
+                Ok(Ttl::from_secs(secs))
+            }
+
+            /// Get the time to live of a key with millisecond precision.
+            #[inline]
+            fn pttl_typed<K: ToRedisArgs>(&self, key: K) -> RedisResult<Ttl> {
+                let ms: i64 = try!(cmd("PTTL").arg(key).query(self));
+                Ok(Ttl::from_millis(ms))
+            }
+
+            /// Stops the server.  The server closes the connection when it
+            /// exits, which counts as success.
+            #[inline]
+            fn shutdown(&self, mode: ShutdownMode) -> RedisResult<()> {
+                match cmd("SHUTDOWN").arg(mode).query::<Value>(self) {
+                    Err(ref err) if err.is_connection_dropped() => Ok(()),
+                    Err(err) => Err(err),
+                    Ok(_) => Ok(()),
+                }
+            }
         }
 
         /// Implements common redis commands for pipelines.  Unlike the regular
@@ -115,9 +194,9 @@ macro_rules! implement_commands {
             $(
                 $(#[$attr])*
                 #[inline]
-                fn $name<'a $(, $tyargs: $ty)*>(
+                fn $name<'a $($(, $tyargs: $ty)*)*>(
                     &mut self $(, $argname: $argty)*) -> &mut Self
-                    { self.perform($body) }
+                    { self.perform(Borrow::borrow(&$body)) }
             )*
         }
     )
@@ -141,6 +220,11 @@ implement_commands! {
         cmd("SET").arg(key).arg(value)
     }
 
+    /// Set the string value of a key with the given options.
+    fn set_options<K: ToRedisArgs, V: ToRedisArgs>(key: K, value: V, options: SetOptions) {
+        cmd("SET").arg(key).arg(value).arg(options)
+    }
+
     /// Sets multiple keys to their values.
     fn set_multiple<K: ToRedisArgs, V: ToRedisArgs>(items: &[(K, V)]) {
         cmd("MSET").arg(items)
@@ -151,7 +235,8 @@ implement_commands! {
         cmd("SETEX").arg(key).arg(seconds).arg(value)
     }
 
-    /// Set the value of a key, only if the key does not exist
+    /// Set the value of a key, only if the key does not exist.  Decode the
+    /// reply as `bool` to learn whether the key was set.
     fn set_nx<K: ToRedisArgs, V: ToRedisArgs>(key: K, value: V) {
         cmd("SETNX").arg(key).arg(value)
     }
@@ -166,17 +251,68 @@ implement_commands! {
         cmd("GETSET").arg(key).arg(value)
     }
 
+    /// Get the value of a key and set its expiration.
+    fn get_ex<K: ToRedisArgs>(key: K, expiry: GetExpiry) {
+        cmd("GETEX").arg(key).arg(expiry)
+    }
+
+    /// Get the value of a key and delete it.
+    fn get_del<K: ToRedisArgs>(key: K) {
+        cmd("GETDEL").arg(key)
+    }
+
+    /// Copy the value of a key to another key.  Returns whether it was
+    /// copied, which fails if the destination exists unless `replace` is
+    /// set.
+    fn copy<K: ToRedisArgs, D: ToRedisArgs>(key: K, dstkey: D, options: CopyOptions) {
+        cmd("COPY").arg(key).arg(dstkey).arg(options)
+    }
+
     /// Delete one or more keys.
     fn del<K: ToRedisArgs>(key: K) {
         cmd("DEL").arg(key)
     }
 
+    /// Delete one or more keys and free their memory in the background.
+    fn unlink<K: ToRedisArgs>(key: K) {
+        cmd("UNLINK").arg(key)
+    }
+
+    /// Update the last access time of one or more keys.  Returns how many
+    /// of them exist.
+    fn touch<K: ToRedisArgs>(key: K) {
+        cmd("TOUCH").arg(key)
+    }
+
     /// Determine if a key exists.
     fn exists<K: ToRedisArgs>(key: K) {
         cmd("EXISTS").arg(key)
     }
 
-    /// Set a key's time to live in seconds.
+    /// Serializes the value stored at a key.  Use `Option<Vec<u8>>` to
+    /// decode it, it is nil if the key does not exist.
+    fn dump<K: ToRedisArgs>(key: K) {
+        cmd("DUMP").arg(key)
+    }
+
+    /// Creates a key from a payload of `dump`.  The key expires after the
+    /// given number of milliseconds, or never if it is 0.
+    fn restore<K: ToRedisArgs, P: ToRedisArgs>
+            (key: K, ttl: usize, payload: P, options: RestoreOptions) {
+        cmd("RESTORE").arg(key).arg(ttl).arg(payload).arg(options)
+    }
+
+    /// Moves keys to the given database of another instance.  The keys
+    /// are only deleted after the destination acknowledged them.  Use
+    /// `MigrateReply` to decode the reply.
+    fn migrate<H: ToRedisArgs, K: ToRedisArgs>
+            (host: H, port: u16, keys: K, db: i64, timeout: Duration, options: MigrateOptions) {
+        cmd("MIGRATE").arg(host).arg(port).arg("").arg(db).arg(timeout_ms(timeout))
+            .arg(options).arg("KEYS").arg(keys)
+    }
+
+    /// Set a key's time to live in seconds.  Decode the reply as `bool` to
+    /// learn whether the key exists.
     fn expire<K: ToRedisArgs>(key: K, seconds: usize) {
         cmd("EXPIRE").arg(key).arg(seconds)
     }
@@ -186,7 +322,8 @@ implement_commands! {
         cmd("EXPIREAT").arg(key).arg(ts)
     }
 
-    /// Set a key's time to live in milliseconds.
+    /// Set a key's time to live in milliseconds.  Decode the reply as
+    /// `bool` to learn whether the key exists.
     fn pexpire<K: ToRedisArgs>(key: K, ms: usize) {
         cmd("PEXPIRE").arg(key).arg(ms)
     }
@@ -196,6 +333,52 @@ implement_commands! {
         cmd("PEXPIREAT").arg(key).arg(ts)
     }
 
+    /// Set a key's time to live in seconds depending on its current
+    /// expiration.  Returns whether the expiration was set.
+    fn expire_options<K: ToRedisArgs>(key: K, seconds: usize, expiry: Expiry) {
+        cmd("EXPIRE").arg(key).arg(seconds).arg(expiry)
+    }
+
+    /// Set the expiration for a key as a UNIX timestamp depending on its
+    /// current expiration.
+    fn expire_at_options<K: ToRedisArgs>(key: K, ts: usize, expiry: Expiry) {
+        cmd("EXPIREAT").arg(key).arg(ts).arg(expiry)
+    }
+
+    /// Set a key's time to live in milliseconds depending on its current
+    /// expiration.
+    fn pexpire_options<K: ToRedisArgs>(key: K, ms: usize, expiry: Expiry) {
+        cmd("PEXPIRE").arg(key).arg(ms).arg(expiry)
+    }
+
+    /// Set the expiration for a key as a UNIX timestamp in milliseconds
+    /// depending on its current expiration.
+    fn pexpire_at_options<K: ToRedisArgs>(key: K, ts: usize, expiry: Expiry) {
+        cmd("PEXPIREAT").arg(key).arg(ts).arg(expiry)
+    }
+
+    /// Get the time to live of a key in seconds.  Returns -1 if the key
+    /// has no expiration and -2 if it does not exist, see `ttl_typed`.
+    fn ttl<K: ToRedisArgs>(key: K) {
+        cmd("TTL").arg(key)
+    }
+
+    /// Get the time to live of a key in milliseconds.
+    fn pttl<K: ToRedisArgs>(key: K) {
+        cmd("PTTL").arg(key)
+    }
+
+    /// Get the expiration of a key as a UNIX timestamp.  Returns -1 if
+    /// the key has no expiration and -2 if it does not exist.
+    fn expire_time<K: ToRedisArgs>(key: K) {
+        cmd("EXPIRETIME").arg(key)
+    }
+
+    /// Get the expiration of a key as a UNIX timestamp in milliseconds.
+    fn pexpire_time<K: ToRedisArgs>(key: K) {
+        cmd("PEXPIRETIME").arg(key)
+    }
+
     /// Remove the expiration from a key.
     fn persist<K: ToRedisArgs>(key: K) {
         cmd("PERSIST").arg(key)
@@ -248,6 +431,12 @@ implement_commands! {
         cmd("BITCOUNT").arg(key).arg(start).arg(end)
     }
 
+    /// Runs the given operations on integer fields of the string value
+    /// stored at key.
+    fn bitfield<K: ToRedisArgs>(key: K, operations: BitFieldOperations) {
+        cmd("BITFIELD").arg(key).arg(operations)
+    }
+
     /// Perform a bitwise AND between multiple keys (containing string values)
     /// and store the result in the destination key.
     fn bit_and<K: ToRedisArgs>(dstkey: K, srckeys: K) {
@@ -272,6 +461,12 @@ implement_commands! {
         cmd("BITOP").arg("NOT").arg(dstkey).arg(srckey)
     }
 
+    /// Returns the longest common subsequence of the strings stored at
+    /// two keys, or details about it depending on the options.
+    fn lcs<K1: ToRedisArgs, K2: ToRedisArgs>(key1: K1, key2: K2, options: LcsOptions) {
+        cmd("LCS").arg(key1).arg(key2).arg(options)
+    }
+
     /// Get the length of the value stored in a key.
     fn strlen<K: ToRedisArgs>(key: K) {
         cmd("STRLEN").arg(key)
@@ -333,6 +528,12 @@ implement_commands! {
         cmd("HGETALL").arg(key)
     }
 
+    /// Gets random fields of a hash with their values.  With a negative
+    /// count the same field may be returned several times.
+    fn hrandfield_withvalues<K: ToRedisArgs>(key: K, count: isize) {
+        cmd("HRANDFIELD").arg(key).arg(count).arg("WITHVALUES")
+    }
+
     /// Gets the length of a hash.
     fn hlen<K: ToRedisArgs>(key: K) {
         cmd("HLEN").arg(key)
@@ -356,6 +557,21 @@ implement_commands! {
         cmd("BRPOPLPUSH").arg(srckey).arg(dstkey).arg(timeout)
     }
 
+    /// Pops up to count elements from the first non-empty list of the
+    /// given keys.  Decode the reply as `Option<(String, Vec<T>)>`, the
+    /// key and its elements or nil if all lists are empty.
+    fn lmpop<K: ToRedisArgs>(keys: &[K], direction: Direction, count: usize) {
+        cmd("LMPOP").arg(keys.len()).arg(keys).arg(direction).arg("COUNT").arg(count)
+    }
+
+    /// Pops like `lmpop` or blocks until an element is available or the
+    /// timeout passes.  A zero timeout blocks forever.
+    fn blmpop<K: ToRedisArgs>(timeout: Duration, keys: &[K], direction: Direction,
+                              count: usize) {
+        cmd("BLMPOP").arg(timeout_secs(timeout)).arg(keys.len()).arg(keys).arg(direction)
+            .arg("COUNT").arg(count)
+    }
+
     /// Get an element from a list by its index.
     fn lindex<K: ToRedisArgs>(key: K, index: isize) {
         cmd("LINDEX").arg(key).arg(index)
@@ -378,6 +594,11 @@ implement_commands! {
         cmd("LLEN").arg(key)
     }
 
+    /// Returns the index of matching elements in the list stored at key.
+    fn lpos<K: ToRedisArgs, V: ToRedisArgs>(key: K, element: V, options: LposOptions) {
+        cmd("LPOS").arg(key).arg(element).arg(options)
+    }
+
     /// Removes and returns the first element of the list stored at key.
     fn lpop<K: ToRedisArgs>(key: K) {
         cmd("LPOP").arg(key)
@@ -464,6 +685,13 @@ implement_commands! {
         cmd("SINTER").arg(keys)
     }
 
+    /// Returns the number of members in the intersection of multiple
+    /// sets without returning the members.  Counting stops at limit
+    /// unless it is 0.
+    fn sintercard<K: ToRedisArgs>(keys: &[K], limit: usize) {
+        cmd("SINTERCARD").arg(keys.len()).arg(keys).arg("LIMIT").arg(limit)
+    }
+
     /// Intersect multiple sets and store the resulting set in a key.
     fn sdinterstore<K: ToRedisArgs>(dstkey: K, keys: K) {
         cmd("SINTERSTORE").arg(dstkey).arg(keys)
@@ -499,6 +727,12 @@ implement_commands! {
         cmd("SRANDMEMBER").arg(key).arg(count)
     }
 
+    /// Get exactly count random members from a set, the same member may
+    /// be returned several times.
+    fn srandmember_repeated<K: ToRedisArgs>(key: K, count: usize) {
+        cmd("SRANDMEMBER").arg(key).arg(-(count as i64))
+    }
+
     /// Remove one or more members from a set.
     fn srem<K: ToRedisArgs, M: ToRedisArgs>(key: K, member: M) {
         cmd("SREM").arg(key).arg(member)
@@ -526,6 +760,20 @@ implement_commands! {
         cmd("ZADD").arg(key).arg(items)
     }
 
+    /// Add a member to a sorted set or update its score with the given
+    /// options.
+    fn zadd_options<K: ToRedisArgs, S: ToRedisArgs, M: ToRedisArgs>(
+            key: K, member: M, score: S, options: ZAddOptions) {
+        cmd("ZADD").arg(key).arg(options).arg(score).arg(member)
+    }
+
+    /// Add multiple members to a sorted set or update their scores with
+    /// the given options.
+    fn zadd_multiple_options<K: ToRedisArgs, S: ToRedisArgs, M: ToRedisArgs>(
+            key: K, items: &[(S, M)], options: ZAddOptions) {
+        cmd("ZADD").arg(key).arg(options).arg(items)
+    }
+
     /// Get the number of members in a sorted set.
     fn zcard<K: ToRedisArgs>(key: K) {
         cmd("ZCARD").arg(key)
@@ -548,6 +796,13 @@ implement_commands! {
         cmd("ZINTERSTORE").arg(dstkey).arg(keys.len()).arg(keys)
     }
 
+    /// Returns the number of members in the intersection of multiple
+    /// sorted sets without returning the members.  Counting stops at
+    /// limit unless it is 0.
+    fn zintercard<K: ToRedisArgs>(keys: &[K], limit: usize) {
+        cmd("ZINTERCARD").arg(keys.len()).arg(keys).arg("LIMIT").arg(limit)
+    }
+
     /// Intersect multiple sorted sets and store the resulting sorted set in
     /// a new key using MIN as aggregation function.
     fn zinterstore_min<K: ToRedisArgs>(dstkey: K, keys: &[K]) {
@@ -565,6 +820,18 @@ implement_commands! {
         cmd("ZLEXCOUNT").arg(key).arg(min).arg(max)
     }
 
+    /// Return a range of members in a sorted set selected by rank, score
+    /// or lexicographical order.
+    fn zrange_with<K: ToRedisArgs>(key: K, query: RangeQuery) {
+        cmd("ZRANGE").arg(key).arg(query)
+    }
+
+    /// Store a range of members of a sorted set in another key.  Returns
+    /// the number of stored members.
+    fn zrangestore<D: ToRedisArgs, K: ToRedisArgs>(dstkey: D, key: K, query: RangeQuery) {
+        cmd("ZRANGESTORE").arg(dstkey).arg(key).arg(query)
+    }
+
     /// Return a range of members in a sorted set, by index
     fn zrange<K: ToRedisArgs>(key: K, start: isize, stop: isize) {
         cmd("ZRANGE").arg(key).arg(start).arg(stop)
@@ -627,11 +894,32 @@ implement_commands! {
         cmd("ZRANK").arg(key).arg(member)
     }
 
+    /// Return random members of a sorted set with their scores.  With a
+    /// negative count the same member may be returned several times.
+    fn zrandmember_withscores<K: ToRedisArgs>(key: K, count: isize) {
+        cmd("ZRANDMEMBER").arg(key).arg(count).arg("WITHSCORES")
+    }
+
     /// Remove one or more members from a sorted set.
     fn zrem<K: ToRedisArgs, M: ToRedisArgs>(key: K, members: M) {
         cmd("ZREM").arg(key).arg(members)
     }
 
+    /// Pops up to count members from the first non-empty sorted set of
+    /// the given keys.  Decode the reply as
+    /// `Option<(String, Vec<Nested<(String, f64)>>)>`, the key and its
+    /// members with their scores or nil if all sets are empty.
+    fn zmpop<K: ToRedisArgs>(keys: &[K], side: ZPopSide, count: usize) {
+        cmd("ZMPOP").arg(keys.len()).arg(keys).arg(side).arg("COUNT").arg(count)
+    }
+
+    /// Pops like `zmpop` or blocks until a member is available or the
+    /// timeout passes.  A zero timeout blocks forever.
+    fn bzmpop<K: ToRedisArgs>(timeout: Duration, keys: &[K], side: ZPopSide, count: usize) {
+        cmd("BZMPOP").arg(timeout_secs(timeout)).arg(keys.len()).arg(keys).arg(side)
+            .arg("COUNT").arg(count)
+    }
+
     /// Remove all members in a sorted set between the given lexicographical range.
     fn zrembylex<K: ToRedisArgs, M: ToRedisArgs, MM: ToRedisArgs>(key: K, min: M, max: MM) {
         cmd("ZREMBYLEX").arg(key).arg(min).arg(max)
@@ -724,7 +1012,7 @@ implement_commands! {
     }
 
     /// Merge N different HyperLogLogs into a single one.
-    fn pfmerge<K: ToRedisArgs>(dstkey: K, srckeys: K) {
+    fn pfmerge<D: ToRedisArgs, K: ToRedisArgs>(dstkey: D, srckeys: K) {
         cmd("PFMERGE").arg(dstkey).arg(srckeys)
     }
 
@@ -732,10 +1020,564 @@ implement_commands! {
     fn publish<K: ToRedisArgs, E: ToRedisArgs>(channel: K, message: E) {
         cmd("PUBLISH").arg(channel).arg(message)
     }
+
+    /// Posts a message to the given sharded channel.
+    fn spublish<K: ToRedisArgs, E: ToRedisArgs>(channel: K, message: E) {
+        cmd("SPUBLISH").arg(channel).arg(message)
+    }
+
+    /// Lists the channels with at least one subscriber that match the
+    /// given glob-style pattern.
+    fn pubsub_channels<K: ToRedisArgs>(pattern: K) {
+        cmd("PUBSUB").arg("CHANNELS").arg(pattern)
+    }
+
+    /// Returns the number of subscribers of the given channels.  Decode
+    /// the reply as `HashMap<String, usize>` to map channels to counts.
+    fn pubsub_numsub<K: ToRedisArgs>(channels: K) {
+        cmd("PUBSUB").arg("NUMSUB").arg(channels)
+    }
+
+    /// Returns the number of patterns clients are subscribed to.
+    fn pubsub_numpat() {
+        cmd("PUBSUB").arg("NUMPAT")
+    }
+
+    // server commands
+
+    /// Returns information about the server.  Use `ServerInfo` or
+    /// `InfoDict` to decode it.
+    fn info() {
+        cmd("INFO")
+    }
+
+    /// Blocks until the writes of the connection reached the given number
+    /// of replicas or the timeout passed, a zero timeout blocks forever.
+    /// Returns the number of replicas that acknowledged the writes.
+    fn wait(numreplicas: usize, timeout: Duration) {
+        cmd("WAIT").arg(numreplicas).arg(timeout_ms(timeout))
+    }
+
+    /// Makes the server a replica of the given master.
+    fn replicaof<H: ToRedisArgs>(host: H, port: u16) {
+        cmd("REPLICAOF").arg(host).arg(port)
+    }
+
+    /// Makes the server a master, it keeps the data it replicated so far.
+    fn replicaof_no_one() {
+        cmd("REPLICAOF").arg("NO").arg("ONE")
+    }
+
+    /// Starts a coordinated failover from this master to a replica.
+    fn failover(options: FailoverOptions) {
+        cmd("FAILOVER").arg(options)
+    }
+
+    /// Deletes all keys of the current database.
+    fn flushdb_options(mode: FlushMode) {
+        cmd("FLUSHDB").arg(mode)
+    }
+
+    /// Deletes all keys of all databases.
+    fn flushall_options(mode: FlushMode) {
+        cmd("FLUSHALL").arg(mode)
+    }
+
+    /// Returns the latency samples of an event.  Use
+    /// `Vec<LatencySample>` to decode it.
+    fn latency_history<E: ToRedisArgs>(event: E) {
+        cmd("LATENCY").arg("HISTORY").arg(event)
+    }
+
+    /// Returns the latest sample of all events.  Use `Vec<LatencyEvent>`
+    /// to decode it.
+    fn latency_latest() {
+        cmd("LATENCY").arg("LATEST")
+    }
+
+    /// Removes the samples of all events and returns how many events
+    /// there were.
+    fn latency_reset() {
+        cmd("LATENCY").arg("RESET")
+    }
+
+    /// Returns a human readable analysis of the latency samples.
+    fn latency_doctor() {
+        cmd("LATENCY").arg("DOCTOR")
+    }
+
+    /// Returns the number of commands the server supports.
+    fn command_count() {
+        cmd("COMMAND").arg("COUNT")
+    }
+
+    /// Returns details like the arity and key positions of commands.  Use
+    /// `Vec<Option<CommandInfo>>` to decode it, unknown commands are nil.
+    fn command_info<N: ToRedisArgs>(names: N) {
+        cmd("COMMAND").arg("INFO").arg(names)
+    }
+
+    /// Returns the documentation of commands.  Use
+    /// `HashMap<String, CommandDocs>` to decode it, unknown commands are
+    /// left out.
+    fn command_docs<N: ToRedisArgs>(names: N) {
+        cmd("COMMAND").arg("DOCS").arg(names)
+    }
+
+    /// Returns the configuration parameters matching the glob-style
+    /// pattern.  Use `HashMap<String, String>` to decode it.
+    fn config_get<P: ToRedisArgs>(pattern: P) {
+        cmd("CONFIG").arg("GET").arg(pattern)
+    }
+
+    /// Sets a configuration parameter at runtime.
+    fn config_set<P: ToRedisArgs, V: ToRedisArgs>(parameter: P, value: V) {
+        cmd("CONFIG").arg("SET").arg(parameter).arg(value)
+    }
+
+    /// Resets the statistics reported by `INFO`.
+    fn config_resetstat() {
+        cmd("CONFIG").arg("RESETSTAT")
+    }
+
+    /// Writes the configuration the server runs with to its config file.
+    fn config_rewrite() {
+        cmd("CONFIG").arg("REWRITE")
+    }
+
+    /// Returns the internal representation of the value stored at a key.
+    /// Use `Option<Encoding>` to decode it.
+    fn object_encoding<K: ToRedisArgs>(key: K) {
+        cmd("OBJECT").arg("ENCODING").arg(key)
+    }
+
+    /// Returns the number of seconds since the key was last accessed.
+    /// Not available if the server evicts by access frequency.
+    fn object_idletime<K: ToRedisArgs>(key: K) {
+        cmd("OBJECT").arg("IDLETIME").arg(key)
+    }
+
+    /// Returns the logarithmic access frequency counter of a key.  Only
+    /// available if the server evicts by access frequency.
+    fn object_freq<K: ToRedisArgs>(key: K) {
+        cmd("OBJECT").arg("FREQ").arg(key)
+    }
+
+    /// Returns the number of references to the value stored at a key.
+    fn object_refcount<K: ToRedisArgs>(key: K) {
+        cmd("OBJECT").arg("REFCOUNT").arg(key)
+    }
+
+    /// Returns the number of bytes used by a key and its value, or nil if
+    /// the key does not exist.  For nested values only the given number
+    /// of elements is sampled, 0 samples all of them.
+    fn memory_usage<K: ToRedisArgs>(key: K, samples: usize) {
+        cmd("MEMORY").arg("USAGE").arg(key).arg("SAMPLES").arg(samples)
+    }
+
+    /// Returns details about the memory usage of the server.  Use
+    /// `MemoryStats` to decode it.
+    fn memory_stats() {
+        cmd("MEMORY").arg("STATS")
+    }
+
+    /// Returns the connections of the server.  Use `Vec<ClientInfo>` to
+    /// decode it.
+    fn client_list() {
+        cmd("CLIENT").arg("LIST")
+    }
+
+    /// Returns the current connection as seen by the server.  Use
+    /// `ClientInfo` to decode it.
+    fn client_info() {
+        cmd("CLIENT").arg("INFO")
+    }
+
+    /// Closes the connections that match the filter and returns how many
+    /// were closed.
+    fn client_kill(filter: ClientKillFilter) {
+        cmd("CLIENT").arg("KILL").arg(filter)
+    }
+
+    /// Turns on or off the tracking of keys for client side caching.
+    fn client_tracking(on: bool, options: TrackingOptions) {
+        cmd("CLIENT").arg("TRACKING").arg(if on { "ON" } else { "OFF" }).arg(options)
+    }
+
+    /// Holds back the commands of all clients for the given time, for
+    /// instance while a replica takes over.
+    fn client_pause(timeout: Duration, mode: PauseMode) {
+        cmd("CLIENT").arg("PAUSE").arg(timeout_ms(timeout)).arg(mode)
+    }
+
+    /// Resumes the clients paused by `client_pause` before the timeout.
+    fn client_unpause() {
+        cmd("CLIENT").arg("UNPAUSE")
+    }
+
+    /// Returns up to count entries of the slow log, the latest first.  A
+    /// negative count returns all of them.  Use `Vec<SlowLogEntry>` to
+    /// decode it.
+    fn slowlog_get(count: isize) {
+        cmd("SLOWLOG").arg("GET").arg(count)
+    }
+
+    /// Returns the number of entries in the slow log.
+    fn slowlog_len() {
+        cmd("SLOWLOG").arg("LEN")
+    }
+
+    /// Removes all entries from the slow log.
+    fn slowlog_reset() {
+        cmd("SLOWLOG").arg("RESET")
+    }
+
+    // script commands
+
+    /// Checks which of the scripts with the given SHA1 hashes the server
+    /// knows.  Use `Vec<bool>` to decode it.
+    fn script_exists<H: ToRedisArgs>(hashes: H) {
+        cmd("SCRIPT").arg("EXISTS").arg(hashes)
+    }
+
+    /// Removes all scripts from the script cache.
+    fn script_flush(mode: FlushMode) {
+        cmd("SCRIPT").arg("FLUSH").arg(mode)
+    }
+
+    /// Kills the currently running script if it did not write yet.
+    fn script_kill() {
+        cmd("SCRIPT").arg("KILL")
+    }
+
+    // function commands
+
+    /// Returns the function libraries whose names match the glob-style
+    /// pattern.  Use `Vec<LibraryInfo>` to decode it.
+    fn function_list<P: ToRedisArgs>(pattern: P) {
+        cmd("FUNCTION").arg("LIST").arg("LIBRARYNAME").arg(pattern)
+    }
+
+    /// Returns the function libraries whose names match the glob-style
+    /// pattern together with their source.
+    fn function_list_with_code<P: ToRedisArgs>(pattern: P) {
+        cmd("FUNCTION").arg("LIST").arg("LIBRARYNAME").arg(pattern).arg("WITHCODE")
+    }
+
+    /// Returns a serialized payload of all function libraries.  Use
+    /// `Vec<u8>` to decode it.
+    fn function_dump() {
+        cmd("FUNCTION").arg("DUMP")
+    }
+
+    /// Restores the function libraries from a payload of `function_dump`.
+    fn function_restore<P: ToRedisArgs>(payload: P, policy: FunctionRestorePolicy) {
+        cmd("FUNCTION").arg("RESTORE").arg(payload).arg(policy)
+    }
+
+    /// Deletes a function library.
+    fn function_delete<L: ToRedisArgs>(library: L) {
+        cmd("FUNCTION").arg("DELETE").arg(library)
+    }
+
+    /// Deletes all function libraries.
+    fn function_flush() {
+        cmd("FUNCTION").arg("FLUSH")
+    }
+
+    // acl commands
+
+    /// Returns the name of the user the connection is authenticated as.
+    fn acl_whoami() {
+        cmd("ACL").arg("WHOAMI")
+    }
+
+    /// Returns the users and their rules in the format of the ACL file.
+    fn acl_list() {
+        cmd("ACL").arg("LIST")
+    }
+
+    /// Returns the rules of a user, or nil if there is no such user.  Use
+    /// `Option<AclUserInfo>` to decode it.
+    fn acl_getuser<U: ToRedisArgs>(user: U) {
+        cmd("ACL").arg("GETUSER").arg(user)
+    }
+
+    /// Creates a user or applies the rules to an existing user.
+    fn acl_setuser<U: ToRedisArgs>(user: U, rules: AclRules) {
+        cmd("ACL").arg("SETUSER").arg(user).arg(rules)
+    }
+
+    /// Deletes one or more users and returns how many existed.
+    fn acl_deluser<U: ToRedisArgs>(users: U) {
+        cmd("ACL").arg("DELUSER").arg(users)
+    }
+
+    /// Returns the command categories.
+    fn acl_cat() {
+        cmd("ACL").arg("CAT")
+    }
+
+    /// Returns the commands of a category.
+    fn acl_cat_commands<C: ToRedisArgs>(category: C) {
+        cmd("ACL").arg("CAT").arg(category)
+    }
+
+    // cluster commands
+
+    /// Returns the state of the cluster.  Use `ClusterInfo` to decode it.
+    fn cluster_info() {
+        cmd("CLUSTER").arg("INFO")
+    }
+
+    /// Returns the nodes of the cluster as known to the server.  Use
+    /// `Vec<ClusterNode>` to decode it.
+    fn cluster_nodes() {
+        cmd("CLUSTER").arg("NODES")
+    }
+
+    /// Returns the slot ranges of the cluster and their nodes.  Use
+    /// `Vec<ClusterSlot>` to decode it.
+    fn cluster_slots() {
+        cmd("CLUSTER").arg("SLOTS")
+    }
+
+    /// Returns the shards of the cluster.  Use `Vec<ClusterShard>` to
+    /// decode it.
+    fn cluster_shards() {
+        cmd("CLUSTER").arg("SHARDS")
+    }
+
+    // stream commands
+
+    /// Appends an entry to a stream.  Use `"*"` as id to let the server
+    /// generate one; the id of the new entry is returned.
+    fn xadd<K: ToRedisArgs, ID: ToRedisArgs, F: ToRedisArgs, V: ToRedisArgs>(
+            key: K, id: ID, items: &[(F, V)]) {
+        cmd("XADD").arg(key).arg(id).arg(items)
+    }
+
+    /// Appends an entry to a stream and trims the stream to the given
+    /// length.
+    fn xadd_maxlen<K: ToRedisArgs, ID: ToRedisArgs, F: ToRedisArgs, V: ToRedisArgs>(
+            key: K, maxlen: StreamMaxlen, id: ID, items: &[(F, V)]) {
+        cmd("XADD").arg(key).arg(maxlen).arg(id).arg(items)
+    }
+
+    /// Reads the entries after the given ids from one or more streams.
+    /// Use `StreamReadReply` to decode the reply.
+    fn xread<K: ToRedisArgs, ID: ToRedisArgs>(keys: &[K], ids: &[ID]) {
+        cmd("XREAD").arg("STREAMS").arg(keys).arg(ids)
+    }
+
+    /// Reads from one or more streams like `xread` with a count limit
+    /// or a blocking timeout.
+    fn xread_options<K: ToRedisArgs, ID: ToRedisArgs>(
+            keys: &[K], ids: &[ID], options: StreamReadOptions) {
+        cmd("XREAD").arg(options).arg("STREAMS").arg(keys).arg(ids)
+    }
+
+    /// Returns the entries of a stream with ids between start and end.
+    /// `"-"` and `"+"` stand for the lowest and highest id.  Use
+    /// `Vec<StreamEntry>` to decode the reply.
+    fn xrange<K: ToRedisArgs, S: ToRedisArgs, E: ToRedisArgs>(key: K, start: S, end: E) {
+        cmd("XRANGE").arg(key).arg(start).arg(end)
+    }
+
+    /// Returns at most count entries of a stream with ids between start
+    /// and end.
+    fn xrange_count<K: ToRedisArgs, S: ToRedisArgs, E: ToRedisArgs>(
+            key: K, start: S, end: E, count: usize) {
+        cmd("XRANGE").arg(key).arg(start).arg(end).arg("COUNT").arg(count)
+    }
+
+    /// Returns the entries of a stream with ids between end and start in
+    /// reverse order.
+    fn xrevrange<K: ToRedisArgs, E: ToRedisArgs, S: ToRedisArgs>(key: K, end: E, start: S) {
+        cmd("XREVRANGE").arg(key).arg(end).arg(start)
+    }
+
+    /// Returns at most count entries of a stream with ids between end
+    /// and start in reverse order.
+    fn xrevrange_count<K: ToRedisArgs, E: ToRedisArgs, S: ToRedisArgs>(
+            key: K, end: E, start: S, count: usize) {
+        cmd("XREVRANGE").arg(key).arg(end).arg(start).arg("COUNT").arg(count)
+    }
+
+    /// Returns the number of entries in a stream.
+    fn xlen<K: ToRedisArgs>(key: K) {
+        cmd("XLEN").arg(key)
+    }
+
+    /// Removes entries from a stream and returns how many were removed.
+    fn xdel<K: ToRedisArgs, ID: ToRedisArgs>(key: K, ids: &[ID]) {
+        cmd("XDEL").arg(key).arg(ids)
+    }
+
+    /// Trims a stream to the given length and returns how many entries
+    /// were removed.
+    fn xtrim<K: ToRedisArgs>(key: K, maxlen: StreamMaxlen) {
+        cmd("XTRIM").arg(key).arg(maxlen)
+    }
+
+    /// Creates a consumer group that starts reading after the given id.
+    /// Use `"$"` to only read new entries.
+    fn xgroup_create<K: ToRedisArgs, G: ToRedisArgs, ID: ToRedisArgs>(key: K, group: G, id: ID) {
+        cmd("XGROUP").arg("CREATE").arg(key).arg(group).arg(id)
+    }
+
+    /// Creates a consumer group like `xgroup_create` and the stream if it
+    /// does not exist yet.
+    fn xgroup_create_mkstream<K: ToRedisArgs, G: ToRedisArgs, ID: ToRedisArgs>(
+            key: K, group: G, id: ID) {
+        cmd("XGROUP").arg("CREATE").arg(key).arg(group).arg(id).arg("MKSTREAM")
+    }
+
+    /// Destroys a consumer group together with its pending entries.
+    fn xgroup_destroy<K: ToRedisArgs, G: ToRedisArgs>(key: K, group: G) {
+        cmd("XGROUP").arg("DESTROY").arg(key).arg(group)
+    }
+
+    /// Reads entries for a consumer of a group.  The id `">"` reads new
+    /// entries, any other id reads the pending entries of the consumer
+    /// after it.  Use `StreamReadReply` to decode the reply.
+    fn xreadgroup<G: ToRedisArgs, C: ToRedisArgs, K: ToRedisArgs, ID: ToRedisArgs>(
+            group: G, consumer: C, keys: &[K], ids: &[ID]) {
+        cmd("XREADGROUP").arg("GROUP").arg(group).arg(consumer)
+            .arg("STREAMS").arg(keys).arg(ids)
+    }
+
+    /// Reads entries for a consumer of a group like `xreadgroup` with a
+    /// count limit, a blocking timeout or without acknowledgement.
+    fn xreadgroup_options<G: ToRedisArgs, C: ToRedisArgs, K: ToRedisArgs, ID: ToRedisArgs>(
+            group: G, consumer: C, keys: &[K], ids: &[ID], options: StreamReadOptions) {
+        cmd("XREADGROUP").arg("GROUP").arg(group).arg(consumer).arg(options)
+            .arg("STREAMS").arg(keys).arg(ids)
+    }
+
+    /// Acknowledges entries of a group and returns how many were pending.
+    fn xack<K: ToRedisArgs, G: ToRedisArgs, ID: ToRedisArgs>(key: K, group: G, ids: &[ID]) {
+        cmd("XACK").arg(key).arg(group).arg(ids)
+    }
+
+    /// Returns a summary of the pending entries of a group.  Use
+    /// `StreamPendingReply` to decode the reply.
+    fn xpending<K: ToRedisArgs, G: ToRedisArgs>(key: K, group: G) {
+        cmd("XPENDING").arg(key).arg(group)
+    }
+
+    /// Returns at most count pending entries of a group with ids between
+    /// start and end.  Use `Vec<StreamPendingEntry>` to decode the reply.
+    fn xpending_count<K: ToRedisArgs, G: ToRedisArgs, S: ToRedisArgs, E: ToRedisArgs>(
+            key: K, group: G, start: S, end: E, count: usize) {
+        cmd("XPENDING").arg(key).arg(group).arg(start).arg(end).arg(count)
+    }
+
+    /// Returns at most count pending entries of one consumer of a group
+    /// with ids between start and end.
+    fn xpending_consumer_count<K: ToRedisArgs, G: ToRedisArgs, S: ToRedisArgs, E: ToRedisArgs,
+                               C: ToRedisArgs>(
+            key: K, group: G, start: S, end: E, count: usize, consumer: C) {
+        cmd("XPENDING").arg(key).arg(group).arg(start).arg(end).arg(count).arg(consumer)
+    }
+
+    /// Transfers pending entries that were idle for at least the given
+    /// time to another consumer.  Use `Vec<StreamEntry>` to decode the
+    /// reply.
+    fn xclaim<K: ToRedisArgs, G: ToRedisArgs, C: ToRedisArgs, ID: ToRedisArgs>(
+            key: K, group: G, consumer: C, min_idle_ms: usize, ids: &[ID]) {
+        cmd("XCLAIM").arg(key).arg(group).arg(consumer).arg(min_idle_ms).arg(ids)
+    }
+
+    /// Transfers pending entries starting at the given id that were idle
+    /// for at least the given time to another consumer.  Use
+    /// `StreamAutoClaimReply` to decode the reply.
+    fn xautoclaim<K: ToRedisArgs, G: ToRedisArgs, C: ToRedisArgs, S: ToRedisArgs>(
+            key: K, group: G, consumer: C, min_idle_ms: usize, start: S) {
+        cmd("XAUTOCLAIM").arg(key).arg(group).arg(consumer).arg(min_idle_ms).arg(start)
+    }
+
+    /// Transfers at most count pending entries like `xautoclaim`.
+    fn xautoclaim_count<K: ToRedisArgs, G: ToRedisArgs, C: ToRedisArgs, S: ToRedisArgs>(
+            key: K, group: G, consumer: C, min_idle_ms: usize, start: S, count: usize) {
+        cmd("XAUTOCLAIM").arg(key).arg(group).arg(consumer).arg(min_idle_ms).arg(start)
+            .arg("COUNT").arg(count)
+    }
+
+    /// Returns the state of a stream.  Use `StreamInfo` to decode the
+    /// reply.
+    fn xinfo_stream<K: ToRedisArgs>(key: K) {
+        cmd("XINFO").arg("STREAM").arg(key)
+    }
+
+    /// Returns the consumer groups of a stream.  Use
+    /// `Vec<StreamGroupInfo>` to decode the reply.
+    fn xinfo_groups<K: ToRedisArgs>(key: K) {
+        cmd("XINFO").arg("GROUPS").arg(key)
+    }
+
+    /// Returns the consumers of a group.  Use `Vec<StreamConsumerInfo>`
+    /// to decode the reply.
+    fn xinfo_consumers<K: ToRedisArgs, G: ToRedisArgs>(key: K, group: G) {
+        cmd("XINFO").arg("CONSUMERS").arg(key).arg(group)
+    }
+
+    // geo commands
+
+    /// Adds members with their positions to a geo set and returns how
+    /// many were new.  Pass `(Coord, member)` pairs.
+    fn geo_add<K: ToRedisArgs, M: ToRedisArgs>(key: K, members: M) {
+        cmd("GEOADD").arg(key).arg(members)
+    }
+
+    /// Returns the positions of members.  Use `Vec<Option<Coord>>` to
+    /// decode the reply, missing members have no position.
+    fn geo_pos<K: ToRedisArgs, M: ToRedisArgs>(key: K, members: M) {
+        cmd("GEOPOS").arg(key).arg(members)
+    }
+
+    /// Returns the distance between two members in the given unit or nil
+    /// if one of them is missing.
+    fn geo_dist<K: ToRedisArgs, M1: ToRedisArgs, M2: ToRedisArgs>(
+            key: K, member1: M1, member2: M2, unit: Unit) {
+        cmd("GEODIST").arg(key).arg(member1).arg(member2).arg(unit)
+    }
+
+    /// Returns the members within an area.  Use `Vec<GeoSearchResult>`
+    /// to decode the reply.
+    fn geo_search<K: ToRedisArgs>(key: K, search: GeoSearch) {
+        cmd("GEOSEARCH").arg(key).arg(search)
+    }
+
+    /// Stores the members within an area of a geo set in another one and
+    /// returns their number.  The `with_*` options of the search are not
+    /// supported here.
+    fn geo_search_store<D: ToRedisArgs, K: ToRedisArgs>(dstkey: D, key: K, search: GeoSearch) {
+        cmd("GEOSEARCHSTORE").arg(dstkey).arg(key).arg(search)
+    }
+
+    /// Stores the members within an area like `geo_search_store` with
+    /// their distance to the center as score instead of their position.
+    fn geo_search_store_dist<D: ToRedisArgs, K: ToRedisArgs>(
+            dstkey: D, key: K, search: GeoSearch) {
+        cmd("GEOSEARCHSTORE").arg(dstkey).arg(key).arg(search).arg("STOREDIST")
+    }
+}
+
+/// Converts a timeout to the fractional seconds the blocking commands
+/// expect.
+fn timeout_secs(timeout: Duration) -> f64 {
+    timeout.as_secs() as f64 + timeout.subsec_nanos() as f64 / 1_000_000_000.0
+}
+
+fn timeout_ms(timeout: Duration) -> u64 {
+    timeout.as_secs() * 1000 + timeout.subsec_nanos() as u64 / 1_000_000
 }
 
 impl Commands for Connection {}
 impl Commands for Client {}
+impl Commands for ClusterConnection {}
+impl Commands for SentinelConnection {}
 
 impl PipelineCommands for Pipeline {
     fn perform(&mut self, cmd: &Cmd) -> &mut Pipeline {
@@ -1,15 +1,22 @@
 use std::path::PathBuf;
-use std::io::{Read, BufReader, Write};
-use std::net::{self, TcpStream};
+use std::io::{self, Read, BufRead, BufReader, BufWriter, Write};
+use std::net::{self, IpAddr, SocketAddr, TcpStream, ToSocketAddrs};
 use std::str::from_utf8;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::HashSet;
-use std::time::Duration;
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use url;
+use socket2::{Domain, Protocol, SockRef, Socket, TcpKeepalive, Type};
 
-use cmd::{cmd, pipe, Pipeline};
-use types::{RedisResult, Value, ToRedisArgs, FromRedisValue, from_redis_value, ErrorKind};
+use cmd::{cmd, pipe, Cmd, Pipeline, pack_command_with_trailing_arg};
+use types::{RedisResult, Value, ToRedisArgs, FromRedisValue, from_redis_value,
+            from_owned_redis_value, ErrorKind, is_server_error};
 use parser::Parser;
 
 #[cfg(feature="with-unix-sockets")]
@@ -17,9 +24,16 @@ use unix_socket::UnixStream;
 #[cfg(all(feature="with-system-unix-sockets", not(feature="with-unix-sockets")))]
 use std::os::unix::net::UnixStream;
 
+#[cfg(any(feature="tls", feature="tls-rustls"))]
+use tls::{self, TlsStream};
+
 
 static DEFAULT_PORT: u16 = 6379;
 
+/// How long a connection attempt gets before the next address is tried
+/// in parallel when a host has both IPv6 and IPv4 addresses.
+static HAPPY_EYEBALLS_DELAY_MS: u64 = 250;
+
 /// This function takes a redis URL string and parses it into a URL
 /// as used by rust-url.  This is necessary as the default parser does
 /// not understand how redis URLs function.
@@ -27,7 +41,7 @@ pub fn parse_redis_url(input: &str) -> Result<url::Url, ()> {
     match url::Url::parse(input) {
         Ok(result) => {
             match result.scheme() {
-                "redis" | "redis+unix" | "unix" => Ok(result),
+                "redis" | "rediss" | "redis+unix" | "unix" => Ok(result),
                 _ => Err(()),
             }
         }
@@ -40,10 +54,18 @@ pub fn parse_redis_url(input: &str) -> Result<url::Url, ()> {
 /// Not all connection addresses are supported on all platforms.  For instance
 /// to connect to a unix socket you need to run this on an operating system
 /// that supports them.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum ConnectionAddr {
     /// Format for this is `(host, port)`.
     Tcp(String, u16),
+    /// Format for this is `(host, port)` but the connection is encrypted
+    /// with TLS.  If `insecure` is set the certificate and hostname of the
+    /// server are not verified.
+    TcpTls {
+        host: String,
+        port: u16,
+        insecure: bool,
+    },
     /// Format for this is the path to the unix socket.
     Unix(PathBuf),
 }
@@ -57,6 +79,7 @@ impl ConnectionAddr {
     pub fn is_supported(&self) -> bool {
         match *self {
             ConnectionAddr::Tcp(_, _) => true,
+            ConnectionAddr::TcpTls { .. } => cfg!(any(feature="tls", feature="tls-rustls")),
             #[cfg(any(feature="with-unix-sockets", feature="with-system-unix-sockets"))]
             ConnectionAddr::Unix(_) => true,
             #[cfg(not(any(feature="with-unix-sockets", feature="with-system-unix-sockets")))]
@@ -65,16 +88,402 @@ impl ConnectionAddr {
     }
 }
 
+/// The version of the redis protocol a connection speaks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProtocolVersion {
+    /// The classic protocol understood by all redis versions.
+    RESP2,
+    /// The RESP3 protocol which is negotiated with `HELLO 3` when
+    /// connecting.  This requires redis 6 or later.
+    RESP3,
+}
+
+/// Certificates for TLS connections.  All certificates and keys are
+/// given in PEM format.
+///
+/// A client certificate and key are needed for servers that require
+/// mutual TLS.  If a root certificate is given, the server certificate
+/// is verified against it instead of the system's trust store.
+#[derive(Clone, Default)]
+pub struct TlsCertificates {
+    /// The certificate presented to the server.
+    pub client_cert: Option<Vec<u8>>,
+    /// The private key of the client certificate.  The `tls` feature
+    /// (native-tls) only accepts PKCS#8 keys (`BEGIN PRIVATE KEY`), PKCS#1
+    /// keys (`BEGIN RSA PRIVATE KEY`) are only supported by `tls-rustls`
+    /// and have to be converted with `openssl pkcs8 -topk8 -nocrypt`
+    /// otherwise.
+    pub client_key: Option<Vec<u8>>,
+    /// A bundle of CA certificates to verify the server with.
+    pub root_cert: Option<Vec<u8>>,
+}
+
+impl fmt::Debug for TlsCertificates {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TlsCertificates")
+            .field("client_cert", &self.client_cert.is_some())
+            .field("client_key", &self.client_key.is_some())
+            .field("root_cert", &self.root_cert.is_some())
+            .finish()
+    }
+}
+
+/// Receives notifications about the lifecycle of connections, for
+/// instance to feed them into logging or alerting.  All methods do
+/// nothing by default so implementors only need to override the events
+/// they are interested in.
+///
+/// The callbacks are invoked synchronously on the thread that uses the
+/// connection and should therefore return quickly.
+pub trait ConnectionEvents: Send + Sync {
+    /// Called after a connection was established and set up.
+    fn on_connect(&self, _addr: &ConnectionAddr) {}
+
+    /// Called when a connection was lost or closed.
+    fn on_disconnect(&self, _addr: &ConnectionAddr) {}
+
+    /// Called before a connection attempts to reconnect.
+    fn on_reconnect_attempt(&self, _addr: &ConnectionAddr) {}
+
+    /// Called when the server rejected the credentials.
+    fn on_auth_failed(&self, _addr: &ConnectionAddr) {}
+}
+
 
 /// Holds the connection information that redis should use for connecting.
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct ConnectionInfo {
     /// A boxed connection address for where to connect to.
     pub addr: Box<ConnectionAddr>,
+    /// Addresses that are tried in order if connecting to `addr` fails.
+    /// This is useful for simple active/passive setups.
+    pub fallback_addrs: Vec<ConnectionAddr>,
     /// The database number to use.  This is usually `0`.
     pub db: i64,
+    /// Optionally the ACL username to authenticate as.  This requires
+    /// redis 6 or later and is only used together with a password.
+    pub username: Option<String>,
     /// Optionally a password that should be used for connection.
     pub passwd: Option<String>,
+    /// Optionally the maximum time establishing the connection may take.
+    /// If this is `None` connecting blocks until the operating system
+    /// gives up.
+    pub connect_timeout: Option<Duration>,
+    /// If set, TCP keepalive probes are sent after the connection has been
+    /// idle for this long.  This keeps connections through NATs and
+    /// firewalls from being dropped silently.
+    pub tcp_keepalive: Option<Duration>,
+    /// Enables `TCP_NODELAY` which disables Nagle's algorithm.
+    pub tcp_nodelay: bool,
+    /// Optionally the local IP address TCP connections are bound to.  On
+    /// hosts with several interfaces this picks the one that is used.
+    pub local_addr: Option<IpAddr>,
+    /// Optionally the capacity of the buffer responses are read into.  If
+    /// this is `None` the default of 8 KiB is used.  Larger buffers mean
+    /// fewer reads for big values, smaller ones save memory with many
+    /// idle connections.
+    pub read_buffer_size: Option<usize>,
+    /// Optionally the capacity of the buffer requests are written through.
+    /// Commands are packed in one piece and written at once, so this only
+    /// sets the size of the chunks arguments streamed with
+    /// `Connection::query_with_reader` are written in.  If this is `None`
+    /// the default of 8 KiB is used.
+    pub write_buffer_size: Option<usize>,
+    /// The protocol version to speak.  This is usually `RESP2`.
+    pub protocol: ProtocolVersion,
+    /// Optionally a name that is set with `CLIENT SETNAME` after
+    /// connecting.  It shows up in the output of `CLIENT LIST`.
+    pub client_name: Option<String>,
+    /// Optionally a handler that is notified about connection events.
+    pub events: Option<Arc<ConnectionEvents>>,
+    /// Optionally certificates for `TcpTls` connections.
+    pub tls_certificates: Option<TlsCertificates>,
+}
+
+impl fmt::Debug for ConnectionInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ConnectionInfo")
+            .field("addr", &self.addr)
+            .field("fallback_addrs", &self.fallback_addrs)
+            .field("db", &self.db)
+            .field("username", &self.username)
+            .field("passwd", &self.passwd)
+            .field("connect_timeout", &self.connect_timeout)
+            .field("tcp_keepalive", &self.tcp_keepalive)
+            .field("tcp_nodelay", &self.tcp_nodelay)
+            .field("local_addr", &self.local_addr)
+            .field("read_buffer_size", &self.read_buffer_size)
+            .field("write_buffer_size", &self.write_buffer_size)
+            .field("protocol", &self.protocol)
+            .field("client_name", &self.client_name)
+            .field("events", &self.events.is_some())
+            .field("tls_certificates", &self.tls_certificates)
+            .finish()
+    }
+}
+
+impl ConnectionInfo {
+    /// Returns a builder for a connection info.  This is a more convenient
+    /// way to configure a connection than filling in the struct by hand:
+    ///
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    ///
+    /// let info = redis::ConnectionInfo::builder()
+    ///     .host("127.0.0.1")
+    ///     .db(2)
+    ///     .connect_timeout(Duration::from_secs(5))
+    ///     .build()
+    ///     .unwrap();
+    /// let client = redis::Client::open(info).unwrap();
+    /// ```
+    pub fn builder() -> ConnectionInfoBuilder {
+        ConnectionInfoBuilder::new()
+    }
+}
+
+/// Incrementally builds a `ConnectionInfo`.
+///
+/// The builder checks the configuration for consistency when `build` is
+/// called so that obvious mistakes surface before the first connection
+/// attempt.
+#[derive(Clone)]
+pub struct ConnectionInfoBuilder {
+    addr: Option<ConnectionAddr>,
+    fallback_addrs: Vec<ConnectionAddr>,
+    host: Option<String>,
+    port: u16,
+    tls: Option<bool>,
+    socket_path: Option<PathBuf>,
+    db: i64,
+    username: Option<String>,
+    passwd: Option<String>,
+    connect_timeout: Option<Duration>,
+    tcp_keepalive: Option<Duration>,
+    tcp_nodelay: bool,
+    local_addr: Option<IpAddr>,
+    read_buffer_size: Option<usize>,
+    write_buffer_size: Option<usize>,
+    protocol: ProtocolVersion,
+    client_name: Option<String>,
+    events: Option<Arc<ConnectionEvents>>,
+    tls_certificates: Option<TlsCertificates>,
+}
+
+impl ConnectionInfoBuilder {
+    /// Creates a new empty builder.  At least a host or a socket path
+    /// needs to be set before building.
+    pub fn new() -> ConnectionInfoBuilder {
+        ConnectionInfoBuilder {
+            addr: None,
+            fallback_addrs: vec![],
+            host: None,
+            port: DEFAULT_PORT,
+            tls: None,
+            socket_path: None,
+            db: 0,
+            username: None,
+            passwd: None,
+            connect_timeout: None,
+            tcp_keepalive: None,
+            tcp_nodelay: false,
+            local_addr: None,
+            read_buffer_size: None,
+            write_buffer_size: None,
+            protocol: ProtocolVersion::RESP2,
+            client_name: None,
+            events: None,
+            tls_certificates: None,
+        }
+    }
+
+    /// Sets the connection address directly.  This takes precedence over
+    /// `host`, `port`, `tls` and `unix`.
+    pub fn addr(&mut self, addr: ConnectionAddr) -> &mut ConnectionInfoBuilder {
+        self.addr = Some(addr);
+        self
+    }
+
+    /// Adds an address that is tried if connecting to the primary address
+    /// fails.  Fallback addresses are tried in the order they are added.
+    pub fn fallback_addr(&mut self, addr: ConnectionAddr) -> &mut ConnectionInfoBuilder {
+        self.fallback_addrs.push(addr);
+        self
+    }
+
+    /// Sets the host to connect to over TCP.
+    pub fn host(&mut self, host: &str) -> &mut ConnectionInfoBuilder {
+        self.host = Some(host.to_string());
+        self
+    }
+
+    /// Sets the TCP port.  This defaults to `6379`.
+    pub fn port(&mut self, port: u16) -> &mut ConnectionInfoBuilder {
+        self.port = port;
+        self
+    }
+
+    /// Encrypts the TCP connection with TLS.  If `insecure` is set the
+    /// certificate of the server is not verified.
+    pub fn tls(&mut self, insecure: bool) -> &mut ConnectionInfoBuilder {
+        self.tls = Some(insecure);
+        self
+    }
+
+    /// Connects to the unix socket at the given path instead of a host.
+    pub fn unix<P: Into<PathBuf>>(&mut self, path: P) -> &mut ConnectionInfoBuilder {
+        self.socket_path = Some(path.into());
+        self
+    }
+
+    /// Sets the database number to select.
+    pub fn db(&mut self, db: i64) -> &mut ConnectionInfoBuilder {
+        self.db = db;
+        self
+    }
+
+    /// Sets the ACL username.  This needs a password as well.
+    pub fn username(&mut self, username: &str) -> &mut ConnectionInfoBuilder {
+        self.username = Some(username.to_string());
+        self
+    }
+
+    /// Sets the password to authenticate with.
+    pub fn password(&mut self, passwd: &str) -> &mut ConnectionInfoBuilder {
+        self.passwd = Some(passwd.to_string());
+        self
+    }
+
+    /// Sets the maximum time establishing the connection may take.
+    pub fn connect_timeout(&mut self, timeout: Duration) -> &mut ConnectionInfoBuilder {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Enables TCP keepalive probes after the given idle time.
+    pub fn tcp_keepalive(&mut self, idle: Duration) -> &mut ConnectionInfoBuilder {
+        self.tcp_keepalive = Some(idle);
+        self
+    }
+
+    /// Enables or disables `TCP_NODELAY`.
+    pub fn tcp_nodelay(&mut self, nodelay: bool) -> &mut ConnectionInfoBuilder {
+        self.tcp_nodelay = nodelay;
+        self
+    }
+
+    /// Binds TCP connections to the given local IP address.
+    pub fn local_addr(&mut self, addr: IpAddr) -> &mut ConnectionInfoBuilder {
+        self.local_addr = Some(addr);
+        self
+    }
+
+    /// Sets the capacity of the buffer responses are read into.
+    pub fn read_buffer_size(&mut self, size: usize) -> &mut ConnectionInfoBuilder {
+        self.read_buffer_size = Some(size);
+        self
+    }
+
+    /// Sets the capacity of the buffer requests are written through.
+    pub fn write_buffer_size(&mut self, size: usize) -> &mut ConnectionInfoBuilder {
+        self.write_buffer_size = Some(size);
+        self
+    }
+
+    /// Sets the protocol version to speak.
+    pub fn protocol(&mut self, protocol: ProtocolVersion) -> &mut ConnectionInfoBuilder {
+        self.protocol = protocol;
+        self
+    }
+
+    /// Sets the name that is registered with `CLIENT SETNAME`.
+    pub fn client_name(&mut self, name: &str) -> &mut ConnectionInfoBuilder {
+        self.client_name = Some(name.to_string());
+        self
+    }
+
+    /// Sets the certificates for TLS connections.  This is needed for
+    /// mutual TLS or servers with certificates from a private CA.
+    pub fn tls_certificates(&mut self, certs: TlsCertificates) -> &mut ConnectionInfoBuilder {
+        self.tls_certificates = Some(certs);
+        self
+    }
+
+    /// Sets the handler that is notified about connection events.
+    pub fn events(&mut self, events: Arc<ConnectionEvents>) -> &mut ConnectionInfoBuilder {
+        self.events = Some(events);
+        self
+    }
+
+    /// Validates the configuration and returns the connection info.
+    pub fn build(&self) -> RedisResult<ConnectionInfo> {
+        let addr = match (self.addr.clone(), self.host.clone(), self.socket_path.clone()) {
+            (Some(addr), _, _) => addr,
+            (None, Some(_), Some(_)) => {
+                fail!((ErrorKind::InvalidClientConfig,
+                       "Cannot connect to both a host and a unix socket"));
+            }
+            (None, Some(host), None) => {
+                match self.tls {
+                    Some(insecure) => {
+                        ConnectionAddr::TcpTls {
+                            host: host,
+                            port: self.port,
+                            insecure: insecure,
+                        }
+                    }
+                    None => ConnectionAddr::Tcp(host, self.port),
+                }
+            }
+            (None, None, Some(path)) => {
+                if self.tls.is_some() {
+                    fail!((ErrorKind::InvalidClientConfig,
+                           "TLS is not supported for unix sockets"));
+                }
+                ConnectionAddr::Unix(path)
+            }
+            (None, None, None) => {
+                fail!((ErrorKind::InvalidClientConfig, "Missing hostname or socket path"));
+            }
+        };
+        if !addr.is_supported() {
+            fail!((ErrorKind::InvalidClientConfig,
+                   "Connection address is not supported by this build"));
+        }
+        if let Some(ref certs) = self.tls_certificates {
+            match addr {
+                ConnectionAddr::TcpTls { .. } => {}
+                _ => {
+                    fail!((ErrorKind::InvalidClientConfig,
+                           "TLS certificates given for a connection without TLS"));
+                }
+            }
+            if certs.client_cert.is_some() != certs.client_key.is_some() {
+                fail!((ErrorKind::InvalidClientConfig,
+                       "A client certificate requires a key and vice versa"));
+            }
+        }
+        if self.username.is_some() && self.passwd.is_none() {
+            fail!((ErrorKind::InvalidClientConfig, "A username requires a password"));
+        }
+        Ok(ConnectionInfo {
+            addr: Box::new(addr),
+            fallback_addrs: self.fallback_addrs.clone(),
+            db: self.db,
+            username: self.username.clone(),
+            passwd: self.passwd.clone(),
+            connect_timeout: self.connect_timeout,
+            tcp_keepalive: self.tcp_keepalive,
+            tcp_nodelay: self.tcp_nodelay,
+            local_addr: self.local_addr,
+            read_buffer_size: self.read_buffer_size,
+            write_buffer_size: self.write_buffer_size,
+            protocol: self.protocol,
+            client_name: self.client_name.clone(),
+            events: self.events.clone(),
+            tls_certificates: self.tls_certificates.clone(),
+        })
+    }
 }
 
 /// Converts an object into a connection info struct.  This allows the
@@ -92,23 +501,144 @@ impl IntoConnectionInfo for ConnectionInfo {
 
 impl<'a> IntoConnectionInfo for &'a str {
     fn into_connection_info(self) -> RedisResult<ConnectionInfo> {
-        match parse_redis_url(self) {
-            Ok(u) => u.into_connection_info(),
+        let (primary, fallbacks) = split_fallback_hosts(self);
+        let mut info = match parse_redis_url(&primary) {
+            Ok(u) => try!(u.into_connection_info()),
             Err(_) => fail!((ErrorKind::InvalidClientConfig, "Redis URL did not parse")),
+        };
+        for host in fallbacks {
+            let addr = try!(fallback_addr(&info.addr, host));
+            info.fallback_addrs.push(addr);
         }
+        Ok(info)
     }
 }
 
+/// Splits a URL with a comma separated host list like
+/// `redis://host1:6379,host2:6380/` into the URL for the first host and
+/// the remaining hosts.
+fn split_fallback_hosts(input: &str) -> (String, Vec<&str>) {
+    let start = match input.find("://") {
+        Some(idx) => idx + 3,
+        None => return (input.to_string(), vec![]),
+    };
+    let end = input[start..]
+        .find(|c| c == '/' || c == '?' || c == '#')
+        .map_or(input.len(), |idx| start + idx);
+    let hosts_start = input[start..end].rfind('@').map_or(start, |idx| start + idx + 1);
+    let mut hosts = input[hosts_start..end].split(',');
+    let primary = hosts.next().unwrap_or("");
+    (format!("{}{}{}", &input[..hosts_start], primary, &input[end..]), hosts.collect())
+}
+
+/// Builds the address of a fallback host.  It uses the same transport
+/// as the primary address.
+fn fallback_addr(primary: &ConnectionAddr, host: &str) -> RedisResult<ConnectionAddr> {
+    let url = match url::Url::parse(&format!("redis://{}", host)) {
+        Ok(url) => url,
+        Err(_) => fail!((ErrorKind::InvalidClientConfig, "Invalid fallback host")),
+    };
+    let host = match url.host() {
+        Some(host) => host.to_string(),
+        None => fail!((ErrorKind::InvalidClientConfig, "Missing hostname")),
+    };
+    let port = url.port().unwrap_or(DEFAULT_PORT);
+    Ok(match *primary {
+        ConnectionAddr::Tcp(..) => ConnectionAddr::Tcp(host, port),
+        ConnectionAddr::TcpTls { insecure, .. } => {
+            ConnectionAddr::TcpTls {
+                host: host,
+                port: port,
+                insecure: insecure,
+            }
+        }
+        ConnectionAddr::Unix(_) => {
+            fail!((ErrorKind::InvalidClientConfig,
+                   "Fallback hosts are not supported for unix sockets"));
+        }
+    })
+}
+
+fn parse_duration(value: &str) -> Option<Duration> {
+    if value.ends_with("ms") {
+        value[..value.len() - 2].parse().ok().map(Duration::from_millis)
+    } else if value.ends_with('s') {
+        value[..value.len() - 1].parse().ok().map(Duration::from_secs)
+    } else {
+        value.parse().ok().map(Duration::from_secs)
+    }
+}
+
+/// Applies the options given in the query string of a URL to a parsed
+/// connection info.  Unknown options are ignored.
+fn apply_url_options(info: &mut ConnectionInfo, url: &url::Url) -> RedisResult<()> {
+    for (key, value) in url.query_pairs() {
+        match &*key {
+            "db" => {
+                info.db = unwrap_or!(value.parse::<i64>().ok(),
+                                     fail!((ErrorKind::InvalidClientConfig,
+                                            "Invalid database number")));
+            }
+            "client_name" => {
+                info.client_name = Some(value.into_owned());
+            }
+            "connect_timeout" => {
+                info.connect_timeout = Some(unwrap_or!(parse_duration(&value),
+                                                       fail!((ErrorKind::InvalidClientConfig,
+                                                              "Invalid connect timeout"))));
+            }
+            "protocol" => {
+                info.protocol = match &*value {
+                    "2" | "resp2" => ProtocolVersion::RESP2,
+                    "3" | "resp3" => ProtocolVersion::RESP3,
+                    _ => fail!((ErrorKind::InvalidClientConfig, "Invalid protocol version")),
+                };
+            }
+            "tls" => {
+                let insecure = match &*value {
+                    "true" => false,
+                    "insecure" => true,
+                    _ => fail!((ErrorKind::InvalidClientConfig, "Invalid tls option")),
+                };
+                let addr = match *info.addr {
+                    ConnectionAddr::Tcp(ref host, port) |
+                    ConnectionAddr::TcpTls { ref host, port, .. } => {
+                        ConnectionAddr::TcpTls {
+                            host: host.clone(),
+                            port: port,
+                            insecure: insecure,
+                        }
+                    }
+                    ConnectionAddr::Unix(_) => {
+                        fail!((ErrorKind::InvalidClientConfig,
+                               "TLS is not supported for unix sockets"));
+                    }
+                };
+                info.addr = Box::new(addr);
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
 fn url_to_tcp_connection_info(url: url::Url) -> RedisResult<ConnectionInfo> {
-    Ok(ConnectionInfo {
-        addr: Box::new(ConnectionAddr::Tcp(match url.host() {
-                                               Some(host) => host.to_string(),
-                                               None => {
-                                                   fail!((ErrorKind::InvalidClientConfig,
-                                                          "Missing hostname"))
-                                               }
-                                           },
-                                           url.port().unwrap_or(DEFAULT_PORT))),
+    let host = match url.host() {
+        Some(host) => host.to_string(),
+        None => fail!((ErrorKind::InvalidClientConfig, "Missing hostname")),
+    };
+    let port = url.port().unwrap_or(DEFAULT_PORT);
+    let mut info = ConnectionInfo {
+        fallback_addrs: vec![],
+        addr: Box::new(if url.scheme() == "rediss" {
+            ConnectionAddr::TcpTls {
+                host: host,
+                port: port,
+                insecure: url.fragment() == Some("insecure"),
+            }
+        } else {
+            ConnectionAddr::Tcp(host, port)
+        }),
         db: match url.path().trim_matches('/') {
             "" => 0,
             path => {
@@ -116,25 +646,52 @@ fn url_to_tcp_connection_info(url: url::Url) -> RedisResult<ConnectionInfo> {
                            fail!((ErrorKind::InvalidClientConfig, "Invalid database number")))
             }
         },
+        username: match url.username() {
+            "" => None,
+            username => Some(username.to_string()),
+        },
         passwd: url.password().and_then(|pw| Some(pw.to_string())),
-    })
+        connect_timeout: None,
+        tcp_keepalive: None,
+        tcp_nodelay: false,
+        local_addr: None,
+        read_buffer_size: None,
+        write_buffer_size: None,
+        protocol: ProtocolVersion::RESP2,
+        client_name: None,
+        events: None,
+        tls_certificates: None,
+    };
+    try!(apply_url_options(&mut info, &url));
+    Ok(info)
 }
 
 #[cfg(any(feature="with-unix-sockets", feature="with-system-unix-sockets"))]
 fn url_to_unix_connection_info(url: url::Url) -> RedisResult<ConnectionInfo> {
-    Ok(ConnectionInfo {
+    let mut info = ConnectionInfo {
         addr: Box::new(ConnectionAddr::Unix(unwrap_or!(url.to_file_path().ok(),
                                                        fail!((ErrorKind::InvalidClientConfig,
                                                               "Missing path"))))),
-        db: match url.query_pairs().into_iter().filter(|&(ref key, _)| key == "db").next() {
-            Some((_, db)) => {
-                unwrap_or!(db.parse::<i64>().ok(),
-                           fail!((ErrorKind::InvalidClientConfig, "Invalid database number")))
-            }
-            None => 0,
+        fallback_addrs: vec![],
+        db: 0,
+        username: match url.username() {
+            "" => None,
+            username => Some(username.to_string()),
         },
         passwd: url.password().and_then(|pw| Some(pw.to_string())),
-    })
+        connect_timeout: None,
+        tcp_keepalive: None,
+        tcp_nodelay: false,
+        local_addr: None,
+        read_buffer_size: None,
+        write_buffer_size: None,
+        protocol: ProtocolVersion::RESP2,
+        client_name: None,
+        events: None,
+        tls_certificates: None,
+    };
+    try!(apply_url_options(&mut info, &url));
+    Ok(info)
 }
 
 #[cfg(not(any(feature="with-unix-sockets", feature="with-system-unix-sockets")))]
@@ -144,7 +701,7 @@ fn url_to_unix_connection_info(_: url::Url) -> RedisResult<ConnectionInfo> {
 
 impl IntoConnectionInfo for url::Url {
     fn into_connection_info(self) -> RedisResult<ConnectionInfo> {
-        if self.scheme() == "redis" {
+        if self.scheme() == "redis" || self.scheme() == "rediss" {
             url_to_tcp_connection_info(self)
         } else if self.scheme() == "unix" || self.scheme() == "redis+unix" {
             url_to_unix_connection_info(self)
@@ -155,16 +712,59 @@ impl IntoConnectionInfo for url::Url {
 }
 
 
+/// A stream a connection can run over instead of the sockets redis-rs
+/// opens itself.  This allows connections over transports the library
+/// does not know about, like TLS terminated by another library or an
+/// in-memory stream in tests.
+///
+/// Timeouts and shutdown are optional; by default setting a timeout
+/// fails and shutting down does nothing.
+pub trait RedisTransport: Read + Write + Send {
+    /// Sets the read timeout of the stream.
+    fn set_read_timeout(&self, _dur: Option<Duration>) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "transport does not support timeouts"))
+    }
+
+    /// Sets the write timeout of the stream.
+    fn set_write_timeout(&self, _dur: Option<Duration>) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "transport does not support timeouts"))
+    }
+
+    /// Shuts down the stream after a protocol error.
+    fn shutdown(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl RedisTransport for TcpStream {
+    fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        TcpStream::set_read_timeout(self, dur)
+    }
+
+    fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        TcpStream::set_write_timeout(self, dur)
+    }
+
+    fn shutdown(&mut self) -> io::Result<()> {
+        TcpStream::shutdown(self, net::Shutdown::Both)
+    }
+}
+
 enum ActualConnection {
     Tcp(BufReader<TcpStream>),
+    #[cfg(any(feature="tls", feature="tls-rustls"))]
+    TcpTls(BufReader<Box<TlsStream>>),
     #[cfg(any(feature="with-unix-sockets", feature="with-system-unix-sockets"))]
-    Unix(UnixStream),
+    Unix(BufReader<UnixStream>),
+    Custom(BufReader<Box<RedisTransport>>),
 }
 
 /// Represents a stateful redis TCP connection.
 pub struct Connection {
     con: RefCell<ActualConnection>,
-    db: i64,
+    db: Cell<i64>,
+    info: ConnectionInfo,
+    open: Cell<bool>,
 }
 
 /// Represents a pubsub connection.
@@ -172,6 +772,23 @@ pub struct PubSub {
     con: Connection,
     channels: HashSet<Vec<u8>>,
     pchannels: HashSet<Vec<u8>>,
+    schannels: HashSet<Vec<u8>>,
+    read_timeout: Cell<Option<Duration>>,
+}
+
+/// Reads a bulk string reply piece by piece.  It is created with
+/// `Connection::query_reader`.
+pub struct BulkReader<'a> {
+    con: &'a mut ActualConnection,
+    remaining: usize,
+}
+
+/// An iterator over the messages of a pubsub connection with their
+/// payload converted to `T`.  It is created with `PubSub::into_typed_stream`.
+pub struct MsgIter<T> {
+    pubsub: PubSub,
+    done: bool,
+    marker: PhantomData<T>,
 }
 
 /// Represents a pubsub message.
@@ -179,20 +796,236 @@ pub struct Msg {
     payload: Value,
     channel: Value,
     pattern: Option<Value>,
+    resubscribed: bool,
+}
+
+/// Connects to a TCP address and applies the socket options of the
+/// connection info.
+fn connect_tcp(host: &str, port: u16, connection_info: &ConnectionInfo) -> io::Result<TcpStream> {
+    let tcp = try!(connect_tcp_addr(host,
+                                    port,
+                                    connection_info.connect_timeout,
+                                    connection_info.local_addr));
+    if connection_info.tcp_nodelay {
+        try!(tcp.set_nodelay(true));
+    }
+    if let Some(idle) = connection_info.tcp_keepalive {
+        try!(SockRef::from(&tcp).set_tcp_keepalive(&TcpKeepalive::new().with_time(idle)));
+    }
+    Ok(tcp)
+}
+
+/// Connects to a TCP address.  If a timeout is given all addresses the
+/// host resolves to are tried until the deadline expires.  If a local
+/// address is given only addresses of the same family are tried.
+///
+/// Hosts that resolve to both IPv6 and IPv4 addresses are connected to
+/// with the "happy eyeballs" approach of RFC 8305 so that an unroutable
+/// address family does not stall the connection.
+fn connect_tcp_addr(host: &str,
+                    port: u16,
+                    timeout: Option<Duration>,
+                    local_addr: Option<IpAddr>)
+                    -> io::Result<TcpStream> {
+    let addrs: Vec<SocketAddr> = try!((host, port).to_socket_addrs())
+        .filter(|addr| local_addr.map_or(true, |local| local.is_ipv4() == addr.is_ipv4()))
+        .collect();
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    if addrs.iter().any(|addr| addr.is_ipv4()) && addrs.iter().any(|addr| addr.is_ipv6()) {
+        return connect_happy_eyeballs(interleave_families(addrs), deadline, local_addr);
+    }
+    let mut last_err = None;
+    for addr in addrs {
+        let remaining = match remaining_time(deadline) {
+            Some(Some(remaining)) => Some(remaining),
+            Some(None) => return Err(timed_out()),
+            None => None,
+        };
+        match connect_socket(&addr, remaining, local_addr) {
+            Ok(tcp) => return Ok(tcp),
+            Err(err) => last_err = Some(err),
+        }
+    }
+    Err(last_err.unwrap_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput,
+                       "could not resolve to any addresses")
+    }))
+}
+
+/// Returns `None` without a deadline, otherwise the time left until the
+/// deadline or `Some(None)` if it passed already.
+fn remaining_time(deadline: Option<Instant>) -> Option<Option<Duration>> {
+    deadline.map(|deadline| {
+        let now = Instant::now();
+        if now >= deadline {
+            None
+        } else {
+            Some(deadline - now)
+        }
+    })
+}
+
+fn timed_out() -> io::Error {
+    io::Error::new(io::ErrorKind::TimedOut, "connection timed out")
+}
+
+/// Reorders addresses so that the address families alternate, starting
+/// with the family of the first address.
+fn interleave_families(addrs: Vec<SocketAddr>) -> Vec<SocketAddr> {
+    let first_is_ipv6 = addrs.first().map_or(false, |addr| addr.is_ipv6());
+    let (first, second): (Vec<SocketAddr>, Vec<SocketAddr>) =
+        addrs.into_iter().partition(|addr| addr.is_ipv6() == first_is_ipv6);
+    let mut rv = Vec::with_capacity(first.len() + second.len());
+    let mut first = first.into_iter();
+    let mut second = second.into_iter();
+    loop {
+        match (first.next(), second.next()) {
+            (None, None) => break,
+            (a, b) => {
+                rv.extend(a);
+                rv.extend(b);
+            }
+        }
+    }
+    rv
+}
+
+/// Starts a connection attempt for each address, each one either after
+/// the previous attempt failed or after a short delay, and returns the
+/// first connection that succeeds.
+fn connect_happy_eyeballs(addrs: Vec<SocketAddr>,
+                          deadline: Option<Instant>,
+                          local_addr: Option<IpAddr>)
+                          -> io::Result<TcpStream> {
+    let (tx, rx) = mpsc::channel();
+    let mut addrs = addrs.into_iter();
+    let mut pending = 0;
+    let mut last_err = None;
+    loop {
+        if let Some(addr) = addrs.next() {
+            let tx = tx.clone();
+            let timeout = match remaining_time(deadline) {
+                Some(Some(remaining)) => Some(remaining),
+                Some(None) => return Err(timed_out()),
+                None => None,
+            };
+            thread::spawn(move || {
+                // the receiver is gone if another attempt won already in
+                // which case this connection is simply dropped.
+                let _ = tx.send(connect_socket(&addr, timeout, local_addr));
+            });
+            pending += 1;
+        } else if pending == 0 {
+            break;
+        }
+
+        let delay = if addrs.len() > 0 {
+            Some(Duration::from_millis(HAPPY_EYEBALLS_DELAY_MS))
+        } else {
+            None
+        };
+        let wait = match (delay, remaining_time(deadline)) {
+            (_, Some(None)) => return Err(timed_out()),
+            (Some(delay), Some(Some(remaining))) => Some(::std::cmp::min(delay, remaining)),
+            (None, Some(Some(remaining))) => Some(remaining),
+            (delay, None) => delay,
+        };
+        let result = match wait {
+            Some(wait) => {
+                match rx.recv_timeout(wait) {
+                    Ok(result) => result,
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
+            None => {
+                match rx.recv() {
+                    Ok(result) => result,
+                    Err(_) => break,
+                }
+            }
+        };
+        pending -= 1;
+        match result {
+            Ok(tcp) => return Ok(tcp),
+            Err(err) => last_err = Some(err),
+        }
+    }
+    Err(last_err.unwrap_or_else(timed_out))
+}
+
+fn connect_socket(addr: &SocketAddr,
+                  timeout: Option<Duration>,
+                  local_addr: Option<IpAddr>)
+                  -> io::Result<TcpStream> {
+    let local_addr = match local_addr {
+        Some(local_addr) => local_addr,
+        None => {
+            return match timeout {
+                Some(timeout) => TcpStream::connect_timeout(addr, timeout),
+                None => TcpStream::connect(addr),
+            };
+        }
+    };
+    let socket = try!(Socket::new(Domain::for_address(*addr), Type::STREAM, Some(Protocol::TCP)));
+    try!(socket.bind(&SocketAddr::new(local_addr, 0).into()));
+    match timeout {
+        Some(timeout) => try!(socket.connect_timeout(&(*addr).into(), timeout)),
+        None => try!(socket.connect(&(*addr).into())),
+    }
+    Ok(socket.into())
+}
+
+/// Wraps a stream in a read buffer of the configured size.
+fn buffered<R: Read>(inner: R, connection_info: &ConnectionInfo) -> BufReader<R> {
+    match connection_info.read_buffer_size {
+        Some(capacity) => BufReader::with_capacity(capacity, inner),
+        None => BufReader::new(inner),
+    }
 }
 
 impl ActualConnection {
-    pub fn new(addr: &ConnectionAddr) -> RedisResult<ActualConnection> {
+    /// Connects to the address of the connection info and falls back to
+    /// the other addresses in order if that fails.
+    pub fn new(connection_info: &ConnectionInfo) -> RedisResult<ActualConnection> {
+        let mut result = ActualConnection::connect_addr(&connection_info.addr, connection_info);
+        for addr in connection_info.fallback_addrs.iter() {
+            if result.is_ok() {
+                break;
+            }
+            result = ActualConnection::connect_addr(addr, connection_info);
+        }
+        result
+    }
+
+    fn connect_addr(addr: &ConnectionAddr,
+                    connection_info: &ConnectionInfo)
+                    -> RedisResult<ActualConnection> {
         Ok(match *addr {
             ConnectionAddr::Tcp(ref host, ref port) => {
                 let host: &str = &*host;
-                let tcp = try!(TcpStream::connect((host, *port)));
-                let buffered = BufReader::new(tcp);
-                ActualConnection::Tcp(buffered)
+                let tcp = try!(connect_tcp(host, *port, connection_info));
+                ActualConnection::Tcp(buffered(tcp, connection_info))
+            }
+            #[cfg(any(feature="tls", feature="tls-rustls"))]
+            ConnectionAddr::TcpTls { ref host, port, insecure } => {
+                let connector = try!(tls::default_connector(insecure,
+                                                            connection_info.tls_certificates
+                                                                .as_ref()));
+                let host: &str = &*host;
+                let tcp = try!(connect_tcp(host, port, connection_info));
+                let tls = try!(connector.connect(host, tcp));
+                ActualConnection::TcpTls(buffered(tls, connection_info))
+            }
+            #[cfg(not(any(feature="tls", feature="tls-rustls")))]
+            ConnectionAddr::TcpTls { .. } => {
+                fail!((ErrorKind::InvalidClientConfig,
+                       "Cannot connect to TCP with TLS without \
+                       the tls or tls-rustls feature"));
             }
             #[cfg(any(feature="with-unix-sockets", feature="with-system-unix-sockets"))]
             ConnectionAddr::Unix(ref path) => {
-                ActualConnection::Unix(try!(UnixStream::connect(path)))
+                ActualConnection::Unix(buffered(try!(UnixStream::connect(path)), connection_info))
             }
             #[cfg(not(any(feature="with-unix-sockets", feature="with-system-unix-sockets")))]
             ConnectionAddr::Unix(ref path) => {
@@ -203,39 +1036,146 @@ impl ActualConnection {
         })
     }
 
-    pub fn send_bytes(&mut self, bytes: &[u8]) -> RedisResult<Value> {
-        let w = match *self {
+    fn writer(&mut self) -> &mut Write {
+        match *self {
             ActualConnection::Tcp(ref mut reader) => reader.get_mut() as &mut Write,
+            #[cfg(any(feature="tls", feature="tls-rustls"))]
+            ActualConnection::TcpTls(ref mut reader) => reader.get_mut() as &mut Write,
             #[cfg(any(feature="with-unix-sockets", feature="with-system-unix-sockets"))]
-            ActualConnection::Unix(ref mut sock) => &mut *sock as &mut Write,
-        };
-        try!(w.write(bytes));
+            ActualConnection::Unix(ref mut reader) => reader.get_mut() as &mut Write,
+            ActualConnection::Custom(ref mut reader) => reader.get_mut() as &mut Write,
+        }
+    }
+
+    pub fn send_bytes(&mut self, bytes: &[u8]) -> RedisResult<Value> {
+        try!(self.writer().write_all(bytes));
         Ok(Value::Okay)
     }
 
+    /// Sends the head of a command followed by exactly `len` bytes from the
+    /// reader and the closing newline, all through a buffer of the given
+    /// capacity.  If the reader fails or ends early the command cannot be
+    /// completed, so the connection is shut down.
+    fn send_from_reader(&mut self,
+                        head: &[u8],
+                        reader: &mut Read,
+                        len: u64,
+                        buffer_size: usize)
+                        -> RedisResult<()> {
+        let result = {
+            let mut writer = BufWriter::with_capacity(buffer_size, self.writer());
+            writer.write_all(head)
+                .and_then(|_| io::copy(&mut reader.take(len), &mut writer))
+                .and_then(|copied| if copied == len {
+                    writer.write_all(b"\r\n")
+                } else {
+                    Err(io::Error::new(io::ErrorKind::UnexpectedEof, "reader ended early"))
+                })
+                .and_then(|_| writer.flush())
+        };
+        if result.is_err() {
+            self.shutdown();
+        }
+        Ok(try!(result))
+    }
+
+    fn shutdown(&mut self) {
+        match *self {
+            ActualConnection::Tcp(ref mut reader) => {
+                let _ = TcpStream::shutdown(reader.get_mut(), net::Shutdown::Both);
+            }
+            #[cfg(any(feature="tls", feature="tls-rustls"))]
+            ActualConnection::TcpTls(ref mut reader) => {
+                let _ = reader.get_mut().shutdown();
+            }
+            #[cfg(any(feature="with-unix-sockets", feature="with-system-unix-sockets"))]
+            ActualConnection::Unix(ref mut reader) => {
+                let _ = reader.get_mut().shutdown(net::Shutdown::Both);
+            }
+            ActualConnection::Custom(ref mut reader) => {
+                let _ = reader.get_mut().shutdown();
+            }
+        }
+    }
+
+    fn buffered_reader(&mut self) -> &mut BufRead {
+        match *self {
+            ActualConnection::Tcp(ref mut reader) => reader,
+            #[cfg(any(feature="tls", feature="tls-rustls"))]
+            ActualConnection::TcpTls(ref mut reader) => reader,
+            #[cfg(any(feature="with-unix-sockets", feature="with-system-unix-sockets"))]
+            ActualConnection::Unix(ref mut reader) => reader,
+            ActualConnection::Custom(ref mut reader) => reader,
+        }
+    }
+
+    /// Reads the header of a bulk string reply and returns its length,
+    /// or `None` if the reply is nil.  Other replies are read completely
+    /// and turned into an error unless they are nil.
+    fn read_bulk_header(&mut self) -> RedisResult<Option<usize>> {
+        let is_bulk = try!(self.buffered_reader().fill_buf()).first() == Some(&b'$');
+        if !is_bulk {
+            return match try!(self.read_response()) {
+                Value::Nil => Ok(None),
+                _ => fail!((ErrorKind::TypeError, "Response is not a bulk string")),
+            };
+        }
+        let mut line = vec![];
+        try!(self.buffered_reader().read_until(b'\n', &mut line));
+        match from_utf8(&line[1..]).ok().and_then(|len| len.trim().parse::<i64>().ok()) {
+            Some(len) if len < 0 => Ok(None),
+            Some(len) => Ok(Some(len as usize)),
+            None => fail!((ErrorKind::ResponseError, "Expected integer, got garbage")),
+        }
+    }
+
     pub fn read_response(&mut self) -> RedisResult<Value> {
         let result = Parser::new(match *self {
                 ActualConnection::Tcp(ref mut reader) => reader as &mut Read,
+                #[cfg(any(feature="tls", feature="tls-rustls"))]
+                ActualConnection::TcpTls(ref mut reader) => reader as &mut Read,
                 #[cfg(any(feature="with-unix-sockets", feature="with-system-unix-sockets"))]
-            ActualConnection::Unix(ref mut sock) => &mut *sock as &mut Read,
+                ActualConnection::Unix(ref mut reader) => reader as &mut Read,
+                ActualConnection::Custom(ref mut reader) => reader as &mut Read,
             })
             .parse_value();
         // shutdown connection on protocol error
         match result {
-            Err(ref e) if e.kind() == ErrorKind::ResponseError => {
-                match *self {
-                    ActualConnection::Tcp(ref mut reader) => {
-                        let _ = reader.get_mut().shutdown(net::Shutdown::Both);
+            Err(ref e) if e.kind() == ErrorKind::ResponseError && !is_server_error(e) => {
+                self.shutdown();
+            }
+            _ => (),
+        }
+        result
+    }
+
+    /// Reads the replies of a pipeline and keeps the last `count` of them.
+    pub fn read_responses(&mut self, offset: usize, count: usize) -> RedisResult<Vec<Value>> {
+        // an error reply of the server does not stop the reading as the
+        // replies of the remaining commands are still coming.
+        let mut rv = vec![];
+        let mut first_err = None;
+        for idx in 0..(offset + count) {
+            match self.read_response() {
+                Ok(item) => {
+                    if idx >= offset {
+                        rv.push(item);
+                    }
+                }
+                Err(err) => {
+                    if !is_server_error(&err) {
+                        return Err(err);
                     }
-                    #[cfg(any(feature="with-unix-sockets", feature="with-system-unix-sockets"))]
-                    ActualConnection::Unix(ref mut sock) => {
-                        let _ = sock.shutdown(net::Shutdown::Both);
+                    if first_err.is_none() {
+                        first_err = Some(err);
                     }
                 }
             }
-            _ => (),
         }
-        result
+        match first_err {
+            Some(err) => Err(err),
+            None => Ok(rv),
+        }
     }
 
     pub fn set_write_timeout(&self, dur: Option<Duration>) -> RedisResult<()> {
@@ -243,9 +1183,16 @@ impl ActualConnection {
             ActualConnection::Tcp(ref reader) => {
                 try!(reader.get_ref().set_write_timeout(dur));
             }
+            #[cfg(any(feature="tls", feature="tls-rustls"))]
+            ActualConnection::TcpTls(ref reader) => {
+                try!(reader.get_ref().get_ref().set_write_timeout(dur));
+            }
             #[cfg(any(feature="with-unix-sockets", feature="with-system-unix-sockets"))]
-            ActualConnection::Unix(ref sock) => {
-                try!(sock.set_write_timeout(dur));
+            ActualConnection::Unix(ref reader) => {
+                try!(reader.get_ref().set_write_timeout(dur));
+            }
+            ActualConnection::Custom(ref reader) => {
+                try!(reader.get_ref().set_write_timeout(dur));
             }
         }
         Ok(())
@@ -256,9 +1203,16 @@ impl ActualConnection {
             ActualConnection::Tcp(ref reader) => {
                 try!(reader.get_ref().set_read_timeout(dur));
             }
+            #[cfg(any(feature="tls", feature="tls-rustls"))]
+            ActualConnection::TcpTls(ref reader) => {
+                try!(reader.get_ref().get_ref().set_read_timeout(dur));
+            }
             #[cfg(any(feature="with-unix-sockets", feature="with-system-unix-sockets"))]
-            ActualConnection::Unix(ref sock) => {
-                try!(sock.set_read_timeout(dur));
+            ActualConnection::Unix(ref reader) => {
+                try!(reader.get_ref().set_read_timeout(dur));
+            }
+            ActualConnection::Custom(ref reader) => {
+                try!(reader.get_ref().set_read_timeout(dur));
             }
         }
         Ok(())
@@ -267,32 +1221,80 @@ impl ActualConnection {
 
 
 pub fn connect(connection_info: &ConnectionInfo) -> RedisResult<Connection> {
-    let con = try!(ActualConnection::new(&connection_info.addr));
+    let con = try!(ActualConnection::new(connection_info));
+    connect_with(con, connection_info)
+}
+
+fn connect_with(con: ActualConnection, connection_info: &ConnectionInfo) -> RedisResult<Connection> {
     let rv = Connection {
         con: RefCell::new(con),
-        db: connection_info.db,
+        db: Cell::new(connection_info.db),
+        info: connection_info.clone(),
+        open: Cell::new(false),
     };
+    try!(rv.setup());
+    Ok(rv)
+}
 
-    match connection_info.passwd {
-        Some(ref passwd) => {
-            match cmd("AUTH").arg(&**passwd).query::<Value>(&rv) {
+/// Runs the handshake on a freshly opened connection: authentication,
+/// protocol negotiation, the client name and the database selection.
+fn setup_connection(con: &Connection, connection_info: &ConnectionInfo) -> RedisResult<()> {
+    match (connection_info.protocol, connection_info.passwd.as_ref()) {
+        (ProtocolVersion::RESP3, passwd) => {
+            // HELLO needs to carry the credentials as it is refused on
+            // connections that are not yet authenticated.
+            let mut hello = cmd("HELLO");
+            hello.arg(3);
+            if let Some(passwd) = passwd {
+                hello.arg("AUTH")
+                    .arg(connection_info.username.as_ref().map_or("default", |u| &**u))
+                    .arg(&**passwd);
+            }
+            match hello.query::<Value>(con) {
+                Ok(_) => {}
+                Err(ref err) if err.extension_error_code() == Some("WRONGPASS") => {
+                    fail!((ErrorKind::AuthenticationFailed, "Password authentication failed"));
+                }
+                Err(err) => {
+                    fail!((ErrorKind::ResponseError,
+                           "Redis server refused to switch to RESP3",
+                           err.to_string()));
+                }
+            }
+        }
+        (ProtocolVersion::RESP2, Some(passwd)) => {
+            let mut auth = cmd("AUTH");
+            if let Some(ref username) = connection_info.username {
+                auth.arg(&**username);
+            }
+            match auth.arg(&**passwd).query::<Value>(con) {
                 Ok(Value::Okay) => {}
                 _ => {
                     fail!((ErrorKind::AuthenticationFailed, "Password authentication failed"));
                 }
             }
         }
-        None => {}
+        (ProtocolVersion::RESP2, None) => {}
     }
 
-    if connection_info.db != 0 {
-        match cmd("SELECT").arg(connection_info.db).query::<Value>(&rv) {
+    if let Some(ref client_name) = connection_info.client_name {
+        match cmd("CLIENT").arg("SETNAME").arg(&**client_name).query::<Value>(con) {
+            Ok(Value::Okay) => {}
+            _ => fail!((ErrorKind::ResponseError, "Redis server refused to set client name")),
+        }
+    }
+
+    // the connection remembers the database it was switched to last so
+    // that a reconnect ends up in the same one.
+    let db = con.db.get();
+    if db != 0 {
+        match cmd("SELECT").arg(db).query::<Value>(con) {
             Ok(Value::Okay) => {}
             _ => fail!((ErrorKind::ResponseError, "Redis server refused to switch database")),
         }
     }
 
-    Ok(rv)
+    Ok(())
 }
 
 pub fn connect_pubsub(connection_info: &ConnectionInfo) -> RedisResult<PubSub> {
@@ -300,6 +1302,8 @@ pub fn connect_pubsub(connection_info: &ConnectionInfo) -> RedisResult<PubSub> {
         con: try!(connect(connection_info)),
         channels: HashSet::new(),
         pchannels: HashSet::new(),
+        schannels: HashSet::new(),
+        read_timeout: Cell::new(None),
     })
 }
 
@@ -332,6 +1336,12 @@ pub trait ConnectionLike {
     /// also might be incorrect if the connection like object is not
     /// actually connected.
     fn get_db(&self) -> i64;
+
+    /// Returns the address of the server the connection talks to.  For a
+    /// cluster this is the address of the first initial node.
+    fn get_addr(&self) -> Option<ConnectionAddr> {
+        None
+    }
 }
 
 
@@ -343,19 +1353,214 @@ pub trait ConnectionLike {
 /// You generally do not much with this object other than passing it to
 /// `Cmd` objects.
 impl Connection {
+    /// Creates a connection over an already established stream.  The
+    /// connection is set up like any other, which means authentication,
+    /// the client name and the database of the connection info are
+    /// applied.  The address of the connection info is only used to
+    /// report events.
+    ///
+    /// Such a connection cannot be reconnected.
+    pub fn new_with_stream<T: RedisTransport + 'static>(stream: T,
+                                                        connection_info: &ConnectionInfo)
+                                                        -> RedisResult<Connection> {
+        let stream: Box<RedisTransport> = Box::new(stream);
+        let con = ActualConnection::Custom(buffered(stream, connection_info));
+        connect_with(con, connection_info)
+    }
+
+    fn setup(&self) -> RedisResult<()> {
+        match setup_connection(self, &self.info) {
+            Ok(()) => {
+                self.open.set(true);
+                if let Some(ref events) = self.info.events {
+                    events.on_connect(&self.info.addr);
+                }
+                Ok(())
+            }
+            Err(err) => {
+                if err.kind() == ErrorKind::AuthenticationFailed {
+                    if let Some(ref events) = self.info.events {
+                        events.on_auth_failed(&self.info.addr);
+                    }
+                }
+                Err(err)
+            }
+        }
+    }
+
+    /// Reports a lost connection to the event handler the first time an
+    /// error indicates that the connection is gone.
+    fn check_dropped<T>(&self, result: RedisResult<T>) -> RedisResult<T> {
+        if let Err(ref err) = result {
+            if err.is_connection_dropped() {
+                self.notify_disconnect();
+            }
+        }
+        result
+    }
+
+    fn notify_disconnect(&self) {
+        if self.open.get() {
+            self.open.set(false);
+            if let Some(ref events) = self.info.events {
+                events.on_disconnect(&self.info.addr);
+            }
+        }
+    }
+
+    /// Switches the connection to another database.  Unlike sending a
+    /// `SELECT` command directly the connection remembers the database and
+    /// selects it again when reconnecting.
+    pub fn select(&self, db: i64) -> RedisResult<()> {
+        match cmd("SELECT").arg(db).query::<Value>(self) {
+            Ok(Value::Okay) => {
+                self.db.set(db);
+                Ok(())
+            }
+            Ok(_) => fail!((ErrorKind::ResponseError, "Redis server refused to switch database")),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Replaces the underlying socket with a fresh connection to the same
+    /// server and runs the connection setup again.  This includes selecting
+    /// the database the connection was last switched to.
+    pub fn reconnect(&self) -> RedisResult<()> {
+        if let ActualConnection::Custom(_) = *self.con.borrow() {
+            fail!((ErrorKind::InvalidClientConfig,
+                   "Cannot reconnect a connection over a custom transport"));
+        }
+        if let Some(ref events) = self.info.events {
+            events.on_reconnect_attempt(&self.info.addr);
+        }
+        let con = try!(ActualConnection::new(&self.info));
+        self.notify_disconnect();
+        *self.con.borrow_mut() = con;
+        self.setup()
+    }
+
+    /// Brings the connection back to the state it had right after
+    /// connecting, which is useful before handing it to someone else.
+    ///
+    /// This sends `RESET`, which discards a pending transaction, unwatches
+    /// all keys, ends subscriptions and `MONITOR` and turns off client
+    /// tracking.  Messages that were still on the way are dropped.  Then
+    /// the connection setup runs again so the connection is authenticated
+    /// and switched to the database of the connection info, not to the one
+    /// it was last switched to.
+    pub fn reset(&self) -> RedisResult<()> {
+        try!(self.send_packed_command(&cmd("RESET").get_packed_command()));
+        loop {
+            // commands that were still on the way may answer with errors,
+            // only a broken connection stops the draining.
+            match self.recv_response() {
+                Ok(Value::Status(ref status)) if status == "RESET" => break,
+                Ok(_) => {}
+                Err(err) => {
+                    if !is_server_error(&err) {
+                        return Err(err);
+                    }
+                }
+            }
+        }
+        self.db.set(self.info.db);
+        setup_connection(self, &self.info)
+    }
+
+    /// Closes the connection gracefully.  This sends `QUIT` and waits
+    /// for the server to acknowledge it before the socket is closed, so
+    /// the server sees a regular disconnect rather than a reset.
+    pub fn close(self) -> RedisResult<()> {
+        try!(cmd("QUIT").query::<Value>(&self));
+        Ok(())
+    }
+
+    /// Sends a command and returns a reader over its reply instead of
+    /// reading the reply into memory.  This is meant for huge bulk string
+    /// replies like those of `GET` or `DUMP`.  `None` is returned if the
+    /// reply is nil, other replies than bulk strings are type errors.
+    ///
+    /// ```rust,no_run
+    /// # let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+    /// # let mut con = client.get_connection().unwrap();
+    /// let mut file = std::fs::File::create("video.mp4").unwrap();
+    /// let reply = con.query_reader(redis::cmd("GET").arg("video")).unwrap();
+    /// if let Some(mut reader) = reply {
+    ///     std::io::copy(&mut reader, &mut file).unwrap();
+    /// }
+    /// ```
+    ///
+    /// The reader borrows the connection, so it cannot be used until the
+    /// reader is dropped.  Dropping the reader early reads and discards
+    /// the rest of the reply.
+    pub fn query_reader(&mut self, cmd: &Cmd) -> RedisResult<Option<BulkReader>> {
+        try!(self.send_packed_command(&cmd.get_packed_command()));
+        let header = self.con.get_mut().read_bulk_header();
+        match try!(self.check_dropped(header)) {
+            Some(len) => {
+                if len == 0 {
+                    // an empty value only is the newline
+                    let newline = skip_newline(self.con.get_mut()).map_err(From::from);
+                    try!(self.check_dropped(newline));
+                }
+                Ok(Some(BulkReader {
+                    con: self.con.get_mut(),
+                    remaining: len,
+                }))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Sends a command with one more argument that is read from `reader`
+    /// and returns the reply.  The argument comes after the arguments of
+    /// the command and is written to the socket in chunks, which allows
+    /// storing huge values without keeping them in memory.  The reader has
+    /// to provide `len` bytes, if it fails or ends early the connection is
+    /// shut down as the command cannot be completed.
+    ///
+    /// ```rust,no_run
+    /// # let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+    /// # let con = client.get_connection().unwrap();
+    /// let file = std::fs::File::open("video.mp4").unwrap();
+    /// let len = file.metadata().unwrap().len();
+    /// let _: () = con.query_with_reader(redis::cmd("SET").arg("video"), file, len).unwrap();
+    /// ```
+    pub fn query_with_reader<T: FromRedisValue, R: Read>(&self,
+                                                         cmd: &Cmd,
+                                                         mut reader: R,
+                                                         len: u64)
+                                                         -> RedisResult<T> {
+        let header = pack_command_with_trailing_arg(cmd, len);
+        let result = {
+            let mut con = self.con.borrow_mut();
+            let buffer_size = self.info.write_buffer_size.unwrap_or(8 * 1024);
+            con.send_from_reader(&header, &mut reader, len, buffer_size)
+                .and_then(|_| con.read_response())
+        };
+        from_owned_redis_value(try!(self.check_dropped(result)))
+    }
+
     /// Sends an already encoded (packed) command into the TCP socket and
     /// does not read a response.  This is useful for commands like
     /// `MONITOR` which yield multiple items.  This needs to be used with
     /// care because it changes the state of the connection.
     pub fn send_packed_command(&self, cmd: &[u8]) -> RedisResult<()> {
-        try!(self.con.borrow_mut().send_bytes(cmd));
+        try!(self.check_dropped(self.con.borrow_mut().send_bytes(cmd)));
         Ok(())
     }
 
     /// Fetches a single response from the connection.  This is useful
     /// if used in combination with `send_packed_command`.
     pub fn recv_response(&self) -> RedisResult<Value> {
-        self.con.borrow_mut().read_response()
+        self.check_dropped(self.con.borrow_mut().read_response())
+    }
+
+    /// Fetches the responses of `offset + count` commands and returns the
+    /// last `count` of them, like the replies of a transaction after
+    /// `send_packed_command`.
+    pub fn recv_responses(&self, offset: usize, count: usize) -> RedisResult<Vec<Value>> {
+        self.check_dropped(self.con.borrow_mut().read_responses(offset, count))
     }
 
     /// Sets the write timeout for the connection.
@@ -379,9 +1584,11 @@ impl Connection {
 
 impl ConnectionLike for Connection {
     fn req_packed_command(&self, cmd: &[u8]) -> RedisResult<Value> {
-        let mut con = self.con.borrow_mut();
-        try!(con.send_bytes(cmd));
-        con.read_response()
+        let result = {
+            let mut con = self.con.borrow_mut();
+            con.send_bytes(cmd).and_then(|_| con.read_response())
+        };
+        self.check_dropped(result)
     }
 
     fn req_packed_commands(&self,
@@ -389,20 +1596,64 @@ impl ConnectionLike for Connection {
                            offset: usize,
                            count: usize)
                            -> RedisResult<Vec<Value>> {
-        let mut con = self.con.borrow_mut();
-        try!(con.send_bytes(cmd));
-        let mut rv = vec![];
-        for idx in 0..(offset + count) {
-            let item = try!(con.read_response());
-            if idx >= offset {
-                rv.push(item);
-            }
-        }
-        Ok(rv)
+        let result = {
+            let mut con = self.con.borrow_mut();
+            con.send_bytes(cmd).and_then(|_| con.read_responses(offset, count))
+        };
+        self.check_dropped(result)
     }
 
     fn get_db(&self) -> i64 {
-        self.db
+        self.db.get()
+    }
+
+    fn get_addr(&self) -> Option<ConnectionAddr> {
+        Some((*self.info.addr).clone())
+    }
+}
+
+impl<'a> BulkReader<'a> {
+    /// Returns the number of bytes that are left to read.
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+}
+
+/// Skips the newline that follows the data of a bulk string.
+fn skip_newline(con: &mut ActualConnection) -> io::Result<()> {
+    let mut newline = [0; 2];
+    con.buffered_reader().read_exact(&mut newline)
+}
+
+impl<'a> Read for BulkReader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.remaining == 0 || buf.is_empty() {
+            return Ok(0);
+        }
+        let len = if buf.len() < self.remaining { buf.len() } else { self.remaining };
+        let nread = try!(self.con.buffered_reader().read(&mut buf[..len]));
+        if nread == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "connection closed"));
+        }
+        self.remaining -= nread;
+        if self.remaining == 0 {
+            try!(skip_newline(self.con));
+        }
+        Ok(nread)
+    }
+}
+
+impl<'a> Drop for BulkReader<'a> {
+    fn drop(&mut self) {
+        // skip what was not read so the next reply can be read
+        let _ = io::copy(self, &mut io::sink());
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.notify_disconnect();
     }
 }
 
@@ -468,22 +1719,67 @@ impl PubSub {
         Ok(())
     }
 
+    /// Subscribes to a sharded channel.  In a cluster the connection has
+    /// to go to the node serving the slot of the channel, see
+    /// `ClusterConnection::get_sharded_pubsub`.
+    pub fn ssubscribe<T: ToRedisArgs>(&mut self, channel: T) -> RedisResult<()> {
+        let chan = self.get_channel(&channel);
+        let _: () = try!(cmd("SSUBSCRIBE").arg(&*chan).query(&self.con));
+        self.schannels.insert(chan);
+        Ok(())
+    }
+
+    /// Unsubscribes from a sharded channel.
+    pub fn sunsubscribe<T: ToRedisArgs>(&mut self, channel: T) -> RedisResult<()> {
+        let chan = self.get_channel(&channel);
+        let _: () = try!(cmd("SUNSUBSCRIBE").arg(&*chan).query(&self.con));
+        self.schannels.remove(&chan);
+        Ok(())
+    }
+
+    /// Unsubscribes from all channels and patterns at once and resets the
+    /// connection, see `Connection::reset`.
+    pub fn reset(&mut self) -> RedisResult<()> {
+        try!(self.con.reset());
+        self.channels.clear();
+        self.pchannels.clear();
+        self.schannels.clear();
+        Ok(())
+    }
+
     /// Fetches the next message from the pubsub connection.  Blocks until
     /// a message becomes available.  This currently does not provide a
     /// wait not to block :(
     ///
     /// The message itself is still generic and can be converted into an
     /// appropriate type through the helper methods on it.
+    ///
+    /// If the connection was lost it is reconnected and all channels and
+    /// patterns are subscribed to again.  A message for which
+    /// `is_resubscribed` returns true is returned in that case since
+    /// messages published in the meantime are lost.
     pub fn get_message(&self) -> RedisResult<Msg> {
         loop {
-            let raw_msg: Vec<Value> = try!(from_redis_value(&try!(self.con.recv_response())));
+            let response = self.con.recv_response();
+            if let Err(ref err) = response {
+                if err.is_connection_dropped() {
+                    try!(self.resubscribe());
+                    return Ok(Msg {
+                        payload: Value::Nil,
+                        channel: Value::Nil,
+                        pattern: None,
+                        resubscribed: true,
+                    });
+                }
+            }
+            let raw_msg: Vec<Value> = try!(from_redis_value(&try!(response)));
             let mut iter = raw_msg.into_iter();
             let msg_type: String = try!(from_redis_value(&unwrap_or!(iter.next(), continue)));
             let mut pattern = None;
             let payload;
             let channel;
 
-            if msg_type == "message" {
+            if msg_type == "message" || msg_type == "smessage" {
                 channel = unwrap_or!(iter.next(), continue);
                 payload = unwrap_or!(iter.next(), continue);
             } else if msg_type == "pmessage" {
@@ -498,6 +1794,7 @@ impl PubSub {
                 payload: payload,
                 channel: channel,
                 pattern: pattern,
+                resubscribed: false,
             });
         }
     }
@@ -508,11 +1805,84 @@ impl PubSub {
     /// block indefinitely. It is an error to pass the zero `Duration` to this
     /// method.
     pub fn set_read_timeout(&self, dur: Option<Duration>) -> RedisResult<()> {
-        self.con.set_read_timeout(dur)
+        try!(self.con.set_read_timeout(dur));
+        self.read_timeout.set(dur);
+        Ok(())
+    }
+
+    /// Turns the connection into an iterator over `(channel, payload)`
+    /// pairs with the payload converted to `T`.  A message that cannot
+    /// be converted yields an error but does not end the iteration.
+    /// Timeouts are yielded as well when a read timeout is set.  If the
+    /// connection is lost and cannot be restored the error is yielded
+    /// and the iteration ends.
+    ///
+    /// Notices about restored subscriptions are skipped, use
+    /// `get_message` to see them.
+    ///
+    /// ```rust,no_run
+    /// # let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+    /// let mut pubsub = client.get_pubsub().unwrap();
+    /// pubsub.subscribe("counters").unwrap();
+    /// for item in pubsub.into_typed_stream::<i64>() {
+    ///     match item {
+    ///         Ok((channel, value)) => println!("{}: {}", channel, value),
+    ///         Err(err) => println!("bad message: {}", err),
+    ///     }
+    /// }
+    /// ```
+    pub fn into_typed_stream<T: FromRedisValue>(self) -> MsgIter<T> {
+        MsgIter {
+            pubsub: self,
+            done: false,
+            marker: PhantomData,
+        }
+    }
+
+    /// Reconnects and subscribes to all channels and patterns again.
+    fn resubscribe(&self) -> RedisResult<()> {
+        try!(self.con.reconnect());
+        try!(self.con.set_read_timeout(self.read_timeout.get()));
+        for chan in self.channels.iter() {
+            let _: () = try!(cmd("SUBSCRIBE").arg(&chan[..]).query(&self.con));
+        }
+        for chan in self.pchannels.iter() {
+            let _: () = try!(cmd("PSUBSCRIBE").arg(&chan[..]).query(&self.con));
+        }
+        for chan in self.schannels.iter() {
+            let _: () = try!(cmd("SSUBSCRIBE").arg(&chan[..]).query(&self.con));
+        }
+        Ok(())
     }
 }
 
 
+impl<T: FromRedisValue> Iterator for MsgIter<T> {
+    type Item = RedisResult<(String, T)>;
+
+    fn next(&mut self) -> Option<RedisResult<(String, T)>> {
+        if self.done {
+            return None;
+        }
+        loop {
+            let msg = match self.pubsub.get_message() {
+                Ok(msg) => msg,
+                Err(err) => {
+                    // a connection that could not be restored stays broken
+                    self.done = err.is_io_error() && !err.is_timeout();
+                    return Some(Err(err));
+                }
+            };
+            if msg.is_resubscribed() {
+                continue;
+            }
+            return Some(msg.get_channel().and_then(|channel| {
+                msg.get_payload().map(|payload| (channel, payload))
+            }));
+        }
+    }
+}
+
 /// This holds the data that comes from listening to a pubsub
 /// connection.  It only contains actual message data.
 impl Msg {
@@ -537,6 +1907,12 @@ impl Msg {
         from_redis_value(&self.payload)
     }
 
+    /// Same as `get_payload`, for call sites that name the type with a
+    /// turbofish: `msg.get_payload_as::<i64>()`.
+    pub fn get_payload_as<T: FromRedisValue>(&self) -> RedisResult<T> {
+        self.get_payload()
+    }
+
     /// Returns the bytes that are the message's payload.  This can be used
     /// as an alternative to the `get_payload` function if you are interested
     /// in the raw bytes in it.
@@ -547,6 +1923,13 @@ impl Msg {
         }
     }
 
+    /// Returns true if this is not an actual message but a notice that
+    /// the connection was lost and the subscriptions were restored.
+    /// Messages published while the connection was down were missed.
+    pub fn is_resubscribed(&self) -> bool {
+        self.resubscribed
+    }
+
     /// Returns true if the message was constructed from a pattern
     /// subscription.
     pub fn from_pattern(&self) -> bool {
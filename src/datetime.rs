@@ -0,0 +1,123 @@
+use std::str::from_utf8;
+
+#[cfg(feature="chrono")]
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+#[cfg(feature="time")]
+use time::OffsetDateTime;
+#[cfg(feature="time")]
+use time::format_description::well_known::Rfc3339;
+
+use types::{FromRedisValue, ToRedisArgs, RedisResult, ErrorKind, Value};
+
+
+// Timestamps are written in RFC 3339 format as that keeps the sub second
+// precision and stays readable.  When reading integers are accepted as
+// well and taken as unix timestamps in seconds, which is what commands
+// like `TIME` or `OBJECT FREQ` and most applications use.
+
+enum Timestamp<'a> {
+    Unix(i64),
+    Text(&'a str),
+}
+
+fn timestamp(v: &Value) -> RedisResult<Timestamp> {
+    let s = match *v {
+        Value::Int(secs) => return Ok(Timestamp::Unix(secs)),
+        Value::Data(ref bytes) => try!(from_utf8(bytes)),
+        Value::Status(ref s) => &s[..],
+        _ => fail!((ErrorKind::TypeError, "Response type not timestamp compatible")),
+    };
+    Ok(match s.parse() {
+        Ok(secs) => Timestamp::Unix(secs),
+        Err(_) => Timestamp::Text(s),
+    })
+}
+
+fn invalid_timestamp<T>(detail: String) -> RedisResult<T> {
+    fail!((ErrorKind::TypeError, "Invalid timestamp", detail))
+}
+
+#[cfg(feature="chrono")]
+impl ToRedisArgs for DateTime<Utc> {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        vec![self.to_rfc3339_opts(SecondsFormat::AutoSi, true).into_bytes()]
+    }
+}
+
+#[cfg(feature="chrono")]
+impl FromRedisValue for DateTime<Utc> {
+    fn from_redis_value(v: &Value) -> RedisResult<DateTime<Utc>> {
+        match try!(timestamp(v)) {
+            Timestamp::Unix(secs) => {
+                match DateTime::from_timestamp(secs, 0) {
+                    Some(dt) => Ok(dt),
+                    None => invalid_timestamp(secs.to_string()),
+                }
+            }
+            Timestamp::Text(s) => {
+                match DateTime::parse_from_rfc3339(s) {
+                    Ok(dt) => Ok(dt.with_timezone(&Utc)),
+                    Err(err) => invalid_timestamp(format!("{}: {:?}", err, s)),
+                }
+            }
+        }
+    }
+}
+
+/// Naive date times are written like UTC date times without the offset
+/// and unix timestamps are read as UTC.
+#[cfg(feature="chrono")]
+impl ToRedisArgs for NaiveDateTime {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        vec![self.format("%Y-%m-%dT%H:%M:%S%.f").to_string().into_bytes()]
+    }
+}
+
+#[cfg(feature="chrono")]
+impl FromRedisValue for NaiveDateTime {
+    fn from_redis_value(v: &Value) -> RedisResult<NaiveDateTime> {
+        match try!(timestamp(v)) {
+            Timestamp::Unix(secs) => {
+                match DateTime::from_timestamp(secs, 0) {
+                    Some(dt) => Ok(dt.naive_utc()),
+                    None => invalid_timestamp(secs.to_string()),
+                }
+            }
+            Timestamp::Text(s) => {
+                match s.parse() {
+                    Ok(dt) => Ok(dt),
+                    Err(err) => invalid_timestamp(format!("{}: {:?}", err, s)),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature="time")]
+impl ToRedisArgs for OffsetDateTime {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        // XXX: formatting only fails for years that RFC 3339 cannot express
+        let s = self.format(&Rfc3339).expect("date time cannot be formatted as RFC 3339");
+        vec![s.into_bytes()]
+    }
+}
+
+#[cfg(feature="time")]
+impl FromRedisValue for OffsetDateTime {
+    fn from_redis_value(v: &Value) -> RedisResult<OffsetDateTime> {
+        match try!(timestamp(v)) {
+            Timestamp::Unix(secs) => {
+                match OffsetDateTime::from_unix_timestamp(secs) {
+                    Ok(dt) => Ok(dt),
+                    Err(_) => invalid_timestamp(secs.to_string()),
+                }
+            }
+            Timestamp::Text(s) => {
+                match OffsetDateTime::parse(s, &Rfc3339) {
+                    Ok(dt) => Ok(dt),
+                    Err(err) => invalid_timestamp(format!("{}: {:?}", err, s)),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+
+use cmd::cmd;
+use types::{ToRedisArgs, FromRedisValue, RedisResult, RedisError, ErrorKind, Value,
+            from_redis_value};
+use connection::ConnectionLike;
+
+/// Represents a function of a redis function library.
+pub struct Function {
+    code: String,
+    name: String,
+}
+
+/// The function object represents a function of a library that can be
+/// called on the redis server (redis 7 or later).  The object holds the
+/// source of the whole library and loads it when the server does not know
+/// the function yet, much like `Script` does for lua scripts.
+///
+/// Example:
+///
+/// ```rust,no_run
+/// # let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+/// # let con = client.get_connection().unwrap();
+/// let add = redis::Function::new(r"#!lua name=mathlib
+///     redis.register_function('add', function(keys, args)
+///         return tonumber(args[1]) + tonumber(args[2])
+///     end)
+/// ", "add");
+/// let result = add.arg(1).arg(2).invoke(&con);
+/// assert_eq!(result, Ok(3));
+/// ```
+impl Function {
+    /// Creates a new function object from the source of its library and
+    /// the name of the function.
+    pub fn new(library: &str, name: &str) -> Function {
+        Function {
+            code: library.to_string(),
+            name: name.to_string(),
+        }
+    }
+
+    /// Returns the name of the function.
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Loads the library of the function, replacing an older version of
+    /// it.  This is useful to load libraries eagerly on startup, calling
+    /// a function loads its library if needed anyway.
+    pub fn load(&self, con: &ConnectionLike) -> RedisResult<()> {
+        let _: String = try!(cmd("FUNCTION")
+            .arg("LOAD")
+            .arg("REPLACE")
+            .arg(self.code.as_bytes())
+            .query(con));
+        Ok(())
+    }
+
+    /// Creates a function invocation object with a key filled in.
+    #[inline]
+    pub fn key<T: ToRedisArgs>(&self, key: T) -> FunctionInvocation {
+        FunctionInvocation {
+            function: self,
+            args: vec![],
+            keys: key.to_redis_args(),
+        }
+    }
+
+    /// Creates a function invocation object with an argument filled in.
+    #[inline]
+    pub fn arg<T: ToRedisArgs>(&self, arg: T) -> FunctionInvocation {
+        FunctionInvocation {
+            function: self,
+            args: arg.to_redis_args(),
+            keys: vec![],
+        }
+    }
+
+    /// Returns an empty function invocation object.  This is primarily
+    /// useful for programmatically adding arguments and keys because the
+    /// type will not change.  Normally you can use `arg` and `key`
+    /// directly.
+    #[inline]
+    pub fn prepare_invoke(&self) -> FunctionInvocation {
+        FunctionInvocation {
+            function: self,
+            args: vec![],
+            keys: vec![],
+        }
+    }
+
+    /// Invokes the function directly without arguments.
+    #[inline]
+    pub fn invoke<T: FromRedisValue>(&self, con: &ConnectionLike) -> RedisResult<T> {
+        self.prepare_invoke().invoke(con)
+    }
+}
+
+/// Represents a prepared function call.
+pub struct FunctionInvocation<'a> {
+    function: &'a Function,
+    args: Vec<Vec<u8>>,
+    keys: Vec<Vec<u8>>,
+}
+
+/// This type collects keys and other arguments for the function so that
+/// it can be then invoked.
+impl<'a> FunctionInvocation<'a> {
+    /// Adds a regular argument to the invocation.  This ends up in the
+    /// `args` table of the function.
+    #[inline]
+    pub fn arg<'b, T: ToRedisArgs>(&'b mut self, arg: T) -> &'b mut FunctionInvocation<'a>
+        where 'a: 'b
+    {
+        self.args.extend(arg.to_redis_args().into_iter());
+        self
+    }
+
+    /// Adds a key argument to the invocation.  This ends up in the `keys`
+    /// table of the function.
+    #[inline]
+    pub fn key<'b, T: ToRedisArgs>(&'b mut self, key: T) -> &'b mut FunctionInvocation<'a>
+        where 'a: 'b
+    {
+        self.keys.extend(key.to_redis_args().into_iter());
+        self
+    }
+
+    /// Invokes the function with `FCALL` and returns the result.
+    #[inline]
+    pub fn invoke<T: FromRedisValue>(&self, con: &ConnectionLike) -> RedisResult<T> {
+        self.call("FCALL", con)
+    }
+
+    /// Invokes the function with `FCALL_RO` and returns the result.  The
+    /// function has to be registered with the `no-writes` flag, in turn
+    /// it can run on replicas.
+    #[inline]
+    pub fn invoke_ro<T: FromRedisValue>(&self, con: &ConnectionLike) -> RedisResult<T> {
+        self.call("FCALL_RO", con)
+    }
+
+    fn call<T: FromRedisValue>(&self, command: &str, con: &ConnectionLike) -> RedisResult<T> {
+        // the library is loaded at most once, if it does not register
+        // the function loading it again does not help.
+        let mut loaded = false;
+        loop {
+            match cmd(command)
+                .arg(self.function.name.as_bytes())
+                .arg(self.keys.len())
+                .arg(&*self.keys)
+                .arg(&*self.args)
+                .query(con) {
+                Ok(val) => {
+                    return Ok(val);
+                }
+                Err(err) => {
+                    if is_function_not_found(&err) && !loaded {
+                        try!(self.function.load(con));
+                        loaded = true;
+                    } else {
+                        fail!(err);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn is_function_not_found(err: &RedisError) -> bool {
+    err.kind() == ErrorKind::ResponseError &&
+    err.detail().map_or(false, |detail| detail.starts_with("Function not found"))
+}
+
+
+/// What `FUNCTION RESTORE` does with the libraries that already exist.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FunctionRestorePolicy {
+    /// Fails if a restored library exists, this is the default.
+    Append,
+    /// Replaces the existing libraries of the same name.
+    Replace,
+    /// Deletes all existing libraries first.
+    Flush,
+}
+
+impl ToRedisArgs for FunctionRestorePolicy {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        let policy = match *self {
+            FunctionRestorePolicy::Append => "APPEND",
+            FunctionRestorePolicy::Replace => "REPLACE",
+            FunctionRestorePolicy::Flush => "FLUSH",
+        };
+        vec![policy.as_bytes().to_vec()]
+    }
+}
+
+
+/// A function of a library as reported by `FUNCTION LIST`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionInfo {
+    /// The name of the function.
+    pub name: String,
+    /// The description the function was registered with.
+    pub description: Option<String>,
+    /// The flags the function was registered with, like `no-writes`.
+    pub flags: Vec<String>,
+}
+
+impl FromRedisValue for FunctionInfo {
+    fn from_redis_value(v: &Value) -> RedisResult<FunctionInfo> {
+        let fields: HashMap<String, Value> = try!(from_redis_value(v));
+        let nil = Value::Nil;
+        Ok(FunctionInfo {
+            name: try!(from_redis_value(fields.get("name").unwrap_or(&nil))),
+            description: try!(from_redis_value(fields.get("description").unwrap_or(&nil))),
+            flags: try!(from_redis_value(fields.get("flags").unwrap_or(&nil))),
+        })
+    }
+}
+
+/// A library as reported by `FUNCTION LIST`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LibraryInfo {
+    /// The name of the library.
+    pub name: String,
+    /// The engine of the library, like `LUA`.
+    pub engine: String,
+    /// The functions of the library.
+    pub functions: Vec<FunctionInfo>,
+    /// The source of the library if it was asked for.
+    pub code: Option<String>,
+}
+
+impl FromRedisValue for LibraryInfo {
+    fn from_redis_value(v: &Value) -> RedisResult<LibraryInfo> {
+        let fields: HashMap<String, Value> = try!(from_redis_value(v));
+        let nil = Value::Nil;
+        Ok(LibraryInfo {
+            name: try!(from_redis_value(fields.get("library_name").unwrap_or(&nil))),
+            engine: try!(from_redis_value(fields.get("engine").unwrap_or(&nil))),
+            functions: try!(from_redis_value(fields.get("functions").unwrap_or(&nil))),
+            code: try!(from_redis_value(fields.get("library_code").unwrap_or(&nil))),
+        })
+    }
+}
@@ -0,0 +1,277 @@
+use types::{FromRedisValue, ToRedisArgs, RedisResult, ErrorKind, Value, from_redis_value};
+
+
+/// The unit of distances in geo commands.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Unit {
+    /// Meters.
+    Meters,
+    /// Kilometers.
+    Kilometers,
+    /// Miles.
+    Miles,
+    /// Feet.
+    Feet,
+}
+
+impl ToRedisArgs for Unit {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        let unit = match *self {
+            Unit::Meters => "m",
+            Unit::Kilometers => "km",
+            Unit::Miles => "mi",
+            Unit::Feet => "ft",
+        };
+        vec![unit.as_bytes().to_vec()]
+    }
+}
+
+
+/// A position on earth.
+///
+/// As arguments coordinates are written longitude first, which is the
+/// order redis uses.  To add members pass `(Coord, member)` pairs:
+///
+/// ```rust,no_run
+/// use redis::{Commands, Coord};
+/// # let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+/// # let con = client.get_connection().unwrap();
+/// let _: usize = con.geo_add("cities", &[(Coord::lon_lat(13.361389, 38.115556), "Palermo"),
+///                                        (Coord::lon_lat(15.087269, 37.502669), "Catania")])
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coord {
+    /// The longitude in degrees.
+    pub longitude: f64,
+    /// The latitude in degrees.
+    pub latitude: f64,
+}
+
+impl Coord {
+    /// Creates a coordinate from longitude and latitude.
+    pub fn lon_lat(longitude: f64, latitude: f64) -> Coord {
+        Coord {
+            longitude: longitude,
+            latitude: latitude,
+        }
+    }
+}
+
+impl ToRedisArgs for Coord {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        let mut rv = self.longitude.to_redis_args();
+        rv.extend(self.latitude.to_redis_args());
+        rv
+    }
+}
+
+impl FromRedisValue for Coord {
+    fn from_redis_value(v: &Value) -> RedisResult<Coord> {
+        let (longitude, latitude) = try!(from_redis_value(v));
+        Ok(Coord::lon_lat(longitude, latitude))
+    }
+}
+
+
+/// The area a `GEOSEARCH` looks in around its center.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GeoShape {
+    /// A circle with the given radius.
+    Radius(f64, Unit),
+    /// A box with the given width and height.
+    Box(f64, f64, Unit),
+}
+
+/// The order of search results.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GeoOrder {
+    /// Nearest members first.
+    Asc,
+    /// Farthest members first.
+    Desc,
+}
+
+
+/// A search for `GEOSEARCH` and `GEOSEARCHSTORE`.
+///
+/// ```rust,no_run
+/// use redis::{Commands, GeoSearch, GeoSearchResult, GeoShape, Unit};
+/// # let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+/// # let con = client.get_connection().unwrap();
+/// let search = GeoSearch::from_member("Palermo", GeoShape::Radius(200.0, Unit::Kilometers))
+///     .with_dist()
+///     .count(5);
+/// let results: Vec<GeoSearchResult> = con.geo_search("cities", search).unwrap();
+/// for result in results {
+///     println!("{} is {:?} km away", result.name, result.dist);
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoSearch {
+    origin: Vec<Vec<u8>>,
+    shape: GeoShape,
+    order: Option<GeoOrder>,
+    count: Option<(usize, bool)>,
+    with_coord: bool,
+    with_dist: bool,
+    with_hash: bool,
+}
+
+impl GeoSearch {
+    /// Searches around the position of a member of the set.
+    pub fn from_member<M: ToRedisArgs>(member: M, shape: GeoShape) -> GeoSearch {
+        let mut origin = vec![b"FROMMEMBER".to_vec()];
+        origin.extend(member.to_redis_args());
+        GeoSearch::new(origin, shape)
+    }
+
+    /// Searches around the given position.
+    pub fn from_coord(coord: Coord, shape: GeoShape) -> GeoSearch {
+        let mut origin = vec![b"FROMLONLAT".to_vec()];
+        origin.extend(coord.to_redis_args());
+        GeoSearch::new(origin, shape)
+    }
+
+    fn new(origin: Vec<Vec<u8>>, shape: GeoShape) -> GeoSearch {
+        GeoSearch {
+            origin: origin,
+            shape: shape,
+            order: None,
+            count: None,
+            with_coord: false,
+            with_dist: false,
+            with_hash: false,
+        }
+    }
+
+    /// Sorts the results by distance.
+    pub fn order(mut self, order: GeoOrder) -> GeoSearch {
+        self.order = Some(order);
+        self
+    }
+
+    /// Returns at most this many results.
+    pub fn count(mut self, count: usize) -> GeoSearch {
+        self.count = Some((count, false));
+        self
+    }
+
+    /// Returns at most this many results and stops as soon as enough
+    /// were found, so they are not necessarily the nearest ones.
+    pub fn count_any(mut self, count: usize) -> GeoSearch {
+        self.count = Some((count, true));
+        self
+    }
+
+    /// Includes the positions in the results.
+    pub fn with_coord(mut self) -> GeoSearch {
+        self.with_coord = true;
+        self
+    }
+
+    /// Includes the distances to the center in the results, in the unit
+    /// of the shape.
+    pub fn with_dist(mut self) -> GeoSearch {
+        self.with_dist = true;
+        self
+    }
+
+    /// Includes the raw geohashes in the results.
+    pub fn with_hash(mut self) -> GeoSearch {
+        self.with_hash = true;
+        self
+    }
+}
+
+impl ToRedisArgs for GeoSearch {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        let mut rv = self.origin.clone();
+        match self.shape {
+            GeoShape::Radius(radius, unit) => {
+                rv.push(b"BYRADIUS".to_vec());
+                rv.extend(radius.to_redis_args());
+                rv.extend(unit.to_redis_args());
+            }
+            GeoShape::Box(width, height, unit) => {
+                rv.push(b"BYBOX".to_vec());
+                rv.extend(width.to_redis_args());
+                rv.extend(height.to_redis_args());
+                rv.extend(unit.to_redis_args());
+            }
+        }
+        match self.order {
+            Some(GeoOrder::Asc) => rv.push(b"ASC".to_vec()),
+            Some(GeoOrder::Desc) => rv.push(b"DESC".to_vec()),
+            None => {}
+        }
+        if let Some((count, any)) = self.count {
+            rv.push(b"COUNT".to_vec());
+            rv.extend(count.to_redis_args());
+            if any {
+                rv.push(b"ANY".to_vec());
+            }
+        }
+        if self.with_coord {
+            rv.push(b"WITHCOORD".to_vec());
+        }
+        if self.with_dist {
+            rv.push(b"WITHDIST".to_vec());
+        }
+        if self.with_hash {
+            rv.push(b"WITHHASH".to_vec());
+        }
+        rv
+    }
+}
+
+
+/// A member found by `GEOSEARCH`.  The optional parts are only set if
+/// the search asked for them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoSearchResult {
+    /// The name of the member.
+    pub name: String,
+    /// The distance to the center of the search.
+    pub dist: Option<f64>,
+    /// The raw geohash of the position.
+    pub hash: Option<i64>,
+    /// The position of the member.
+    pub coord: Option<Coord>,
+}
+
+impl FromRedisValue for GeoSearchResult {
+    fn from_redis_value(v: &Value) -> RedisResult<GeoSearchResult> {
+        // without any WITH* option the reply only is the name, otherwise
+        // the parts follow the name in a fixed order and can be told
+        // apart by their type.
+        let items = match *v {
+            Value::Bulk(ref items) => &items[..],
+            _ => {
+                return Ok(GeoSearchResult {
+                    name: try!(from_redis_value(v)),
+                    dist: None,
+                    hash: None,
+                    coord: None,
+                })
+            }
+        };
+        let mut rv = GeoSearchResult {
+            name: match items.first() {
+                Some(name) => try!(from_redis_value(name)),
+                None => fail!((ErrorKind::TypeError, "Invalid GEOSEARCH reply")),
+            },
+            dist: None,
+            hash: None,
+            coord: None,
+        };
+        for item in &items[1..] {
+            match *item {
+                Value::Int(hash) => rv.hash = Some(hash),
+                Value::Bulk(_) => rv.coord = Some(try!(from_redis_value(item))),
+                _ => rv.dist = Some(try!(from_redis_value(item))),
+            }
+        }
+        Ok(rv)
+    }
+}
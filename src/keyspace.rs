@@ -0,0 +1,172 @@
+use std::time::Duration;
+
+use client::Client;
+use cmd::cmd;
+use connection::PubSub;
+use types::{RedisResult, ErrorKind};
+
+
+/// The kind of change a keyspace notification reports.
+///
+/// Only generic events are listed, type specific events like `lpush` or
+/// `hset` are reported as `Other`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyspaceEventType {
+    /// The key was deleted.
+    Del,
+    /// The key was renamed, this is reported for the old name.
+    RenameFrom,
+    /// The key was renamed, this is reported for the new name.
+    RenameTo,
+    /// The key was moved to another database, reported for the source.
+    MoveFrom,
+    /// The key was moved to another database, reported for the target.
+    MoveTo,
+    /// The key was the target of a `COPY`.
+    CopyTo,
+    /// An expiration was set on the key.
+    Expire,
+    /// The expiration of the key was removed.
+    Persist,
+    /// The key expired.
+    Expired,
+    /// The key was evicted because of the memory limit.
+    Evicted,
+    /// The key was created.
+    New,
+    /// A string value was set.
+    Set,
+    /// Any other event with its name.
+    Other(String),
+}
+
+impl KeyspaceEventType {
+    fn from_name(name: &str) -> KeyspaceEventType {
+        match name {
+            "del" => KeyspaceEventType::Del,
+            "rename_from" => KeyspaceEventType::RenameFrom,
+            "rename_to" => KeyspaceEventType::RenameTo,
+            "move_from" => KeyspaceEventType::MoveFrom,
+            "move_to" => KeyspaceEventType::MoveTo,
+            "copy_to" => KeyspaceEventType::CopyTo,
+            "expire" => KeyspaceEventType::Expire,
+            "persist" => KeyspaceEventType::Persist,
+            "expired" => KeyspaceEventType::Expired,
+            "evicted" => KeyspaceEventType::Evicted,
+            "new" => KeyspaceEventType::New,
+            "set" => KeyspaceEventType::Set,
+            other => KeyspaceEventType::Other(other.to_string()),
+        }
+    }
+}
+
+/// A parsed keyspace notification.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyspaceEvent {
+    /// The database of the key.
+    pub db: i64,
+    /// The key that changed.
+    pub key: String,
+    /// What happened to the key.
+    pub event: KeyspaceEventType,
+}
+
+impl KeyspaceEvent {
+    /// Parses the channel and payload of a notification.  Channels look
+    /// like `__keyspace@<db>__:<key>` with the event as payload or
+    /// `__keyevent@<db>__:<event>` with the key as payload.  Returns
+    /// `None` for other channels.
+    pub fn parse(channel: &str, payload: &str) -> Option<KeyspaceEvent> {
+        let (is_keyspace, rest) = if channel.starts_with("__keyspace@") {
+            (true, &channel["__keyspace@".len()..])
+        } else if channel.starts_with("__keyevent@") {
+            (false, &channel["__keyevent@".len()..])
+        } else {
+            return None;
+        };
+        let idx = match rest.find("__:") {
+            Some(idx) => idx,
+            None => return None,
+        };
+        let db = match rest[..idx].parse() {
+            Ok(db) => db,
+            Err(_) => return None,
+        };
+        let name = &rest[idx + 3..];
+        let (key, event) = if is_keyspace { (name, payload) } else { (payload, name) };
+        Some(KeyspaceEvent {
+            db: db,
+            key: key.to_string(),
+            event: KeyspaceEventType::from_name(event),
+        })
+    }
+}
+
+
+/// Listens for keyspace notifications of a server.
+///
+/// The server only sends notifications for the event classes enabled in
+/// `notify-keyspace-events`.  The flags are the ones documented for that
+/// setting, for instance `"KEA"` for all events on both the keyspace and
+/// the keyevent channels.  Note that every change is reported once per
+/// enabled channel type, so enabling both `K` and `E` yields two events
+/// for each change.
+///
+/// ```rust,no_run
+/// let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+/// let notifications = redis::KeyspaceNotifications::new(&client, "Eg$x").unwrap();
+/// loop {
+///     let event = notifications.get_event().unwrap();
+///     println!("{:?} on {} in db {}", event.event, event.key, event.db);
+/// }
+/// ```
+pub struct KeyspaceNotifications {
+    pubsub: PubSub,
+}
+
+impl KeyspaceNotifications {
+    /// Sets `notify-keyspace-events` to the given flags with `CONFIG SET`
+    /// and subscribes to the notifications of all databases.  The flags
+    /// need to enable keyspace (`K`) or keyevent (`E`) notifications.
+    pub fn new(client: &Client, flags: &str) -> RedisResult<KeyspaceNotifications> {
+        let keyspace = flags.contains('K');
+        let keyevent = flags.contains('E');
+        if !keyspace && !keyevent {
+            fail!((ErrorKind::InvalidClientConfig,
+                   "Notification flags need to enable keyspace or keyevent events",
+                   flags.to_string()));
+        }
+        let con = try!(client.get_connection());
+        let _: () = try!(cmd("CONFIG").arg("SET").arg("notify-keyspace-events").arg(flags)
+            .query(&con));
+        let mut pubsub = try!(client.get_pubsub());
+        if keyspace {
+            try!(pubsub.psubscribe("__keyspace@*__:*"));
+        }
+        if keyevent {
+            try!(pubsub.psubscribe("__keyevent@*__:*"));
+        }
+        Ok(KeyspaceNotifications { pubsub: pubsub })
+    }
+
+    /// Fetches the next notification.  Blocks until one arrives or the
+    /// read timeout passes.
+    pub fn get_event(&self) -> RedisResult<KeyspaceEvent> {
+        loop {
+            let msg = try!(self.pubsub.get_message());
+            if msg.is_resubscribed() {
+                continue;
+            }
+            let channel: String = try!(msg.get_channel());
+            let payload: String = try!(msg.get_payload());
+            if let Some(event) = KeyspaceEvent::parse(&channel, &payload) {
+                return Ok(event);
+            }
+        }
+    }
+
+    /// Sets the read timeout for `get_event`.
+    pub fn set_read_timeout(&self, dur: Option<Duration>) -> RedisResult<()> {
+        self.pubsub.set_read_timeout(dur)
+    }
+}
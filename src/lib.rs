@@ -50,7 +50,7 @@
 //!
 //! ## Optional Features
 //!
-//! There are currently two features defined that can enable additional
+//! There are currently eleven features defined that can enable additional
 //! functionality if so desired.
 //!
 //! `with-unix-sockets`:
@@ -62,6 +62,41 @@
 //! `with-rustc-json`:
 //!   This feature flag enables the `rustc_serialize` JSON support.
 //!
+//! `tls`:
+//!   This feature flag enables TLS encrypted connections through the
+//!   `native-tls` crate.  It is required for `rediss://` URLs.
+//!
+//! `tls-rustls`:
+//!   Like `tls` but uses `rustls` instead of the platform's TLS library
+//!   which allows building fully static binaries.
+//!
+//! `serde`:
+//!   This feature flag adds the `Serde` and `SerdeJson` wrappers which
+//!   store any type supporting `serde` in its binary encoding or as JSON.
+//!   It implies `json`.
+//!
+//! `json`:
+//!   This feature flag adds conversions for `serde_json::Value`, which is
+//!   stored as JSON text.
+//!
+//! `derive`:
+//!   This feature flag adds `#[derive(RedisHash)]` which stores a struct
+//!   as a hash with one field per struct field.
+//!
+//! `chrono` and `time`:
+//!   These feature flags add conversions for `DateTime<Utc>` and
+//!   `NaiveDateTime` of `chrono` and `OffsetDateTime` of `time`.  They are
+//!   written in RFC 3339 format and read from that format or from unix
+//!   timestamps in seconds.
+//!
+//! `uuid`:
+//!   This feature flag adds conversions for `Uuid` of the `uuid` crate.
+//!   They are written hyphenated and read from text or 16 raw bytes.
+//!
+//! `bytes`:
+//!   This feature flag allows reading binary replies as `Bytes` of the
+//!   `bytes` crate, which takes over the buffer of the reply.
+//!
 //! ## Connection Parameters
 //!
 //! redis-rs knows different ways to define where a connection should
@@ -70,9 +105,36 @@
 //!
 //! * string slices in `redis://` URL format.
 //! * URL objects from the redis-url crate.
-//! * `ConnectionInfo` objects.
+//! * `ConnectionInfo` objects, for instance as returned by
+//!   `ConnectionInfo::builder()`.
+//!
+//! The URL format is `redis://[[<username>]:<passwd>@]<hostname>[:port][/<db>]`
+//!
+//! A username is only needed for redis 6 ACL users, otherwise the
+//! password authenticates the `default` user.
+//!
+//! Further connection options can be given as query parameters, for
+//! instance `redis://127.0.0.1/?connect_timeout=5s&client_name=worker`:
+//!
+//! * `db`: the database number, overriding the one in the path.
+//! * `client_name`: a name to register with `CLIENT SETNAME`.
+//! * `connect_timeout`: the connect timeout in seconds (`5` or `5s`) or
+//!   milliseconds (`500ms`).
+//! * `protocol`: `resp2` (the default) or `resp3`.
+//! * `tls`: `true` or `insecure` to encrypt a `redis://` connection.
+//!
+//! If the crate is compiled with the `tls` or `tls-rustls` feature TLS encrypted connections
+//! can be requested with the `rediss` scheme.  Appending `#insecure` to the
+//! URL disables verification of the server certificate:
+//!
+//! `rediss://[[<username>]:<passwd>@]<hostname>[:port][/<db>][#insecure]`
 //!
-//! The URL format is `redis://[:<passwd>@]<hostname>[:port][/<db>]`
+//! Client certificates for mutual TLS and a custom CA bundle can be
+//! configured with `TlsCertificates` on the `ConnectionInfo`.
+//!
+//! Several hosts can be given separated by commas, for instance
+//! `redis://primary:6379,standby:6379/`.  They are tried in order until a
+//! connection succeeds.
 //!
 //! In case the create is compiled with Unix socket support you can also
 //! use a unix URL in this format:
@@ -84,6 +146,36 @@
 //!
 //! `unix:///[:<passwd>@]<path>[?db=<db>]`
 //!
+//! ## Sentinel
+//!
+//! Deployments where redis sentinel manages failover can be used with
+//! the `SentinelClient`.  It is given the addresses of the sentinels and
+//! the name of the service and connects to whichever server is the
+//! master at the time:
+//!
+//! ```rust,no_run
+//! let sentinel = redis::SentinelClient::open(vec!["redis://10.0.0.1:26379/"],
+//!                                            "mymaster").unwrap();
+//! let con = sentinel.get_connection().unwrap();
+//! ```
+//!
+//! `get_failover_connection` returns a connection that additionally
+//! follows the master when sentinel promotes a replica.
+//!
+//! ## Cluster
+//!
+//! A redis cluster is used through the `ClusterClient`.  It needs the
+//! address of at least one node and learns about the others from the
+//! cluster itself.  Commands are sent to the node that serves their key:
+//!
+//! ```rust,no_run
+//! let client = redis::ClusterClient::open(vec!["redis://127.0.0.1:7000/"]).unwrap();
+//! let con = client.get_connection().unwrap();
+//! ```
+//!
+//! Use hash tags (`{user1000}.following`) to make sure keys that are used
+//! together by a pipeline or script end up on the same node.
+//!
 //! ## Executing Low-Level Commands
 //!
 //! To execute low-level commands you can use the `cmd` function which allows
@@ -176,7 +268,8 @@
 //!
 //! As you can see the cursor argument needs to be defined with `cursor_arg`
 //! instead of `arg` so that the library knows which argument needs updating
-//! as the query is run for more items.
+//! as the query is run for more items.  `Iter::cursor` returns the cursor to
+//! resume an interrupted iteration from, for instance in another process.
 //!
 //! # Pipelining
 //!
@@ -284,8 +377,8 @@
 //! # Scripts
 //!
 //! Lua scripts are supported through the `Script` type in a convenient
-//! way (it does not support pipelining currently).  It will automatically
-//! load the script if it does not exist and invoke it.
+//! way.  It will automatically load the script if it does not exist and
+//! invoke it.  Pipelines run scripts with `Pipeline::invoke_script`.
 //!
 //! Example:
 //!
@@ -312,22 +405,74 @@
 
 extern crate url;
 extern crate sha1;
+extern crate socket2;
 
 #[cfg(feature="with-rustc-json")]
 pub extern crate rustc_serialize as serialize;
 #[cfg(feature="with-unix-sockets")]
 extern crate unix_socket;
+#[cfg(feature="tls")]
+extern crate native_tls;
+#[cfg(feature="tls-rustls")]
+extern crate rustls;
+#[cfg(feature="tls-rustls")]
+extern crate rustls_pemfile;
+#[cfg(feature="tls-rustls")]
+extern crate webpki_roots;
+#[cfg(feature="serde")]
+extern crate serde;
+#[cfg(feature="json")]
+extern crate serde_json;
+#[cfg(feature="serde")]
+extern crate bincode;
+#[cfg(feature="derive")]
+extern crate redis_derive;
+#[cfg(feature="chrono")]
+extern crate chrono;
+#[cfg(feature="time")]
+extern crate time;
+#[cfg(feature="uuid")]
+extern crate uuid;
+#[cfg(feature="bytes")]
+extern crate bytes;
 
 #[doc(hidden)]
 #[cfg(feature="with-rustc-json")]
 pub use serialize::json::Json;
+#[cfg(feature="serde")]
+pub use serde_support::{Serde, SerdeJson};
+#[cfg(feature="derive")]
+pub use redis_derive::RedisHash;
 
 // public api
 pub use parser::{parse_redis_value, Parser};
 pub use client::Client;
-pub use script::{Script, ScriptInvocation};
-pub use connection::{Connection, ConnectionLike, ConnectionInfo, ConnectionAddr,
-                     IntoConnectionInfo, PubSub, Msg, transaction, parse_redis_url};
+pub use script::{Script, ScriptBuilder, ScriptInvocation, ScriptManager};
+pub use function::{Function, FunctionInvocation, FunctionRestorePolicy, FunctionInfo,
+                   LibraryInfo};
+pub use sentinel::{SentinelClient, SentinelConnection};
+pub use streams::{StreamEntry, StreamKey, StreamMaxlen, StreamReadOptions, StreamReadReply,
+                  StreamPendingReply, StreamPendingEntry, StreamAutoClaimReply, StreamConsumer,
+                  StreamInfo, StreamGroupInfo, StreamConsumerInfo};
+pub use geo::{Unit, Coord, GeoShape, GeoOrder, GeoSearch, GeoSearchResult};
+pub use bitfield::{BitFieldOperations, BitFieldOffset, BitFieldOverflow, BitFieldType};
+pub use options::{SetOptions, SetExpiry, ExistenceCheck, ZAddOptions, ZAddComparison, GetExpiry,
+                  CopyOptions, Expiry, Direction, ZPopSide, LposOptions, RangeQuery,
+                  LcsOptions, LcsMatch, LcsMatches, FlushMode, RestoreOptions, MigrateOptions,
+                  MigrateReply, ScanOptions, Ttl};
+pub use server::{Encoding, MemoryStats, ClientInfo, ClientType, ClientKillFilter, PauseMode,
+                 SlowLogEntry, ServerInfo, MemoryInfo, ReplicationInfo, PersistenceInfo,
+                 StatsInfo, KeyspaceInfo, FailoverOptions, LatencySample, LatencyEvent,
+                 CommandInfo, CommandDocs, ShutdownMode, TrackingOptions};
+pub use acl::{AclRules, AclUserInfo};
+pub use keyspace::{KeyspaceNotifications, KeyspaceEvent, KeyspaceEventType};
+pub use cluster::{ClusterClient, ClusterConnection, ReadFromReplica, RetryPolicy, ClusterInfo,
+                  ClusterNode, ClusterSlot, ClusterSlotNode, ClusterShard, ClusterShardNode,
+                  key_slot};
+pub use connection::{Connection, ConnectionLike, ConnectionInfo, ConnectionInfoBuilder,
+                     ConnectionAddr, ConnectionEvents, IntoConnectionInfo, RedisTransport,
+                     TlsCertificates, ProtocolVersion, PubSub, Msg, MsgIter, BulkReader,
+                     transaction, parse_redis_url};
 pub use cmd::{cmd, Cmd, pipe, Pipeline, Iter, pack_command};
 pub use commands::{Commands, PipelineCommands};
 
@@ -344,7 +489,9 @@ pub use types::{
 
     /* utility types */
     InfoDict,
+    Nested,
     NumericBehavior,
+    RedisString,
 
     /* conversion traits */
     FromRedisValue,
@@ -352,6 +499,7 @@ pub use types::{
 
     /* utility functions */
     from_redis_value,
+    from_owned_redis_value,
 };
 
 mod macros;
@@ -361,5 +509,21 @@ mod client;
 mod connection;
 mod types;
 mod script;
+mod function;
+mod sentinel;
+mod cluster;
+mod keyspace;
+mod server;
+mod acl;
+mod streams;
+mod geo;
+mod bitfield;
+mod options;
 mod cmd;
 mod commands;
+#[cfg(any(feature="tls", feature="tls-rustls"))]
+mod tls;
+#[cfg(feature="serde")]
+mod serde_support;
+#[cfg(any(feature="chrono", feature="time"))]
+mod datetime;
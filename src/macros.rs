@@ -20,3 +20,58 @@ macro_rules! unwrap_or {
         }
     )
 }
+
+/// Implements `ToRedisArgs` and `FromRedisValue` for an enum without
+/// fields by mapping each variant to a fixed string.  Decoding any other
+/// string fails with a type error.
+///
+/// ```rust,no_run
+/// #[macro_use] extern crate redis;
+/// use redis::Commands;
+///
+/// #[derive(Debug, PartialEq)]
+/// enum Status {
+///     Active,
+///     Suspended,
+/// }
+///
+/// redis_string_enum!(Status {
+///     Active => "active",
+///     Suspended => "suspended",
+/// });
+///
+/// # fn main() {
+/// # let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+/// # let con = client.get_connection().unwrap();
+/// let _: () = con.set("user:1:status", Status::Active).unwrap();
+/// let status: Status = con.get("user:1:status").unwrap();
+/// # }
+/// ```
+#[macro_export]
+macro_rules! redis_string_enum {
+    ($t:ident { $($variant:ident => $text:expr),* $(,)* }) => (
+        impl $crate::ToRedisArgs for $t {
+            fn to_redis_args(&self) -> Vec<Vec<u8>> {
+                let text: &str = match *self {
+                    $($t::$variant => $text,)*
+                };
+                vec![text.as_bytes().to_vec()]
+            }
+        }
+
+        impl $crate::FromRedisValue for $t {
+            fn from_redis_value(v: &$crate::Value) -> $crate::RedisResult<$t> {
+                let text: String = match $crate::from_redis_value(v) {
+                    Ok(text) => text,
+                    Err(err) => return Err(err),
+                };
+                $(if text == $text { return Ok($t::$variant); })*
+                Err($crate::RedisError::from((
+                    $crate::ErrorKind::TypeError,
+                    "Response was of incompatible type",
+                    format!("{:?} (response was {:?}, expected {})",
+                            "Unknown variant", v, stringify!($t)))))
+            }
+        }
+    )
+}
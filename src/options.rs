@@ -0,0 +1,849 @@
+use std::cmp;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use types::{FromRedisValue, ToRedisArgs, RedisResult, ErrorKind, Value, from_redis_value};
+
+
+/// The expiration to set with `SET`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SetExpiry {
+    /// Expires after the given number of seconds.
+    Ex(usize),
+    /// Expires after the given number of milliseconds.
+    Px(usize),
+    /// Expires at the given UNIX time in seconds.
+    ExAt(usize),
+    /// Expires at the given UNIX time in milliseconds.
+    PxAt(usize),
+    /// Keeps the expiration the key already has.
+    KeepTtl,
+}
+
+impl ToRedisArgs for SetExpiry {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        let (name, value) = match *self {
+            SetExpiry::Ex(secs) => ("EX", secs),
+            SetExpiry::Px(ms) => ("PX", ms),
+            SetExpiry::ExAt(secs) => ("EXAT", secs),
+            SetExpiry::PxAt(ms) => ("PXAT", ms),
+            SetExpiry::KeepTtl => return vec![b"KEEPTTL".to_vec()],
+        };
+        vec![name.as_bytes().to_vec(), value.to_string().into_bytes()]
+    }
+}
+
+/// Makes a write depend on whether the key already exists.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExistenceCheck {
+    /// Only writes if the key does not exist yet.
+    Nx,
+    /// Only writes if the key already exists.
+    Xx,
+}
+
+impl ToRedisArgs for ExistenceCheck {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        match *self {
+            ExistenceCheck::Nx => vec![b"NX".to_vec()],
+            ExistenceCheck::Xx => vec![b"XX".to_vec()],
+        }
+    }
+}
+
+
+/// Options for `SET`.
+///
+/// Without `get` the reply is `OK`, or nil if the existence check
+/// prevented the write, so it can be decoded as `bool`.  With `get` the
+/// reply is the old value, decode it as `Option<T>`:
+///
+/// ```rust,no_run
+/// use redis::{Commands, ExistenceCheck, SetExpiry, SetOptions};
+/// # let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+/// # let con = client.get_connection().unwrap();
+/// let opts = SetOptions::default()
+///     .conditional_set(ExistenceCheck::Xx)
+///     .with_expiration(SetExpiry::Ex(60))
+///     .get();
+/// let old: Option<String> = con.set_options("session", "token", opts).unwrap();
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SetOptions {
+    conditional_set: Option<ExistenceCheck>,
+    get: bool,
+    expiration: Option<SetExpiry>,
+}
+
+impl SetOptions {
+    /// Only sets the value depending on whether the key exists.
+    pub fn conditional_set(mut self, check: ExistenceCheck) -> SetOptions {
+        self.conditional_set = Some(check);
+        self
+    }
+
+    /// Returns the old value of the key.
+    pub fn get(mut self) -> SetOptions {
+        self.get = true;
+        self
+    }
+
+    /// Sets the expiration of the key.
+    pub fn with_expiration(mut self, expiration: SetExpiry) -> SetOptions {
+        self.expiration = Some(expiration);
+        self
+    }
+}
+
+impl ToRedisArgs for SetOptions {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        let mut rv = self.conditional_set.to_redis_args();
+        if self.get {
+            rv.push(b"GET".to_vec());
+        }
+        rv.extend(self.expiration.to_redis_args());
+        rv
+    }
+}
+
+
+/// Only updates a score of a sorted set if the new one compares to the
+/// old one like this.  Members are still added.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ZAddComparison {
+    /// The new score is greater than the old one.
+    Gt,
+    /// The new score is less than the old one.
+    Lt,
+}
+
+/// Options for `ZADD`.
+///
+/// The reply is the number of added members, or the number of added and
+/// updated members with `changed`.  With `incr` the command works like
+/// `ZINCRBY` and replies the new score or nil if the update was not
+/// made, decode it as `Option<f64>`:
+///
+/// ```rust,no_run
+/// use redis::{Commands, ZAddComparison, ZAddOptions};
+/// # let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+/// # let con = client.get_connection().unwrap();
+/// // only keeps the highest score of each player
+/// let opts = ZAddOptions::default().update_if(ZAddComparison::Gt).changed();
+/// let changed: usize = con.zadd_options("highscores", "alice", 420, opts).unwrap();
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ZAddOptions {
+    conditional_set: Option<ExistenceCheck>,
+    comparison: Option<ZAddComparison>,
+    changed: bool,
+    incr: bool,
+}
+
+impl ZAddOptions {
+    /// Only adds new members with `Nx` or only updates existing ones with
+    /// `Xx`.
+    pub fn conditional_set(mut self, check: ExistenceCheck) -> ZAddOptions {
+        self.conditional_set = Some(check);
+        self
+    }
+
+    /// Only updates scores that compare to the new ones like this.
+    pub fn update_if(mut self, comparison: ZAddComparison) -> ZAddOptions {
+        self.comparison = Some(comparison);
+        self
+    }
+
+    /// Counts updated members in the reply as well.
+    pub fn changed(mut self) -> ZAddOptions {
+        self.changed = true;
+        self
+    }
+
+    /// Increments the score of the member instead of setting it.  Only
+    /// one member can be given.
+    pub fn incr(mut self) -> ZAddOptions {
+        self.incr = true;
+        self
+    }
+}
+
+impl ToRedisArgs for ZAddOptions {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        let mut rv = self.conditional_set.to_redis_args();
+        match self.comparison {
+            Some(ZAddComparison::Gt) => rv.push(b"GT".to_vec()),
+            Some(ZAddComparison::Lt) => rv.push(b"LT".to_vec()),
+            None => {}
+        }
+        if self.changed {
+            rv.push(b"CH".to_vec());
+        }
+        if self.incr {
+            rv.push(b"INCR".to_vec());
+        }
+        rv
+    }
+}
+
+
+/// The expiration to set with `GETEX`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GetExpiry {
+    /// Expires after the given number of seconds.
+    Ex(usize),
+    /// Expires after the given number of milliseconds.
+    Px(usize),
+    /// Expires at the given UNIX time in seconds.
+    ExAt(usize),
+    /// Expires at the given UNIX time in milliseconds.
+    PxAt(usize),
+    /// Removes the expiration of the key.
+    Persist,
+}
+
+impl ToRedisArgs for GetExpiry {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        let (name, value) = match *self {
+            GetExpiry::Ex(secs) => ("EX", secs),
+            GetExpiry::Px(ms) => ("PX", ms),
+            GetExpiry::ExAt(secs) => ("EXAT", secs),
+            GetExpiry::PxAt(ms) => ("PXAT", ms),
+            GetExpiry::Persist => return vec![b"PERSIST".to_vec()],
+        };
+        vec![name.as_bytes().to_vec(), value.to_string().into_bytes()]
+    }
+}
+
+/// Options for `COPY`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CopyOptions {
+    db: Option<i64>,
+    replace: bool,
+}
+
+impl CopyOptions {
+    /// Copies to the given database instead of the current one.
+    pub fn db(mut self, db: i64) -> CopyOptions {
+        self.db = Some(db);
+        self
+    }
+
+    /// Replaces the destination key if it exists.
+    pub fn replace(mut self) -> CopyOptions {
+        self.replace = true;
+        self
+    }
+}
+
+impl ToRedisArgs for CopyOptions {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        let mut rv = vec![];
+        if let Some(db) = self.db {
+            rv.push(b"DB".to_vec());
+            rv.extend(db.to_redis_args());
+        }
+        if self.replace {
+            rv.push(b"REPLACE".to_vec());
+        }
+        rv
+    }
+}
+
+/// Options for `RESTORE`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RestoreOptions {
+    replace: bool,
+    absttl: bool,
+    idletime: Option<usize>,
+    freq: Option<u8>,
+}
+
+impl RestoreOptions {
+    /// Replaces the key if it exists.
+    pub fn replace(mut self) -> RestoreOptions {
+        self.replace = true;
+        self
+    }
+
+    /// Takes the ttl as a unix time in milliseconds rather than as a
+    /// duration.
+    pub fn absttl(mut self) -> RestoreOptions {
+        self.absttl = true;
+        self
+    }
+
+    /// Sets the number of seconds the key counts as idle, for servers
+    /// that evict the least recently used keys.
+    pub fn idletime(mut self, seconds: usize) -> RestoreOptions {
+        self.idletime = Some(seconds);
+        self
+    }
+
+    /// Sets the access frequency counter of the key, for servers that
+    /// evict the least frequently used keys.
+    pub fn freq(mut self, freq: u8) -> RestoreOptions {
+        self.freq = Some(freq);
+        self
+    }
+}
+
+impl ToRedisArgs for RestoreOptions {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        let mut rv = vec![];
+        if self.replace {
+            rv.push(b"REPLACE".to_vec());
+        }
+        if self.absttl {
+            rv.push(b"ABSTTL".to_vec());
+        }
+        if let Some(seconds) = self.idletime {
+            rv.push(b"IDLETIME".to_vec());
+            rv.extend(seconds.to_redis_args());
+        }
+        if let Some(freq) = self.freq {
+            rv.push(b"FREQ".to_vec());
+            rv.extend(freq.to_redis_args());
+        }
+        rv
+    }
+}
+
+
+/// Options for `MIGRATE`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MigrateOptions {
+    copy: bool,
+    replace: bool,
+    auth: Option<(Option<String>, String)>,
+}
+
+impl MigrateOptions {
+    /// Keeps the keys on the source instance.
+    pub fn copy(mut self) -> MigrateOptions {
+        self.copy = true;
+        self
+    }
+
+    /// Replaces keys that exist on the destination instance.
+    pub fn replace(mut self) -> MigrateOptions {
+        self.replace = true;
+        self
+    }
+
+    /// Authenticates at the destination instance with a password.
+    pub fn auth(mut self, password: &str) -> MigrateOptions {
+        self.auth = Some((None, password.to_string()));
+        self
+    }
+
+    /// Authenticates at the destination instance as the given user.
+    pub fn auth2(mut self, username: &str, password: &str) -> MigrateOptions {
+        self.auth = Some((Some(username.to_string()), password.to_string()));
+        self
+    }
+}
+
+impl ToRedisArgs for MigrateOptions {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        let mut rv = vec![];
+        if self.copy {
+            rv.push(b"COPY".to_vec());
+        }
+        if self.replace {
+            rv.push(b"REPLACE".to_vec());
+        }
+        match self.auth {
+            Some((Some(ref username), ref password)) => {
+                rv.push(b"AUTH2".to_vec());
+                rv.extend(username.to_redis_args());
+                rv.extend(password.to_redis_args());
+            }
+            Some((None, ref password)) => {
+                rv.push(b"AUTH".to_vec());
+                rv.extend(password.to_redis_args());
+            }
+            None => {}
+        }
+        rv
+    }
+}
+
+/// The reply of `MIGRATE`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MigrateReply {
+    /// The keys were migrated.
+    Ok,
+    /// None of the keys existed on the source instance.
+    NoKey,
+}
+
+impl FromRedisValue for MigrateReply {
+    fn from_redis_value(v: &Value) -> RedisResult<MigrateReply> {
+        match *v {
+            Value::Okay => Ok(MigrateReply::Ok),
+            Value::Status(ref s) if s == "NOKEY" => Ok(MigrateReply::NoKey),
+            _ => fail!((ErrorKind::TypeError, "Invalid MIGRATE reply", format!("{:?}", v))),
+        }
+    }
+}
+
+
+/// Makes `EXPIRE` and friends depend on the current expiration of the
+/// key.  Keys without expiration count as having an infinite one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Expiry {
+    /// Only sets the expiration if the key has none.
+    Nx,
+    /// Only sets the expiration if the key has one.
+    Xx,
+    /// Only sets the expiration if it is later than the current one.
+    Gt,
+    /// Only sets the expiration if it is earlier than the current one.
+    Lt,
+}
+
+impl ToRedisArgs for Expiry {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        let flag = match *self {
+            Expiry::Nx => "NX",
+            Expiry::Xx => "XX",
+            Expiry::Gt => "GT",
+            Expiry::Lt => "LT",
+        };
+        vec![flag.as_bytes().to_vec()]
+    }
+}
+
+
+/// The time to live of a key as `TTL` and `PTTL` report it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ttl {
+    /// The key exists but does not expire.
+    NoExpiry,
+    /// The key does not exist.
+    Missing,
+    /// The key expires after the given time.
+    Expires(Duration),
+}
+
+impl Ttl {
+    /// Interprets a reply of `TTL`, which is in seconds.
+    pub fn from_secs(secs: i64) -> Ttl {
+        match secs {
+            -1 => Ttl::NoExpiry,
+            -2 => Ttl::Missing,
+            secs => Ttl::Expires(Duration::from_secs(cmp::max(secs, 0) as u64)),
+        }
+    }
+
+    /// Interprets a reply of `PTTL`, which is in milliseconds.
+    pub fn from_millis(ms: i64) -> Ttl {
+        match ms {
+            -1 => Ttl::NoExpiry,
+            -2 => Ttl::Missing,
+            ms => Ttl::Expires(Duration::from_millis(cmp::max(ms, 0) as u64)),
+        }
+    }
+}
+
+
+/// The end of a list to pop from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    /// The head of the list.
+    Left,
+    /// The tail of the list.
+    Right,
+}
+
+impl ToRedisArgs for Direction {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        match *self {
+            Direction::Left => vec![b"LEFT".to_vec()],
+            Direction::Right => vec![b"RIGHT".to_vec()],
+        }
+    }
+}
+
+/// The end of a sorted set to pop from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ZPopSide {
+    /// The members with the lowest scores.
+    Min,
+    /// The members with the highest scores.
+    Max,
+}
+
+impl ToRedisArgs for ZPopSide {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        match *self {
+            ZPopSide::Min => vec![b"MIN".to_vec()],
+            ZPopSide::Max => vec![b"MAX".to_vec()],
+        }
+    }
+}
+
+
+/// Options for `LPOS`.
+///
+/// Without `count` the reply is the index of the match or nil, decode it
+/// as `Option<usize>`.  With `count` the reply is a list of indexes,
+/// decode it as `Vec<usize>`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LposOptions {
+    rank: Option<isize>,
+    count: Option<usize>,
+    maxlen: Option<usize>,
+}
+
+impl LposOptions {
+    /// Skips matches: a rank of 2 returns the second match, a negative
+    /// rank searches from the tail of the list.
+    pub fn rank(mut self, rank: isize) -> LposOptions {
+        self.rank = Some(rank);
+        self
+    }
+
+    /// Returns up to this many matches, 0 returns all of them.
+    pub fn count(mut self, count: usize) -> LposOptions {
+        self.count = Some(count);
+        self
+    }
+
+    /// Only compares this many elements.
+    pub fn maxlen(mut self, maxlen: usize) -> LposOptions {
+        self.maxlen = Some(maxlen);
+        self
+    }
+}
+
+impl ToRedisArgs for LposOptions {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        let mut rv = vec![];
+        if let Some(rank) = self.rank {
+            rv.push(b"RANK".to_vec());
+            rv.extend(rank.to_redis_args());
+        }
+        if let Some(count) = self.count {
+            rv.push(b"COUNT".to_vec());
+            rv.extend(count.to_redis_args());
+        }
+        if let Some(maxlen) = self.maxlen {
+            rv.push(b"MAXLEN".to_vec());
+            rv.extend(maxlen.to_redis_args());
+        }
+        rv
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RangeBy {
+    Index,
+    Score,
+    Lex,
+}
+
+/// A range of a sorted set for `ZRANGE` and `ZRANGESTORE`.
+///
+/// ```rust,no_run
+/// use redis::{Commands, RangeQuery};
+/// # let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+/// # let con = client.get_connection().unwrap();
+/// // the ten best scores of at most 100
+/// let query = RangeQuery::by_score("-inf", 100).rev().limit(0, 10).with_scores();
+/// let best: Vec<(String, f64)> = con.zrange_with("highscores", query).unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RangeQuery {
+    by: RangeBy,
+    min: Vec<Vec<u8>>,
+    max: Vec<Vec<u8>>,
+    rev: bool,
+    limit: Option<(isize, isize)>,
+    with_scores: bool,
+}
+
+impl RangeQuery {
+    /// Selects the members from rank start to rank stop, both
+    /// inclusive.  Negative ranks count from the end.
+    pub fn by_index(start: isize, stop: isize) -> RangeQuery {
+        RangeQuery::new(RangeBy::Index, start.to_redis_args(), stop.to_redis_args())
+    }
+
+    /// Selects the members with a score between min and max.  Use `(`
+    /// in front of a score to exclude it and `-inf` and `+inf` for open
+    /// ends.
+    pub fn by_score<M: ToRedisArgs, MM: ToRedisArgs>(min: M, max: MM) -> RangeQuery {
+        RangeQuery::new(RangeBy::Score, min.to_redis_args(), max.to_redis_args())
+    }
+
+    /// Selects the members between min and max in lexicographical order,
+    /// which requires all members to have the same score.  The bounds
+    /// start with `[` or `(` for inclusive or exclusive, `-` and `+`
+    /// stand for open ends.
+    pub fn by_lex<M: ToRedisArgs, MM: ToRedisArgs>(min: M, max: MM) -> RangeQuery {
+        RangeQuery::new(RangeBy::Lex, min.to_redis_args(), max.to_redis_args())
+    }
+
+    fn new(by: RangeBy, min: Vec<Vec<u8>>, max: Vec<Vec<u8>>) -> RangeQuery {
+        RangeQuery {
+            by: by,
+            min: min,
+            max: max,
+            rev: false,
+            limit: None,
+            with_scores: false,
+        }
+    }
+
+    /// Orders the members from the highest to the lowest score.  Ranks
+    /// then count from the highest score while score and lex ranges are
+    /// still given as min and max.
+    pub fn rev(mut self) -> RangeQuery {
+        self.rev = true;
+        self
+    }
+
+    /// Skips offset members and returns at most count of the rest, a
+    /// negative count returns all of them.  Only applies to score and lex
+    /// ranges.
+    pub fn limit(mut self, offset: isize, count: isize) -> RangeQuery {
+        self.limit = Some((offset, count));
+        self
+    }
+
+    /// Returns the scores together with the members, decode the reply as
+    /// `Vec<(T, f64)>` or as `Vec<Nested<(T, f64)>>` with RESP3.  Not
+    /// supported by `ZRANGESTORE`.
+    pub fn with_scores(mut self) -> RangeQuery {
+        self.with_scores = true;
+        self
+    }
+}
+
+impl ToRedisArgs for RangeQuery {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        // reversed score and lex ranges take the upper bound first
+        let mut rv = if self.rev && self.by != RangeBy::Index {
+            let mut rv = self.max.clone();
+            rv.extend(self.min.iter().cloned());
+            rv
+        } else {
+            let mut rv = self.min.clone();
+            rv.extend(self.max.iter().cloned());
+            rv
+        };
+        match self.by {
+            RangeBy::Index => {}
+            RangeBy::Score => rv.push(b"BYSCORE".to_vec()),
+            RangeBy::Lex => rv.push(b"BYLEX".to_vec()),
+        }
+        if self.rev {
+            rv.push(b"REV".to_vec());
+        }
+        if let Some((offset, count)) = self.limit {
+            rv.push(b"LIMIT".to_vec());
+            rv.extend(offset.to_redis_args());
+            rv.extend(count.to_redis_args());
+        }
+        if self.with_scores {
+            rv.push(b"WITHSCORES".to_vec());
+        }
+        rv
+    }
+}
+
+
+/// Options for `LCS`.
+///
+/// By default the reply is the longest common subsequence itself.  With
+/// `len` it is its length, with `idx` it is the position of the matches,
+/// decode it as `LcsMatches` then.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LcsOptions {
+    len: bool,
+    idx: bool,
+    min_match_len: Option<usize>,
+    with_match_len: bool,
+}
+
+impl LcsOptions {
+    /// Returns the length of the subsequence.
+    pub fn len(mut self) -> LcsOptions {
+        self.len = true;
+        self
+    }
+
+    /// Returns the positions of the matching ranges.
+    pub fn idx(mut self) -> LcsOptions {
+        self.idx = true;
+        self
+    }
+
+    /// Only returns matching ranges of at least this length.
+    pub fn min_match_len(mut self, len: usize) -> LcsOptions {
+        self.min_match_len = Some(len);
+        self
+    }
+
+    /// Returns the length of each matching range.
+    pub fn with_match_len(mut self) -> LcsOptions {
+        self.with_match_len = true;
+        self
+    }
+}
+
+impl ToRedisArgs for LcsOptions {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        let mut rv = vec![];
+        if self.len {
+            rv.push(b"LEN".to_vec());
+        }
+        if self.idx {
+            rv.push(b"IDX".to_vec());
+        }
+        if let Some(len) = self.min_match_len {
+            rv.push(b"MINMATCHLEN".to_vec());
+            rv.extend(len.to_redis_args());
+        }
+        if self.with_match_len {
+            rv.push(b"WITHMATCHLEN".to_vec());
+        }
+        rv
+    }
+}
+
+/// A range that is part of the longest common subsequence of two
+/// strings.  The positions are inclusive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LcsMatch {
+    /// The start and end of the range in the first string.
+    pub first: (usize, usize),
+    /// The start and end of the range in the second string.
+    pub second: (usize, usize),
+    /// The length of the range if `with_match_len` was set.
+    pub len: Option<usize>,
+}
+
+impl FromRedisValue for LcsMatch {
+    fn from_redis_value(v: &Value) -> RedisResult<LcsMatch> {
+        let items: Vec<Value> = try!(from_redis_value(v));
+        if items.len() < 2 {
+            fail!((ErrorKind::TypeError, "Invalid LCS match", format!("{:?}", v)));
+        }
+        Ok(LcsMatch {
+            first: try!(from_redis_value(&items[0])),
+            second: try!(from_redis_value(&items[1])),
+            len: match items.get(2) {
+                Some(len) => Some(try!(from_redis_value(len))),
+                None => None,
+            },
+        })
+    }
+}
+
+/// The reply of `LCS` with `idx`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LcsMatches {
+    /// The matching ranges, the last ones of the strings first.
+    pub matches: Vec<LcsMatch>,
+    /// The length of the longest common subsequence.
+    pub len: usize,
+}
+
+impl FromRedisValue for LcsMatches {
+    fn from_redis_value(v: &Value) -> RedisResult<LcsMatches> {
+        let map: HashMap<String, Value> = try!(from_redis_value(v));
+        let nil = Value::Nil;
+        let matches: Vec<Value> = try!(from_redis_value(map.get("matches").unwrap_or(&nil)));
+        let mut rv = Vec::with_capacity(matches.len());
+        for item in matches.iter() {
+            rv.push(try!(from_redis_value(item)));
+        }
+        Ok(LcsMatches {
+            matches: rv,
+            len: try!(from_redis_value(map.get("len").unwrap_or(&nil))),
+        })
+    }
+}
+
+
+/// Whether a flush frees the memory in the background.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FlushMode {
+    /// Returns right away and frees the memory in the background.
+    Async,
+    /// Returns after the memory was freed.
+    Sync,
+}
+
+impl ToRedisArgs for FlushMode {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        let mode = match *self {
+            FlushMode::Async => "ASYNC",
+            FlushMode::Sync => "SYNC",
+        };
+        vec![mode.as_bytes().to_vec()]
+    }
+}
+
+
+/// Options for `SCAN`.
+///
+/// ```rust,no_run
+/// use redis::{Commands, ScanOptions};
+/// # let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+/// # let con = client.get_connection().unwrap();
+/// let options = ScanOptions::default().pattern("session:*").count(1000).key_type("hash");
+/// for key in con.scan_options::<String>(options).unwrap() {
+///     println!("{}", key);
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScanOptions {
+    pattern: Option<Vec<u8>>,
+    count: Option<usize>,
+    key_type: Option<String>,
+}
+
+impl ScanOptions {
+    /// Only returns keys matching the glob-style pattern.
+    pub fn pattern<P: ToRedisArgs>(mut self, pattern: P) -> ScanOptions {
+        self.pattern = pattern.to_redis_args().into_iter().next();
+        self
+    }
+
+    /// Hints how many keys the server looks at per batch.
+    pub fn count(mut self, count: usize) -> ScanOptions {
+        self.count = Some(count);
+        self
+    }
+
+    /// Only returns keys holding the given type of value, like `string`,
+    /// `hash` or `stream`.
+    pub fn key_type(mut self, key_type: &str) -> ScanOptions {
+        self.key_type = Some(key_type.to_string());
+        self
+    }
+}
+
+impl ToRedisArgs for ScanOptions {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        let mut rv = vec![];
+        if let Some(ref pattern) = self.pattern {
+            rv.push(b"MATCH".to_vec());
+            rv.push(pattern.clone());
+        }
+        if let Some(count) = self.count {
+            rv.push(b"COUNT".to_vec());
+            rv.extend(count.to_redis_args());
+        }
+        if let Some(ref key_type) = self.key_type {
+            rv.push(b"TYPE".to_vec());
+            rv.extend(key_type.to_redis_args());
+        }
+        rv
+    }
+}
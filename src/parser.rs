@@ -1,6 +1,7 @@
-use std::io::{Read, BufReader};
+use std::io::{self, Read, BufReader};
 
-use types::{RedisResult, Value, ErrorKind, make_extension_error};
+use types::{RedisResult, RedisError, Value, ErrorKind, make_extension_error, make_server_error,
+            is_server_error};
 
 
 /// The internal redis response parser.
@@ -34,6 +35,17 @@ impl<'a, T: Read> Parser<T> {
             '$' => self.parse_data(),
             '*' => self.parse_bulk(),
             '-' => self.parse_error(),
+            // RESP3 types
+            '_' => self.parse_null(),
+            ',' => self.parse_double(),
+            '#' => self.parse_boolean(),
+            '(' => self.parse_big_number(),
+            '=' => self.parse_verbatim_string(),
+            '%' => self.parse_map(),
+            '~' => self.parse_set(),
+            '>' => self.parse_push(),
+            '|' => self.parse_attribute(),
+            '!' => self.parse_blob_error(),
             _ => fail!((ErrorKind::ResponseError, "Invalid response when parsing value")),
         }
     }
@@ -90,7 +102,7 @@ impl<'a, T: Read> Parser<T> {
         let nread = try!(self.reader.read(buf));
 
         if nread < 1 {
-            fail!((ErrorKind::ResponseError, "Could not read enough bytes"))
+            Err(unexpected_eof())
         } else {
             Ok(buf[0])
         }
@@ -106,7 +118,7 @@ impl<'a, T: Read> Parser<T> {
             };
             match res_nread {
                 Ok(nread) if nread > 0 => i += nread,
-                Ok(_) => fail!((ErrorKind::ResponseError, "Could not read enough bytes")),
+                Ok(_) => return Err(unexpected_eof()),
                 Err(e) => return Err(From::from(e)),
             }
         }
@@ -150,33 +162,172 @@ impl<'a, T: Read> Parser<T> {
         if length < 0 {
             Ok(Value::Nil)
         } else {
-            let mut rv = vec![];
-            rv.reserve(length as usize);
-            for _ in 0..length {
-                rv.push(try!(self.parse_value()));
+            Ok(Value::Bulk(try!(self.parse_values(length as usize))))
+        }
+    }
+
+    fn parse_values(&mut self, length: usize) -> RedisResult<Vec<Value>> {
+        let mut rv = vec![];
+        rv.reserve(length);
+        // an error of the server, like one of the replies of EXEC, does not
+        // stop the reading so the remaining items are not left behind.
+        let mut first_err = None;
+        for _ in 0..length {
+            match self.parse_value() {
+                Ok(value) => rv.push(value),
+                Err(err) => {
+                    if !is_server_error(&err) {
+                        return Err(err);
+                    }
+                    if first_err.is_none() {
+                        first_err = Some(err);
+                    }
+                }
             }
-            Ok(Value::Bulk(rv))
+        }
+        match first_err {
+            Some(err) => Err(err),
+            None => Ok(rv),
         }
     }
 
-    fn parse_error(&mut self) -> RedisResult<Value> {
-        let desc = "An error was signalled by the server";
+    fn parse_null(&mut self) -> RedisResult<Value> {
+        try!(self.read_line());
+        Ok(Value::Nil)
+    }
+
+    fn parse_double(&mut self) -> RedisResult<Value> {
         let line = try!(self.read_string_line());
-        let mut pieces = line.splitn(2, ' ');
-        let kind = match pieces.next().unwrap() {
-            "ERR" => ErrorKind::ResponseError,
-            "EXECABORT" => ErrorKind::ExecAbortError,
-            "LOADING" => ErrorKind::BusyLoadingError,
-            "NOSCRIPT" => ErrorKind::NoScriptError,
-            code => {
-                fail!(make_extension_error(code, pieces.next()));
+        match line.trim().parse::<f64>() {
+            Err(_) => fail!((ErrorKind::ResponseError, "Expected double, got garbage")),
+            Ok(_) => Ok(Value::Double(line.trim().to_string())),
+        }
+    }
+
+    fn parse_boolean(&mut self) -> RedisResult<Value> {
+        match &try!(self.read_string_line())[..] {
+            "t" => Ok(Value::Boolean(true)),
+            "f" => Ok(Value::Boolean(false)),
+            _ => fail!((ErrorKind::ResponseError, "Expected boolean, got garbage")),
+        }
+    }
+
+    fn parse_big_number(&mut self) -> RedisResult<Value> {
+        Ok(Value::BigNumber(try!(self.read_string_line())))
+    }
+
+    fn parse_verbatim_string(&mut self) -> RedisResult<Value> {
+        let length = try!(self.read_int_line());
+        if length < 4 {
+            fail!((ErrorKind::ResponseError, "Verbatim string too short"));
+        }
+        let mut format = try!(self.read(length as usize));
+        try!(self.expect_newline());
+        if format[3] != b':' {
+            fail!((ErrorKind::ResponseError, "Verbatim string without format"));
+        }
+        let text = format.split_off(4);
+        format.truncate(3);
+        match (String::from_utf8(format), String::from_utf8(text)) {
+            (Ok(format), Ok(text)) => {
+                Ok(Value::VerbatimString {
+                    format: format,
+                    text: text,
+                })
             }
-        };
-        match pieces.next() {
-            Some(detail) => fail!((kind, desc, detail.to_string())),
-            None => fail!((kind, desc)),
+            _ => fail!((ErrorKind::ResponseError, "Expected valid string, got garbage")),
         }
     }
+
+    /// Reads the length of a RESP3 type, which has no nil form.
+    fn read_length(&mut self) -> RedisResult<usize> {
+        let length = try!(self.read_int_line());
+        if length < 0 {
+            fail!((ErrorKind::ResponseError, "Invalid length in response"));
+        }
+        Ok(length as usize)
+    }
+
+    fn parse_map(&mut self) -> RedisResult<Value> {
+        let length = try!(self.read_length());
+        let mut rv = vec![];
+        rv.reserve(length);
+        // like in `parse_values` the whole map is read even if some of the
+        // entries are errors of the server.
+        let mut first_err = None;
+        for _ in 0..length {
+            let key = self.parse_value();
+            let value = self.parse_value();
+            match (key, value) {
+                (Ok(key), Ok(value)) => rv.push((key, value)),
+                (Err(err), _) | (_, Err(err)) => {
+                    if !is_server_error(&err) {
+                        return Err(err);
+                    }
+                    if first_err.is_none() {
+                        first_err = Some(err);
+                    }
+                }
+            }
+        }
+        match first_err {
+            Some(err) => Err(err),
+            None => Ok(Value::Map(rv)),
+        }
+    }
+
+    fn parse_set(&mut self) -> RedisResult<Value> {
+        let length = try!(self.read_length());
+        Ok(Value::Set(try!(self.parse_values(length))))
+    }
+
+    fn parse_push(&mut self) -> RedisResult<Value> {
+        let length = try!(self.read_length());
+        Ok(Value::Push(try!(self.parse_values(length))))
+    }
+
+    fn parse_attribute(&mut self) -> RedisResult<Value> {
+        // attributes carry auxiliary information about the reply that
+        // follows them.  Nothing uses them yet so they are skipped.
+        try!(self.parse_map());
+        self.parse_value()
+    }
+
+    fn parse_blob_error(&mut self) -> RedisResult<Value> {
+        let length = try!(self.read_length());
+        let data = try!(self.read(length));
+        try!(self.expect_newline());
+        match String::from_utf8(data) {
+            Ok(line) => Err(make_error(&line)),
+            Err(_) => fail!((ErrorKind::ResponseError, "Expected valid string, got garbage")),
+        }
+    }
+
+    fn parse_error(&mut self) -> RedisResult<Value> {
+        let line = try!(self.read_string_line());
+        Err(make_error(&line))
+    }
+}
+
+/// The error for a connection that was closed in the middle of a value.
+fn unexpected_eof() -> RedisError {
+    From::from(io::Error::new(io::ErrorKind::UnexpectedEof, "Could not read enough bytes"))
+}
+
+/// Turns an error line sent by the server into the matching error.
+fn make_error(line: &str) -> RedisError {
+    let mut pieces = line.splitn(2, ' ');
+    let kind = match pieces.next().unwrap() {
+        "ERR" => ErrorKind::ResponseError,
+        "EXECABORT" => ErrorKind::ExecAbortError,
+        "LOADING" => ErrorKind::BusyLoadingError,
+        "NOSCRIPT" => ErrorKind::NoScriptError,
+        "CROSSSLOT" => ErrorKind::CrossSlot,
+        code => {
+            return make_extension_error(code, pieces.next());
+        }
+    };
+    make_server_error(kind, pieces.next())
 }
 
 
@@ -1,8 +1,13 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
 use sha1::Sha1;
 
-use cmd::cmd;
-use types::{ToRedisArgs, FromRedisValue, RedisResult, ErrorKind};
-use connection::ConnectionLike;
+use cmd::{cmd, Cmd, pack_borrowed_command};
+use types::{ToRedisArgs, FromRedisValue, RedisResult, ErrorKind, from_owned_redis_value,
+            add_command_to_error};
+use connection::{ConnectionAddr, ConnectionLike};
 
 /// Represents a lua script.
 pub struct Script {
@@ -41,13 +46,28 @@ impl Script {
         &self.hash
     }
 
+    /// Uploads the script to the server.  Invoking a script uploads it if
+    /// needed anyway, loading it eagerly on startup saves that round trip
+    /// on the first invocation.
+    pub fn load(&self, con: &ConnectionLike) -> RedisResult<()> {
+        let _: String = try!(self.load_cmd().query(con));
+        Ok(())
+    }
+
+    fn load_cmd(&self) -> Cmd {
+        let mut rv = cmd("SCRIPT");
+        rv.arg("LOAD").arg(self.code.as_bytes());
+        rv
+    }
+
     /// Creates a script invocation object with a key filled in.
     #[inline]
     pub fn key<T: ToRedisArgs>(&self, key: T) -> ScriptInvocation {
         ScriptInvocation {
             script: self,
             args: vec![],
-            keys: key.to_redis_args(),
+            keys: key.to_redis_args().into_iter().map(Cow::Owned).collect(),
+            read_only: false,
         }
     }
 
@@ -56,8 +76,9 @@ impl Script {
     pub fn arg<T: ToRedisArgs>(&self, arg: T) -> ScriptInvocation {
         ScriptInvocation {
             script: self,
-            args: arg.to_redis_args(),
+            args: arg.to_redis_args().into_iter().map(Cow::Owned).collect(),
             keys: vec![],
+            read_only: false,
         }
     }
 
@@ -70,26 +91,83 @@ impl Script {
             script: self,
             args: vec![],
             keys: vec![],
+            read_only: false,
         }
     }
 
     /// Invokes the script directly without arguments.
     #[inline]
     pub fn invoke<T: FromRedisValue>(&self, con: &ConnectionLike) -> RedisResult<T> {
-        ScriptInvocation {
-                script: self,
-                args: vec![],
-                keys: vec![],
-            }
-            .invoke(con)
+        self.prepare_invoke().invoke(con)
+    }
+}
+
+/// Composes a script from shared Lua snippets and a body.  The snippets
+/// are added in order, each one at most once, and trailing whitespace as
+/// well as leading and trailing blank lines are removed, so the same
+/// composition always results in the same code and hash.
+///
+/// ```rust
+/// const CLAMP: &'static str = "
+///     local function clamp(x, lo, hi)
+///         return math.max(lo, math.min(hi, x))
+///     end
+/// ";
+///
+/// let script = redis::ScriptBuilder::new()
+///     .helper(CLAMP)
+///     .build("return clamp(tonumber(ARGV[1]), 0, 100)");
+/// let same = redis::ScriptBuilder::new()
+///     .helper(CLAMP)
+///     .helper(CLAMP)
+///     .build("return clamp(tonumber(ARGV[1]), 0, 100)\n");
+/// assert_eq!(script.get_hash(), same.get_hash());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ScriptBuilder {
+    helpers: Vec<String>,
+}
+
+impl ScriptBuilder {
+    /// Creates a builder without snippets.
+    pub fn new() -> ScriptBuilder {
+        ScriptBuilder::default()
+    }
+
+    /// Adds a snippet that is placed before the body.  Adding a snippet
+    /// that was already added does nothing.
+    pub fn helper(mut self, code: &str) -> ScriptBuilder {
+        let code = normalize_snippet(code);
+        if !self.helpers.contains(&code) {
+            self.helpers.push(code);
+        }
+        self
+    }
+
+    /// Creates the script from the snippets followed by the body.
+    pub fn build(&self, body: &str) -> Script {
+        let mut code = self.helpers.join("\n");
+        if !code.is_empty() {
+            code.push('\n');
+        }
+        code.push_str(&normalize_snippet(body));
+        Script::new(&code)
     }
 }
 
+fn normalize_snippet(code: &str) -> String {
+    let lines: Vec<&str> = code.lines().map(|line| line.trim_end()).collect();
+    let start = lines.iter().position(|line| !line.is_empty()).unwrap_or(lines.len());
+    let end = lines.iter().rposition(|line| !line.is_empty()).map_or(start, |idx| idx + 1);
+    lines[start..end].join("\n")
+}
+
 /// Represents a prepared script call.
 pub struct ScriptInvocation<'a> {
     script: &'a Script,
-    args: Vec<Vec<u8>>,
-    keys: Vec<Vec<u8>>,
+    args: Vec<Cow<'a, [u8]>>,
+    keys: Vec<Cow<'a, [u8]>>,
+    read_only: bool,
 }
 
 /// This type collects keys and other arguments for the script so that it
@@ -103,7 +181,28 @@ impl<'a> ScriptInvocation<'a> {
     pub fn arg<'b, T: ToRedisArgs>(&'b mut self, arg: T) -> &'b mut ScriptInvocation<'a>
         where 'a: 'b
     {
-        self.args.extend(arg.to_redis_args().into_iter());
+        self.args.extend(arg.to_redis_args().into_iter().map(Cow::Owned));
+        self
+    }
+
+    /// Adds an already encoded argument to the invocation.  Unlike `arg`
+    /// this does not copy the data, which saves allocations for large
+    /// payloads.
+    ///
+    /// ```rust,no_run
+    /// # let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+    /// # let con = client.get_connection().unwrap();
+    /// let script = redis::Script::new("return redis.call('SET', KEYS[1], ARGV[1])");
+    /// let payload = vec![0u8; 1 << 20];
+    /// let mut invocation = script.prepare_invoke();
+    /// invocation.key_bytes(b"blob").arg_bytes(&payload);
+    /// let _: () = invocation.invoke(&con).unwrap();
+    /// ```
+    #[inline]
+    pub fn arg_bytes<'b>(&'b mut self, arg: &'a [u8]) -> &'b mut ScriptInvocation<'a>
+        where 'a: 'b
+    {
+        self.args.push(Cow::Borrowed(arg));
         self
     }
 
@@ -113,7 +212,27 @@ impl<'a> ScriptInvocation<'a> {
     pub fn key<'b, T: ToRedisArgs>(&'b mut self, key: T) -> &'b mut ScriptInvocation<'a>
         where 'a: 'b
     {
-        self.keys.extend(key.to_redis_args().into_iter());
+        self.keys.extend(key.to_redis_args().into_iter().map(Cow::Owned));
+        self
+    }
+
+    /// Adds an already encoded key to the invocation without copying it.
+    #[inline]
+    pub fn key_bytes<'b>(&'b mut self, key: &'a [u8]) -> &'b mut ScriptInvocation<'a>
+        where 'a: 'b
+    {
+        self.keys.push(Cow::Borrowed(key));
+        self
+    }
+
+    /// Invokes the script with `EVALSHA_RO` instead of `EVALSHA`.  The
+    /// script must not write, in turn it can run on replicas and cluster
+    /// connections route it like other read-only commands.
+    #[inline]
+    pub fn read_only<'b>(&'b mut self) -> &'b mut ScriptInvocation<'a>
+        where 'a: 'b
+    {
+        self.read_only = true;
         self
     }
 
@@ -121,21 +240,13 @@ impl<'a> ScriptInvocation<'a> {
     #[inline]
     pub fn invoke<T: FromRedisValue>(&self, con: &ConnectionLike) -> RedisResult<T> {
         loop {
-            match cmd("EVALSHA")
-                .arg(self.script.hash.as_bytes())
-                .arg(self.keys.len())
-                .arg(&*self.keys)
-                .arg(&*self.args)
-                .query(con) {
+            match self.query_eval(con) {
                 Ok(val) => {
                     return Ok(val);
                 }
                 Err(err) => {
                     if err.kind() == ErrorKind::NoScriptError {
-                        let _: () = try!(cmd("SCRIPT")
-                            .arg("LOAD")
-                            .arg(self.script.code.as_bytes())
-                            .query(con));
+                        let _: () = try!(self.script.load_cmd().query(con));
                     } else {
                         fail!(err);
                     }
@@ -143,4 +254,134 @@ impl<'a> ScriptInvocation<'a> {
             }
         }
     }
+
+    fn eval_cmd(&self) -> Cmd {
+        let mut rv = cmd(self.command());
+        rv.arg(self.script.hash.as_bytes()).arg(self.keys.len());
+        for item in self.keys.iter().chain(self.args.iter()) {
+            rv.arg(&item[..]);
+        }
+        rv
+    }
+
+    fn command(&self) -> &'static str {
+        if self.read_only { "EVALSHA_RO" } else { "EVALSHA" }
+    }
+
+    /// Sends `EVALSHA` with the keys and arguments encoded straight from
+    /// their buffers.
+    fn query_eval<T: FromRedisValue>(&self, con: &ConnectionLike) -> RedisResult<T> {
+        let numkeys = self.keys.len().to_string();
+        let mut args: Vec<&[u8]> = vec![self.command().as_bytes(),
+                                        self.script.hash.as_bytes(),
+                                        numkeys.as_bytes()];
+        args.extend(self.keys.iter().chain(self.args.iter()).map(|item| &item[..]));
+        let reply = try!(con.req_packed_command(&pack_borrowed_command(&args)));
+        from_owned_redis_value(reply).map_err(|err| add_command_to_error(err, self.command()))
+    }
+}
+
+/// Returns the hash of the script, the command that loads it and the
+/// command that invokes it, which is how pipelines run scripts.
+pub fn pipeline_script_commands(invocation: &ScriptInvocation) -> (String, Cmd, Cmd) {
+    (invocation.script.hash.clone(), invocation.script.load_cmd(), invocation.eval_cmd())
+}
+
+
+/// A set of named scripts that remembers which of them the server is
+/// known to have loaded.  Known scripts are invoked with `EVALSHA` right
+/// away, other scripts are loaded first, so invocations only run into
+/// `NOSCRIPT` if the script cache of the server was flushed.  The loaded
+/// scripts are tracked per address and database of the connection, so a
+/// manager can be shared by connections to different servers.
+///
+/// ```rust,no_run
+/// # let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+/// # let con = client.get_connection().unwrap();
+/// let scripts = redis::ScriptManager::new()
+///     .add("incr_by", redis::Script::new("return redis.call('INCRBY', KEYS[1], ARGV[1])"));
+/// scripts.preload_all(&con).unwrap();
+///
+/// let script = scripts.get("incr_by").unwrap();
+/// let value: i64 = scripts.invoke(script.key("counter").arg(5), &con).unwrap();
+/// ```
+#[derive(Default)]
+pub struct ScriptManager {
+    scripts: HashMap<String, Script>,
+    loaded: Mutex<HashMap<(Option<ConnectionAddr>, i64), HashSet<String>>>,
+}
+
+impl ScriptManager {
+    /// Creates a manager without scripts.
+    pub fn new() -> ScriptManager {
+        ScriptManager::default()
+    }
+
+    /// Adds a script under the given name, replacing a script that had
+    /// the same name.
+    pub fn add(mut self, name: &str, script: Script) -> ScriptManager {
+        self.scripts.insert(name.to_string(), script);
+        self
+    }
+
+    /// Returns the script with the given name.
+    pub fn get(&self, name: &str) -> Option<&Script> {
+        self.scripts.get(name)
+    }
+
+    /// Checks whether the script is known to be loaded on the server of
+    /// the connection.
+    pub fn is_loaded(&self, script: &Script, con: &ConnectionLike) -> bool {
+        match self.loaded.lock().unwrap().get(&server_key(con)) {
+            Some(loaded) => loaded.contains(&script.hash),
+            None => false,
+        }
+    }
+
+    /// Loads all scripts.  On a cluster connection they are loaded on
+    /// every master.
+    pub fn preload_all(&self, con: &ConnectionLike) -> RedisResult<()> {
+        for script in self.scripts.values() {
+            try!(self.load(script, con));
+        }
+        Ok(())
+    }
+
+    /// Invokes a script, loading it first unless it is known to be
+    /// loaded.  The script does not have to be one of the managed
+    /// scripts.
+    pub fn invoke<T: FromRedisValue>(&self,
+                                     invocation: &ScriptInvocation,
+                                     con: &ConnectionLike)
+                                     -> RedisResult<T> {
+        if !self.is_loaded(invocation.script, con) {
+            try!(self.load(invocation.script, con));
+        }
+        match invocation.query_eval(con) {
+            Err(ref err) if err.kind() == ErrorKind::NoScriptError => {
+                if let Some(loaded) = self.loaded.lock().unwrap().get_mut(&server_key(con)) {
+                    loaded.remove(&invocation.script.hash);
+                }
+                try!(self.load(invocation.script, con));
+                invocation.query_eval(con)
+            }
+            result => result,
+        }
+    }
+
+    fn load(&self, script: &Script, con: &ConnectionLike) -> RedisResult<()> {
+        try!(script.load(con));
+        self.loaded
+            .lock()
+            .unwrap()
+            .entry(server_key(con))
+            .or_insert_with(HashSet::new)
+            .insert(script.hash.clone());
+        Ok(())
+    }
+}
+
+/// Identifies the server whose script cache a connection uses.
+fn server_key(con: &ConnectionLike) -> (Option<ConnectionAddr>, i64) {
+    (con.get_addr(), con.get_db())
 }
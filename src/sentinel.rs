@@ -0,0 +1,350 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use cmd::cmd;
+use connection::{connect, connect_pubsub, Connection, ConnectionAddr, ConnectionInfo,
+                 ConnectionLike, IntoConnectionInfo};
+use types::{RedisResult, RedisError, ErrorKind, Value, from_redis_value};
+
+
+/// A client for redis deployments that are managed by sentinel.
+///
+/// Instead of a fixed address the client knows a list of sentinels and
+/// the name of the monitored service.  Whenever a connection is requested
+/// the sentinels are asked for the current master so that connections
+/// end up on the right server after a failover.
+///
+/// Example usage:
+///
+/// ```rust,no_run
+/// let sentinel = redis::SentinelClient::open(vec!["redis://10.0.0.1:26379/",
+///                                                 "redis://10.0.0.2:26379/"],
+///                                            "mymaster").unwrap();
+/// let con = sentinel.get_connection().unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct SentinelClient {
+    sentinels: Vec<ConnectionInfo>,
+    service_name: String,
+    node_info: ConnectionInfo,
+    next_replica: Arc<AtomicUsize>,
+}
+
+impl SentinelClient {
+    /// Creates a client for the given sentinels and service name.  This
+    /// does not connect to the sentinels yet.
+    ///
+    /// Connections to the master use the transport settings (timeouts,
+    /// TLS and so on) of the first sentinel but no password, client name
+    /// or database.  Use `set_node_connection_info` to change that.
+    pub fn open<T: IntoConnectionInfo>(sentinels: Vec<T>,
+                                       service_name: &str)
+                                       -> RedisResult<SentinelClient> {
+        let mut infos = vec![];
+        for sentinel in sentinels {
+            infos.push(try!(sentinel.into_connection_info()));
+        }
+        let mut node_info = match infos.first() {
+            Some(info) => info.clone(),
+            None => fail!((ErrorKind::InvalidClientConfig, "At least one sentinel is required")),
+        };
+        node_info.fallback_addrs = vec![];
+        node_info.db = 0;
+        node_info.username = None;
+        node_info.passwd = None;
+        node_info.client_name = None;
+        Ok(SentinelClient {
+            sentinels: infos,
+            service_name: service_name.to_string(),
+            node_info: node_info,
+            next_replica: Arc::new(AtomicUsize::new(0)),
+        })
+    }
+
+    /// Sets the connection info that is used for connections to the
+    /// redis servers of the service.  Its address is replaced by the one
+    /// the sentinels report.  A `TcpTls` address makes the connections
+    /// use TLS with the same verification setting.
+    pub fn set_node_connection_info(&mut self, info: ConnectionInfo) {
+        self.node_info = info;
+    }
+
+    /// Returns the name of the service the sentinels are asked about.
+    pub fn get_service_name(&self) -> &str {
+        &self.service_name
+    }
+
+    /// Asks the sentinels in order for the address of the current master
+    /// and returns the first answer.
+    pub fn get_master_addr(&self) -> RedisResult<ConnectionAddr> {
+        let mut last_err = None;
+        for sentinel in self.sentinels.iter() {
+            let con = match connect(sentinel) {
+                Ok(con) => con,
+                Err(err) => {
+                    last_err = Some(err);
+                    continue;
+                }
+            };
+            match cmd("SENTINEL")
+                .arg("get-master-addr-by-name")
+                .arg(&*self.service_name)
+                .query::<Option<(String, u16)>>(&con) {
+                Ok(Some((host, port))) => return Ok(self.node_addr(host, port)),
+                Ok(None) => {
+                    last_err = Some(RedisError::from((ErrorKind::ResponseError,
+                                                      "Sentinel does not know the service",
+                                                      self.service_name.clone())));
+                }
+                Err(err) => last_err = Some(err),
+            }
+        }
+        Err(last_err.unwrap())
+    }
+
+    /// Returns the connection info for the current master.
+    pub fn get_master_connection_info(&self) -> RedisResult<ConnectionInfo> {
+        let mut info = self.node_info.clone();
+        info.addr = Box::new(try!(self.get_master_addr()));
+        Ok(info)
+    }
+
+    /// Connects to the current master.  The server is checked to be a
+    /// master as sentinels can briefly report stale addresses during a
+    /// failover.
+    pub fn get_connection(&self) -> RedisResult<Connection> {
+        self.connect_master(self.node_info.db)
+    }
+
+    /// Connects to the current master and keeps following it.  The
+    /// returned connection subscribes to the `+switch-master` events of
+    /// the sentinels and switches over to the new master after a
+    /// failover.  It also looks up the master again if the connection
+    /// is lost or the server turned into a replica.
+    ///
+    /// The command that is running while the master changes fails, the
+    /// following ones are sent to the new master.
+    pub fn get_failover_connection(&self) -> RedisResult<SentinelConnection> {
+        let con = try!(self.get_connection());
+        let master_changed = Arc::new(AtomicBool::new(false));
+        let closed = Arc::new(AtomicBool::new(false));
+        spawn_switch_master_watcher(self.clone(), master_changed.clone(), closed.clone());
+        Ok(SentinelConnection {
+            client: self.clone(),
+            con: RefCell::new(con),
+            master_changed: master_changed,
+            closed: closed,
+        })
+    }
+
+    /// Asks the sentinels in order for the replicas of the service and
+    /// returns the addresses of those that are reachable according to
+    /// the first sentinel that answers.
+    pub fn get_replica_addrs(&self) -> RedisResult<Vec<ConnectionAddr>> {
+        let mut last_err = None;
+        for sentinel in self.sentinels.iter() {
+            let con = match connect(sentinel) {
+                Ok(con) => con,
+                Err(err) => {
+                    last_err = Some(err);
+                    continue;
+                }
+            };
+            match cmd("SENTINEL")
+                .arg("replicas")
+                .arg(&*self.service_name)
+                .query::<Vec<HashMap<String, String>>>(&con) {
+                Ok(replicas) => {
+                    let mut rv = vec![];
+                    for replica in replicas {
+                        if let Some(addr) = self.replica_addr(&replica) {
+                            rv.push(addr);
+                        }
+                    }
+                    return Ok(rv);
+                }
+                Err(err) => last_err = Some(err),
+            }
+        }
+        Err(last_err.unwrap())
+    }
+
+    /// Connects to one of the replicas of the service.  Replicas are
+    /// picked round-robin across calls; if a replica cannot be reached the
+    /// next one is tried.  Without any replica the connection goes to the
+    /// master so that reads keep working.
+    ///
+    /// Note that replicas can lag behind the master.
+    pub fn get_replica_connection(&self) -> RedisResult<Connection> {
+        let replicas = try!(self.get_replica_addrs());
+        let start = self.next_replica.fetch_add(1, Ordering::SeqCst);
+        for idx in 0..replicas.len() {
+            let mut info = self.node_info.clone();
+            info.addr = Box::new(replicas[(start + idx) % replicas.len()].clone());
+            if let Ok(con) = connect(&info) {
+                return Ok(con);
+            }
+        }
+        self.get_connection()
+    }
+
+    fn replica_addr(&self, replica: &HashMap<String, String>) -> Option<ConnectionAddr> {
+        let flags = replica.get("flags").map_or("", |flags| &flags[..]);
+        let is_down = |flag: &str| flag == "s_down" || flag == "o_down" || flag == "disconnected";
+        if flags.split(',').any(is_down) {
+            return None;
+        }
+        match (replica.get("ip"), replica.get("port").and_then(|port| port.parse().ok())) {
+            (Some(ip), Some(port)) => Some(self.node_addr(ip.clone(), port)),
+            _ => None,
+        }
+    }
+
+    fn connect_master(&self, db: i64) -> RedisResult<Connection> {
+        let mut info = try!(self.get_master_connection_info());
+        info.db = db;
+        let con = try!(connect(&info));
+        let role: Vec<Value> = try!(cmd("ROLE").query(&con));
+        match role.first().map(from_redis_value::<String>) {
+            Some(Ok(ref role)) if role == "master" => Ok(con),
+            _ => fail!((ErrorKind::ResponseError, "Server reported by sentinel is not a master")),
+        }
+    }
+
+    fn node_addr(&self, host: String, port: u16) -> ConnectionAddr {
+        match *self.node_info.addr {
+            ConnectionAddr::TcpTls { insecure, .. } => {
+                ConnectionAddr::TcpTls {
+                    host: host,
+                    port: port,
+                    insecure: insecure,
+                }
+            }
+            _ => ConnectionAddr::Tcp(host, port),
+        }
+    }
+}
+
+
+/// A connection to the master of a sentinel managed service that
+/// follows failovers.  It is created with
+/// `SentinelClient::get_failover_connection`.
+///
+/// On a switch the connection is set up again, which means the password,
+/// client name and the selected database are restored.
+pub struct SentinelConnection {
+    client: SentinelClient,
+    con: RefCell<Connection>,
+    master_changed: Arc<AtomicBool>,
+    closed: Arc<AtomicBool>,
+}
+
+impl SentinelConnection {
+    /// Connects to the master the sentinels currently report.
+    pub fn reconnect(&self) -> RedisResult<()> {
+        let db = self.con.borrow().get_db();
+        let con = try!(self.client.connect_master(db));
+        *self.con.borrow_mut() = con;
+        Ok(())
+    }
+
+    fn check_master(&self) -> RedisResult<()> {
+        if self.master_changed.swap(false, Ordering::SeqCst) {
+            try!(self.reconnect());
+        }
+        Ok(())
+    }
+
+    fn handle_error<T>(&self, result: RedisResult<T>) -> RedisResult<T> {
+        if let Err(ref err) = result {
+            if err.is_connection_dropped() || err.extension_error_code() == Some("READONLY") {
+                // the error is still reported but the next command goes
+                // to the new master if one could be found.
+                let _ = self.reconnect();
+            }
+        }
+        result
+    }
+}
+
+impl ConnectionLike for SentinelConnection {
+    fn req_packed_command(&self, cmd: &[u8]) -> RedisResult<Value> {
+        try!(self.check_master());
+        let result = self.con.borrow().req_packed_command(cmd);
+        self.handle_error(result)
+    }
+
+    fn req_packed_commands(&self,
+                           cmd: &[u8],
+                           offset: usize,
+                           count: usize)
+                           -> RedisResult<Vec<Value>> {
+        try!(self.check_master());
+        let result = self.con.borrow().req_packed_commands(cmd, offset, count);
+        self.handle_error(result)
+    }
+
+    fn get_db(&self) -> i64 {
+        self.con.borrow().get_db()
+    }
+
+    fn get_addr(&self) -> Option<ConnectionAddr> {
+        self.con.borrow().get_addr()
+    }
+}
+
+impl Drop for SentinelConnection {
+    fn drop(&mut self) {
+        self.closed.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Listens for `+switch-master` events of the service on one of the
+/// sentinels and sets `master_changed` when one arrives.  The thread
+/// stops once `closed` is set.
+fn spawn_switch_master_watcher(client: SentinelClient,
+                               master_changed: Arc<AtomicBool>,
+                               closed: Arc<AtomicBool>) {
+    thread::spawn(move || {
+        while !closed.load(Ordering::SeqCst) {
+            for sentinel in client.sentinels.iter() {
+                let _ = watch_sentinel(&client, sentinel, &master_changed, &closed);
+                if closed.load(Ordering::SeqCst) {
+                    return;
+                }
+            }
+            thread::sleep(Duration::from_secs(1));
+        }
+    });
+}
+
+fn watch_sentinel(client: &SentinelClient,
+                  sentinel: &ConnectionInfo,
+                  master_changed: &AtomicBool,
+                  closed: &AtomicBool)
+                  -> RedisResult<()> {
+    let mut pubsub = try!(connect_pubsub(sentinel));
+    try!(pubsub.subscribe("+switch-master"));
+    // wake up regularly to notice when the connection was dropped
+    try!(pubsub.set_read_timeout(Some(Duration::from_secs(1))));
+    while !closed.load(Ordering::SeqCst) {
+        match pubsub.get_message() {
+            // a switch may have been missed while the connection was down
+            Ok(ref msg) if msg.is_resubscribed() => master_changed.store(true, Ordering::SeqCst),
+            Ok(msg) => {
+                // the payload is "<name> <old-ip> <old-port> <new-ip> <new-port>"
+                let payload: String = try!(msg.get_payload());
+                if payload.split(' ').next() == Some(&*client.service_name) {
+                    master_changed.store(true, Ordering::SeqCst);
+                }
+            }
+            Err(ref err) if err.is_timeout() => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
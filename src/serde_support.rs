@@ -0,0 +1,91 @@
+use bincode;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json;
+
+use types::{FromRedisValue, ToRedisArgs, RedisResult, ErrorKind, Value};
+
+
+/// Stores a value of any serde type in its compact binary encoding, which
+/// is the one of the `bincode` crate.  Use it for string values and hash
+/// fields alike:
+///
+/// ```rust,no_run
+/// # #[macro_use] extern crate serde_derive;
+/// # extern crate redis;
+/// use redis::{Commands, Serde};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct User {
+///     name: String,
+///     age: u32,
+/// }
+///
+/// # fn main() {
+/// # let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+/// # let con = client.get_connection().unwrap();
+/// let user = User { name: "Jane".to_string(), age: 42 };
+/// let _: () = con.hset("users", 1, Serde(&user)).unwrap();
+/// let Serde(user): Serde<User> = con.hget("users", 1).unwrap();
+/// # }
+/// ```
+///
+/// Encoding panics if serde fails to encode the value, which only happens
+/// for types with broken `Serialize` implementations.  The encoding
+/// depends on the field order, so changing the type makes values that were
+/// stored before unreadable.  Use `SerdeJson` if that is a concern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Serde<T>(pub T);
+
+impl<T: Serialize> ToRedisArgs for Serde<T> {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        vec![bincode::serialize(&self.0).expect("value cannot be encoded with bincode")]
+    }
+}
+
+impl<T: DeserializeOwned> FromRedisValue for Serde<T> {
+    fn from_redis_value(v: &Value) -> RedisResult<Serde<T>> {
+        match *v {
+            Value::Data(ref bytes) => {
+                match bincode::deserialize(bytes) {
+                    Ok(value) => Ok(Serde(value)),
+                    Err(err) => {
+                        fail!((ErrorKind::TypeError, "Invalid bincode value", err.to_string()))
+                    }
+                }
+            }
+            _ => fail!((ErrorKind::TypeError, "Response type not bincode compatible")),
+        }
+    }
+}
+
+
+/// Stores a value of any serde type as JSON.  This is larger than the
+/// encoding of `Serde` but readable by other clients and tolerant to
+/// fields being added to the type.
+///
+/// Encoding panics if the value cannot be represented in JSON, for
+/// instance a map whose keys are not strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct SerdeJson<T>(pub T);
+
+impl<T: Serialize> ToRedisArgs for SerdeJson<T> {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        vec![serde_json::to_vec(&self.0).expect("value cannot be encoded as JSON")]
+    }
+}
+
+impl<T: DeserializeOwned> FromRedisValue for SerdeJson<T> {
+    fn from_redis_value(v: &Value) -> RedisResult<SerdeJson<T>> {
+        let rv = match *v {
+            Value::Data(ref bytes) => serde_json::from_slice(bytes),
+            Value::Status(ref s) => serde_json::from_str(s),
+            Value::Int(val) => serde_json::from_str(&val.to_string()),
+            _ => fail!((ErrorKind::TypeError, "Response type not JSON compatible")),
+        };
+        match rv {
+            Ok(value) => Ok(SerdeJson(value)),
+            Err(err) => fail!((ErrorKind::TypeError, "Invalid JSON value", err.to_string())),
+        }
+    }
+}
@@ -0,0 +1,903 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
+
+use types::{FromRedisValue, ToRedisArgs, RedisResult, ErrorKind, Value, InfoDict,
+            from_redis_value};
+
+
+/// The internal representation of a value as returned by
+/// `OBJECT ENCODING`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Encoding {
+    /// A plain string.
+    Raw,
+    /// A string that holds a 64 bit integer.
+    Int,
+    /// A short string allocated together with its object.
+    EmbStr,
+    /// A compact list, set, hash or sorted set.
+    ListPack,
+    /// The compact encoding of small lists and hashes before redis 7.
+    ZipList,
+    /// A linked list of listpacks or ziplists.
+    QuickList,
+    /// A linked list, only used by old servers.
+    LinkedList,
+    /// A hash table.
+    HashTable,
+    /// A set of integers.
+    IntSet,
+    /// A skip list.
+    SkipList,
+    /// A stream.
+    Stream,
+    /// An encoding this library does not know about.
+    Other(String),
+}
+
+impl FromRedisValue for Encoding {
+    fn from_redis_value(v: &Value) -> RedisResult<Encoding> {
+        let s: String = try!(from_redis_value(v));
+        Ok(match &s[..] {
+            "raw" => Encoding::Raw,
+            "int" => Encoding::Int,
+            "embstr" => Encoding::EmbStr,
+            "listpack" => Encoding::ListPack,
+            "ziplist" => Encoding::ZipList,
+            "quicklist" => Encoding::QuickList,
+            "linkedlist" => Encoding::LinkedList,
+            "hashtable" => Encoding::HashTable,
+            "intset" => Encoding::IntSet,
+            "skiplist" => Encoding::SkipList,
+            "stream" => Encoding::Stream,
+            _ => Encoding::Other(s),
+        })
+    }
+}
+
+
+/// The reply of `MEMORY STATS`.  The most useful numbers are available
+/// as fields, all others including the per database overheads can be
+/// looked up with `get`:
+///
+/// ```rust,no_run
+/// use redis::{Commands, MemoryStats};
+/// # let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+/// # let con = client.get_connection().unwrap();
+/// let stats: MemoryStats = con.memory_stats().unwrap();
+/// let clients: Option<u64> = stats.get("clients.normal");
+/// println!("{} bytes used, {:?} by clients", stats.total_allocated, clients);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryStats {
+    /// The peak number of bytes allocated by the server.
+    pub peak_allocated: u64,
+    /// The number of bytes currently allocated by the server.
+    pub total_allocated: u64,
+    /// The number of bytes allocated at startup.
+    pub startup_allocated: u64,
+    /// The number of bytes used for bookkeeping rather than data.
+    pub overhead_total: u64,
+    /// The number of keys in all databases.
+    pub keys_count: u64,
+    /// The number of bytes used by the data itself.
+    pub dataset_bytes: u64,
+    /// The ratio of memory used by the process to memory allocated.
+    pub fragmentation: f64,
+    fields: HashMap<String, Value>,
+}
+
+impl MemoryStats {
+    /// Looks up a field of the reply by its name and converts it into
+    /// the given type.
+    pub fn get<T: FromRedisValue>(&self, field: &str) -> Option<T> {
+        self.fields.get(field).and_then(|v| from_redis_value(v).ok())
+    }
+}
+
+impl FromRedisValue for MemoryStats {
+    fn from_redis_value(v: &Value) -> RedisResult<MemoryStats> {
+        let fields: HashMap<String, Value> = try!(from_redis_value(v));
+        let mut rv = MemoryStats {
+            peak_allocated: 0,
+            total_allocated: 0,
+            startup_allocated: 0,
+            overhead_total: 0,
+            keys_count: 0,
+            dataset_bytes: 0,
+            fragmentation: 0.0,
+            fields: fields,
+        };
+        rv.peak_allocated = rv.get("peak.allocated").unwrap_or(0);
+        rv.total_allocated = rv.get("total.allocated").unwrap_or(0);
+        rv.startup_allocated = rv.get("startup.allocated").unwrap_or(0);
+        rv.overhead_total = rv.get("overhead.total").unwrap_or(0);
+        rv.keys_count = rv.get("keys.count").unwrap_or(0);
+        rv.dataset_bytes = rv.get("dataset.bytes").unwrap_or(0);
+        rv.fragmentation = rv.get("fragmentation").unwrap_or(0.0);
+        Ok(rv)
+    }
+}
+
+
+/// A client connection as reported by `CLIENT LIST` and `CLIENT INFO`.
+///
+/// ```rust,no_run
+/// use redis::{ClientInfo, Commands};
+/// # let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+/// # let con = client.get_connection().unwrap();
+/// let clients: Vec<ClientInfo> = con.client_list().unwrap();
+/// for client in clients.iter().filter(|client| client.idle > 60) {
+///     println!("{} has been idle since running {}", client.addr, client.cmd);
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClientInfo {
+    /// The unique id of the connection.
+    pub id: u64,
+    /// The address of the client.
+    pub addr: String,
+    /// The name the client gave itself, if any.
+    pub name: Option<String>,
+    /// The age of the connection in seconds.
+    pub age: u64,
+    /// The number of seconds the connection has been idle.
+    pub idle: u64,
+    /// The flags of the connection, for instance `N` for a normal client
+    /// or `P` for a subscriber.
+    pub flags: String,
+    /// The currently selected database.
+    pub db: i64,
+    /// The last command the client ran.
+    pub cmd: String,
+    fields: HashMap<String, String>,
+}
+
+impl ClientInfo {
+    /// Parses one line of the reply of `CLIENT LIST`.
+    pub fn parse(line: &str) -> RedisResult<ClientInfo> {
+        let fields: HashMap<String, String> = line.split_whitespace()
+            .filter_map(|field| {
+                let mut parts = field.splitn(2, '=');
+                match (parts.next(), parts.next()) {
+                    (Some(key), Some(value)) => Some((key.to_string(), value.to_string())),
+                    _ => None,
+                }
+            })
+            .collect();
+        if !fields.contains_key("id") {
+            fail!((ErrorKind::TypeError, "Invalid CLIENT LIST line", line.to_string()));
+        }
+        Ok(ClientInfo {
+            id: try!(parse_field(&fields, "id", line)),
+            addr: fields.get("addr").cloned().unwrap_or_default(),
+            name: fields.get("name").and_then(|name| {
+                if name.is_empty() { None } else { Some(name.clone()) }
+            }),
+            age: try!(parse_field(&fields, "age", line)),
+            idle: try!(parse_field(&fields, "idle", line)),
+            flags: fields.get("flags").cloned().unwrap_or_default(),
+            db: try!(parse_field(&fields, "db", line)),
+            cmd: fields.get("cmd").cloned().unwrap_or_default(),
+            fields: fields,
+        })
+    }
+
+    /// Looks up any field of the line, like `laddr`, `user` or `lib-name`.
+    pub fn get(&self, field: &str) -> Option<&str> {
+        self.fields.get(field).map(|value| &value[..])
+    }
+
+    /// Checks if the connection has the given flag.
+    pub fn has_flag(&self, flag: char) -> bool {
+        self.flags.contains(flag)
+    }
+}
+
+fn parse_field<T: FromStr + Default>(fields: &HashMap<String, String>,
+                                     field: &str,
+                                     line: &str)
+                                     -> RedisResult<T> {
+    match fields.get(field) {
+        Some(value) => match value.parse() {
+            Ok(rv) => Ok(rv),
+            Err(_) => fail!((ErrorKind::TypeError, "Invalid CLIENT LIST line", line.to_string())),
+        },
+        None => Ok(T::default()),
+    }
+}
+
+impl FromRedisValue for ClientInfo {
+    fn from_redis_value(v: &Value) -> RedisResult<ClientInfo> {
+        let line: String = try!(from_redis_value(v));
+        ClientInfo::parse(line.trim())
+    }
+
+    fn from_byte_vec(vec: &[u8]) -> Option<Vec<ClientInfo>> {
+        String::from_utf8_lossy(vec)
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(ClientInfo::parse)
+            .collect::<RedisResult<_>>()
+            .ok()
+    }
+}
+
+
+/// The kind of a client connection.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClientType {
+    /// A regular client.
+    Normal,
+    /// The connection of a replica to its master.
+    Master,
+    /// The connection of a master to its replica.
+    Replica,
+    /// A client in subscriber mode.
+    PubSub,
+}
+
+impl ToRedisArgs for ClientType {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        let ty = match *self {
+            ClientType::Normal => "normal",
+            ClientType::Master => "master",
+            ClientType::Replica => "replica",
+            ClientType::PubSub => "pubsub",
+        };
+        vec![ty.as_bytes().to_vec()]
+    }
+}
+
+
+/// Which commands `CLIENT PAUSE` holds back.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PauseMode {
+    /// All commands of all normal and pubsub clients.
+    All,
+    /// Only commands that may change data.
+    Write,
+}
+
+impl ToRedisArgs for PauseMode {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        let mode = match *self {
+            PauseMode::All => "ALL",
+            PauseMode::Write => "WRITE",
+        };
+        vec![mode.as_bytes().to_vec()]
+    }
+}
+
+
+
+/// Options for `CLIENT TRACKING`, which makes the server send
+/// invalidation messages for keys the connection read, as a base for
+/// client side caching.
+///
+/// ```rust,no_run
+/// use redis::{Commands, TrackingOptions};
+/// # let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+/// # let con = client.get_connection().unwrap();
+/// # let invalidations_id = 0;
+/// let options = TrackingOptions::default()
+///     .redirect(invalidations_id)
+///     .bcast()
+///     .prefix("user:")
+///     .noloop();
+/// let _: () = con.client_tracking(true, options).unwrap();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TrackingOptions {
+    redirect: Option<u64>,
+    prefixes: Vec<String>,
+    bcast: bool,
+    optin: bool,
+    optout: bool,
+    noloop: bool,
+}
+
+impl TrackingOptions {
+    /// Sends the invalidation messages to the connection with the given
+    /// id, which has to subscribe to `__redis__:invalidate`.
+    pub fn redirect(mut self, client_id: u64) -> TrackingOptions {
+        self.redirect = Some(client_id);
+        self
+    }
+
+    /// Tracks all keys with one of the given prefixes instead of the keys
+    /// the connection read, can be given several times.
+    pub fn prefix(mut self, prefix: &str) -> TrackingOptions {
+        self.prefixes.push(prefix.to_string());
+        self
+    }
+
+    /// Tracks keys by prefix, all keys if no prefix is given.
+    pub fn bcast(mut self) -> TrackingOptions {
+        self.bcast = true;
+        self
+    }
+
+    /// Only tracks the keys read right after `CLIENT CACHING yes`.
+    pub fn optin(mut self) -> TrackingOptions {
+        self.optin = true;
+        self
+    }
+
+    /// Tracks all keys read except right after `CLIENT CACHING no`.
+    pub fn optout(mut self) -> TrackingOptions {
+        self.optout = true;
+        self
+    }
+
+    /// Does not send invalidations for keys the connection changed
+    /// itself.
+    pub fn noloop(mut self) -> TrackingOptions {
+        self.noloop = true;
+        self
+    }
+}
+
+impl ToRedisArgs for TrackingOptions {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        let mut rv = vec![];
+        if let Some(client_id) = self.redirect {
+            rv.push(b"REDIRECT".to_vec());
+            rv.extend(client_id.to_redis_args());
+        }
+        for prefix in self.prefixes.iter() {
+            rv.push(b"PREFIX".to_vec());
+            rv.extend(prefix.to_redis_args());
+        }
+        if self.bcast {
+            rv.push(b"BCAST".to_vec());
+        }
+        if self.optin {
+            rv.push(b"OPTIN".to_vec());
+        }
+        if self.optout {
+            rv.push(b"OPTOUT".to_vec());
+        }
+        if self.noloop {
+            rv.push(b"NOLOOP".to_vec());
+        }
+        rv
+    }
+}
+
+
+/// Whether `SHUTDOWN` saves the data first.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShutdownMode {
+    /// Exits without saving, even if save points are configured.
+    NoSave,
+    /// Saves the data before exiting, even without save points.
+    Save,
+}
+
+impl ToRedisArgs for ShutdownMode {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        let mode = match *self {
+            ShutdownMode::NoSave => "NOSAVE",
+            ShutdownMode::Save => "SAVE",
+        };
+        vec![mode.as_bytes().to_vec()]
+    }
+}
+
+
+/// Selects the connections `CLIENT KILL` closes.  A connection has to
+/// match all filters, the reply is the number of closed connections:
+///
+/// ```rust,no_run
+/// use redis::{ClientKillFilter, ClientType, Commands};
+/// # let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+/// # let con = client.get_connection().unwrap();
+/// let filter = ClientKillFilter::new().user("worker").client_type(ClientType::PubSub);
+/// let killed: usize = con.client_kill(filter).unwrap();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClientKillFilter {
+    args: Vec<Vec<u8>>,
+}
+
+impl ClientKillFilter {
+    /// Creates an empty filter, the server needs at least one filter.
+    pub fn new() -> ClientKillFilter {
+        ClientKillFilter::default()
+    }
+
+    /// Matches the connection with the given id.
+    pub fn id(self, id: u64) -> ClientKillFilter {
+        self.push(("ID", id))
+    }
+
+    /// Matches the connection from the given address as `ip:port`.
+    pub fn addr<A: ToRedisArgs>(self, addr: A) -> ClientKillFilter {
+        self.push(("ADDR", addr))
+    }
+
+    /// Matches connections to the given local address of the server.
+    pub fn laddr<A: ToRedisArgs>(self, laddr: A) -> ClientKillFilter {
+        self.push(("LADDR", laddr))
+    }
+
+    /// Matches connections of the given kind.
+    pub fn client_type(self, ty: ClientType) -> ClientKillFilter {
+        self.push(("TYPE", ty))
+    }
+
+    /// Matches connections authenticated as the given user.
+    pub fn user<U: ToRedisArgs>(self, user: U) -> ClientKillFilter {
+        self.push(("USER", user))
+    }
+
+    /// Sets whether the current connection is spared, which it is by
+    /// default.
+    pub fn skip_me(self, skip: bool) -> ClientKillFilter {
+        self.push(("SKIPME", if skip { "yes" } else { "no" }))
+    }
+
+    fn push<T: ToRedisArgs>(mut self, filter: T) -> ClientKillFilter {
+        self.args.extend(filter.to_redis_args());
+        self
+    }
+}
+
+impl ToRedisArgs for ClientKillFilter {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        self.args.clone()
+    }
+}
+
+
+/// A command recorded by the slow log, see `slowlog_get`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlowLogEntry {
+    /// The unique id of the entry.
+    pub id: u64,
+    /// The unix time in seconds the command was run at.
+    pub timestamp: u64,
+    /// The time it took to run the command.
+    pub duration: Duration,
+    /// The command and its arguments, possibly shortened by the server.
+    pub args: Vec<String>,
+    /// The address of the client.  Old servers do not report it.
+    pub client_addr: Option<String>,
+    /// The name of the client, if it has one.
+    pub client_name: Option<String>,
+}
+
+impl FromRedisValue for SlowLogEntry {
+    fn from_redis_value(v: &Value) -> RedisResult<SlowLogEntry> {
+        let items: Vec<Value> = try!(from_redis_value(v));
+        if items.len() < 4 {
+            fail!((ErrorKind::TypeError, "Invalid SLOWLOG entry", format!("{:?}", v)));
+        }
+        let micros: u64 = try!(from_redis_value(&items[2]));
+        let client_name = match items.get(5) {
+            Some(name) => try!(from_redis_value::<String>(name)),
+            None => String::new(),
+        };
+        Ok(SlowLogEntry {
+            id: try!(from_redis_value(&items[0])),
+            timestamp: try!(from_redis_value(&items[1])),
+            duration: Duration::new(micros / 1_000_000, (micros % 1_000_000) as u32 * 1000),
+            args: try!(from_redis_value(&items[3])),
+            client_addr: match items.get(4) {
+                Some(addr) => Some(try!(from_redis_value(addr))),
+                None => None,
+            },
+            client_name: if client_name.is_empty() { None } else { Some(client_name) },
+        })
+    }
+}
+
+
+/// The memory section of `INFO`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MemoryInfo {
+    /// The number of bytes allocated by the server.
+    pub used_memory: u64,
+    /// The number of bytes the operating system sees the server use.
+    pub used_memory_rss: u64,
+    /// The peak number of bytes allocated by the server.
+    pub used_memory_peak: u64,
+    /// The memory limit in bytes, 0 if there is none.
+    pub maxmemory: u64,
+    /// What the server does when the memory limit is reached.
+    pub maxmemory_policy: String,
+    /// The ratio of `used_memory_rss` to `used_memory`.
+    pub mem_fragmentation_ratio: f64,
+}
+
+/// The replication section of `INFO`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReplicationInfo {
+    /// Either `master` or `slave`.
+    pub role: String,
+    /// The number of connected replicas.
+    pub connected_slaves: u64,
+    /// The host of the master if this is a replica.
+    pub master_host: Option<String>,
+    /// The port of the master if this is a replica.
+    pub master_port: Option<u16>,
+    /// Either `up` or `down` if this is a replica.
+    pub master_link_status: Option<String>,
+    /// The replication offset of the server.
+    pub master_repl_offset: i64,
+}
+
+/// The persistence section of `INFO`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PersistenceInfo {
+    /// Whether a dump is being loaded.
+    pub loading: bool,
+    /// The number of changes since the last dump.
+    pub rdb_changes_since_last_save: u64,
+    /// The unix time of the last successful dump.
+    pub rdb_last_save_time: u64,
+    /// Whether a dump is being written in the background.
+    pub rdb_bgsave_in_progress: bool,
+    /// The status of the last dump, `ok` or `err`.
+    pub rdb_last_bgsave_status: String,
+    /// Whether the append only file is enabled.
+    pub aof_enabled: bool,
+}
+
+/// The stats section of `INFO`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StatsInfo {
+    /// The number of connections accepted by the server.
+    pub total_connections_received: u64,
+    /// The number of commands processed by the server.
+    pub total_commands_processed: u64,
+    /// The number of commands processed per second.
+    pub instantaneous_ops_per_sec: u64,
+    /// The number of connections rejected because of `maxclients`.
+    pub rejected_connections: u64,
+    /// The number of keys that expired.
+    pub expired_keys: u64,
+    /// The number of keys evicted because of `maxmemory`.
+    pub evicted_keys: u64,
+    /// The number of successful key lookups.
+    pub keyspace_hits: u64,
+    /// The number of failed key lookups.
+    pub keyspace_misses: u64,
+}
+
+/// The keys of a database as reported by the keyspace section of `INFO`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KeyspaceInfo {
+    /// The number of keys.
+    pub keys: u64,
+    /// The number of keys with an expiration.
+    pub expires: u64,
+    /// The average time to live in milliseconds of keys with an
+    /// expiration.
+    pub avg_ttl: u64,
+}
+
+/// The reply of `INFO` with typed sections.  Fields that are not part
+/// of a section struct can still be looked up with `get`:
+///
+/// ```rust,no_run
+/// use redis::{Commands, ServerInfo};
+/// # let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+/// # let con = client.get_connection().unwrap();
+/// let info: ServerInfo = con.info().unwrap();
+/// let version: Option<String> = info.get("redis_version");
+/// println!("{} bytes used by {:?}", info.memory.used_memory, version);
+/// if let Some(db) = info.keyspace.get(&0) {
+///     println!("{} keys in db 0", db.keys);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ServerInfo {
+    /// The memory section.
+    pub memory: MemoryInfo,
+    /// The replication section.
+    pub replication: ReplicationInfo,
+    /// The persistence section.
+    pub persistence: PersistenceInfo,
+    /// The stats section.
+    pub stats: StatsInfo,
+    /// The keyspace section by database index.
+    pub keyspace: HashMap<i64, KeyspaceInfo>,
+    dict: InfoDict,
+}
+
+impl ServerInfo {
+    /// Parses the text `INFO` replies.
+    pub fn parse(text: &str) -> ServerInfo {
+        let dict = InfoDict::new(text);
+        let mut keyspace = HashMap::new();
+        for line in text.lines() {
+            let mut parts = line.trim().splitn(2, ':');
+            let (key, value) = match (parts.next(), parts.next()) {
+                (Some(key), Some(value)) => (key, value),
+                _ => continue,
+            };
+            if !key.starts_with("db") {
+                continue;
+            }
+            let db = unwrap_or!(key[2..].parse().ok(), continue);
+            let mut info = KeyspaceInfo::default();
+            for field in value.split(',') {
+                let mut parts = field.splitn(2, '=');
+                let (name, value) = match (parts.next(), parts.next()) {
+                    (Some(name), Some(value)) => (name, unwrap_or!(value.parse().ok(), continue)),
+                    _ => continue,
+                };
+                match name {
+                    "keys" => info.keys = value,
+                    "expires" => info.expires = value,
+                    "avg_ttl" => info.avg_ttl = value,
+                    _ => {}
+                }
+            }
+            keyspace.insert(db, info);
+        }
+
+        ServerInfo {
+            memory: MemoryInfo {
+                used_memory: get(&dict, "used_memory"),
+                used_memory_rss: get(&dict, "used_memory_rss"),
+                used_memory_peak: get(&dict, "used_memory_peak"),
+                maxmemory: get(&dict, "maxmemory"),
+                maxmemory_policy: get(&dict, "maxmemory_policy"),
+                mem_fragmentation_ratio: get(&dict, "mem_fragmentation_ratio"),
+            },
+            replication: ReplicationInfo {
+                role: get(&dict, "role"),
+                connected_slaves: get(&dict, "connected_slaves"),
+                master_host: dict.get("master_host"),
+                master_port: dict.get("master_port"),
+                master_link_status: dict.get("master_link_status"),
+                master_repl_offset: get(&dict, "master_repl_offset"),
+            },
+            persistence: PersistenceInfo {
+                loading: get(&dict, "loading"),
+                rdb_changes_since_last_save: get(&dict, "rdb_changes_since_last_save"),
+                rdb_last_save_time: get(&dict, "rdb_last_save_time"),
+                rdb_bgsave_in_progress: get(&dict, "rdb_bgsave_in_progress"),
+                rdb_last_bgsave_status: get(&dict, "rdb_last_bgsave_status"),
+                aof_enabled: get(&dict, "aof_enabled"),
+            },
+            stats: StatsInfo {
+                total_connections_received: get(&dict, "total_connections_received"),
+                total_commands_processed: get(&dict, "total_commands_processed"),
+                instantaneous_ops_per_sec: get(&dict, "instantaneous_ops_per_sec"),
+                rejected_connections: get(&dict, "rejected_connections"),
+                expired_keys: get(&dict, "expired_keys"),
+                evicted_keys: get(&dict, "evicted_keys"),
+                keyspace_hits: get(&dict, "keyspace_hits"),
+                keyspace_misses: get(&dict, "keyspace_misses"),
+            },
+            keyspace: keyspace,
+            dict: dict,
+        }
+    }
+
+    /// Looks up any field by its name and converts it into the given
+    /// type, like `InfoDict::get`.
+    pub fn get<T: FromRedisValue>(&self, key: &str) -> Option<T> {
+        self.dict.get(key)
+    }
+}
+
+fn get<T: FromRedisValue + Default>(dict: &InfoDict, key: &str) -> T {
+    dict.get(key).unwrap_or_default()
+}
+
+impl FromRedisValue for ServerInfo {
+    fn from_redis_value(v: &Value) -> RedisResult<ServerInfo> {
+        let text: String = try!(from_redis_value(v));
+        Ok(ServerInfo::parse(&text))
+    }
+}
+
+
+/// Options for `FAILOVER`, which hands the master role over to a
+/// replica.
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use redis::{Commands, FailoverOptions};
+/// # let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+/// # let con = client.get_connection().unwrap();
+/// let options = FailoverOptions::new()
+///     .to("10.0.0.2", 6379)
+///     .timeout(Duration::from_secs(5));
+/// let _: () = con.failover(options).unwrap();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FailoverOptions {
+    to: Option<(String, u16, bool)>,
+    abort: bool,
+    timeout: Option<Duration>,
+}
+
+impl FailoverOptions {
+    /// Creates options that let the server pick the replica.
+    pub fn new() -> FailoverOptions {
+        FailoverOptions::default()
+    }
+
+    /// Fails over to the given replica.
+    pub fn to(mut self, host: &str, port: u16) -> FailoverOptions {
+        self.to = Some((host.to_string(), port, false));
+        self
+    }
+
+    /// Fails over to the given replica even if it does not catch up
+    /// before the timeout.
+    pub fn force_to(mut self, host: &str, port: u16) -> FailoverOptions {
+        self.to = Some((host.to_string(), port, true));
+        self
+    }
+
+    /// Aborts a failover in progress instead of starting one.
+    pub fn abort(mut self) -> FailoverOptions {
+        self.abort = true;
+        self
+    }
+
+    /// Gives up if the failover does not complete in time.
+    pub fn timeout(mut self, timeout: Duration) -> FailoverOptions {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+impl ToRedisArgs for FailoverOptions {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        let mut rv = vec![];
+        if let Some((ref host, port, force)) = self.to {
+            rv.push(b"TO".to_vec());
+            rv.extend(host.to_redis_args());
+            rv.extend(port.to_redis_args());
+            if force {
+                rv.push(b"FORCE".to_vec());
+            }
+        }
+        if self.abort {
+            rv.push(b"ABORT".to_vec());
+        }
+        if let Some(timeout) = self.timeout {
+            let ms = timeout.as_secs() * 1000 + timeout.subsec_nanos() as u64 / 1_000_000;
+            rv.push(b"TIMEOUT".to_vec());
+            rv.extend(ms.to_redis_args());
+        }
+        rv
+    }
+}
+
+
+/// A sample of `LATENCY HISTORY`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LatencySample {
+    /// The unix time in seconds the sample was taken at.
+    pub timestamp: u64,
+    /// The latency of the event.
+    pub latency: Duration,
+}
+
+impl FromRedisValue for LatencySample {
+    fn from_redis_value(v: &Value) -> RedisResult<LatencySample> {
+        let (timestamp, ms): (u64, u64) = try!(from_redis_value(v));
+        Ok(LatencySample {
+            timestamp: timestamp,
+            latency: Duration::from_millis(ms),
+        })
+    }
+}
+
+/// The latest sample of an event as reported by `LATENCY LATEST`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatencyEvent {
+    /// The name of the event, like `command` or `fork`.
+    pub name: String,
+    /// The unix time in seconds of the latest sample.
+    pub timestamp: u64,
+    /// The latency of the latest sample.
+    pub latest: Duration,
+    /// The highest latency of the event.
+    pub max: Duration,
+}
+
+impl FromRedisValue for LatencyEvent {
+    fn from_redis_value(v: &Value) -> RedisResult<LatencyEvent> {
+        let items: Vec<Value> = try!(from_redis_value(v));
+        if items.len() < 4 {
+            fail!((ErrorKind::TypeError, "Invalid LATENCY LATEST entry", format!("{:?}", v)));
+        }
+        Ok(LatencyEvent {
+            name: try!(from_redis_value(&items[0])),
+            timestamp: try!(from_redis_value(&items[1])),
+            latest: Duration::from_millis(try!(from_redis_value(&items[2]))),
+            max: Duration::from_millis(try!(from_redis_value(&items[3]))),
+        })
+    }
+}
+
+
+/// A command as reported by `COMMAND INFO`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandInfo {
+    /// The name of the command in lower case.
+    pub name: String,
+    /// The number of arguments including the name, negative if it is the
+    /// minimum number.
+    pub arity: i64,
+    /// The flags of the command, like `write` or `readonly`.
+    pub flags: Vec<String>,
+    /// The position of the first key argument, 0 if there are no keys.
+    pub first_key: i64,
+    /// The position of the last key argument, negative if it counts from
+    /// the end.
+    pub last_key: i64,
+    /// The step between key arguments.
+    pub step: i64,
+    /// The ACL categories of the command.  Old servers do not report
+    /// them.
+    pub acl_categories: Vec<String>,
+}
+
+impl FromRedisValue for CommandInfo {
+    fn from_redis_value(v: &Value) -> RedisResult<CommandInfo> {
+        let items: Vec<Value> = try!(from_redis_value(v));
+        if items.len() < 6 {
+            fail!((ErrorKind::TypeError, "Invalid COMMAND INFO entry", format!("{:?}", v)));
+        }
+        Ok(CommandInfo {
+            name: try!(from_redis_value(&items[0])),
+            arity: try!(from_redis_value(&items[1])),
+            flags: try!(from_redis_value(&items[2])),
+            first_key: try!(from_redis_value(&items[3])),
+            last_key: try!(from_redis_value(&items[4])),
+            step: try!(from_redis_value(&items[5])),
+            acl_categories: match items.get(6) {
+                Some(categories) => try!(from_redis_value(categories)),
+                None => vec![],
+            },
+        })
+    }
+}
+
+/// The documentation of a command as reported by `COMMAND DOCS`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandDocs {
+    /// A short description of the command.
+    pub summary: String,
+    /// The server version that added the command.
+    pub since: String,
+    /// The group of the command, like `string` or `server`.
+    pub group: String,
+    /// The time complexity of the command.
+    pub complexity: Option<String>,
+    fields: HashMap<String, Value>,
+}
+
+impl CommandDocs {
+    /// Looks up any field of the documentation, like `arguments`, and
+    /// converts it into the given type.
+    pub fn get<T: FromRedisValue>(&self, field: &str) -> Option<T> {
+        self.fields.get(field).and_then(|v| from_redis_value(v).ok())
+    }
+}
+
+impl FromRedisValue for CommandDocs {
+    fn from_redis_value(v: &Value) -> RedisResult<CommandDocs> {
+        let fields: HashMap<String, Value> = try!(from_redis_value(v));
+        let nil = Value::Nil;
+        Ok(CommandDocs {
+            summary: try!(from_redis_value(fields.get("summary").unwrap_or(&nil))),
+            since: try!(from_redis_value(fields.get("since").unwrap_or(&nil))),
+            group: try!(from_redis_value(fields.get("group").unwrap_or(&nil))),
+            complexity: try!(from_redis_value(fields.get("complexity").unwrap_or(&nil))),
+            fields: fields,
+        })
+    }
+}
@@ -0,0 +1,509 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use client::Client;
+use commands::Commands;
+use connection::Connection;
+use types::{FromRedisValue, ToRedisArgs, RedisResult, ErrorKind, Value, from_redis_value};
+
+
+/// Limits the length of a stream for `XADD` and `XTRIM`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StreamMaxlen {
+    /// Trims the stream to exactly this many entries.
+    Equals(usize),
+    /// Trims the stream to about this many entries.  This is more
+    /// efficient as the server only removes whole nodes.
+    Approx(usize),
+}
+
+impl ToRedisArgs for StreamMaxlen {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        let (op, len) = match *self {
+            StreamMaxlen::Equals(len) => ("=", len),
+            StreamMaxlen::Approx(len) => ("~", len),
+        };
+        vec![b"MAXLEN".to_vec(), op.as_bytes().to_vec(), len.to_string().into_bytes()]
+    }
+}
+
+
+/// Options for `XREAD` and `XREADGROUP`.
+///
+/// ```rust,no_run
+/// use redis::{Commands, StreamReadOptions, StreamReadReply};
+/// # let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+/// # let con = client.get_connection().unwrap();
+/// let opts = StreamReadOptions::default().count(10).block(5000);
+/// let reply: StreamReadReply = con.xread_options(&["events"], &["$"], opts).unwrap();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StreamReadOptions {
+    count: Option<usize>,
+    block: Option<usize>,
+    noack: bool,
+}
+
+impl StreamReadOptions {
+    /// Returns at most this many entries per stream.
+    pub fn count(mut self, count: usize) -> StreamReadOptions {
+        self.count = Some(count);
+        self
+    }
+
+    /// Waits up to this many milliseconds for new entries if there are
+    /// none.  Zero waits forever.
+    pub fn block(mut self, ms: usize) -> StreamReadOptions {
+        self.block = Some(ms);
+        self
+    }
+
+    /// Does not add the entries to the pending list of the group, which
+    /// means they do not need to be acknowledged.  This only applies to
+    /// `XREADGROUP`.
+    pub fn noack(mut self) -> StreamReadOptions {
+        self.noack = true;
+        self
+    }
+}
+
+impl ToRedisArgs for StreamReadOptions {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        let mut rv = vec![];
+        if let Some(count) = self.count {
+            rv.push(b"COUNT".to_vec());
+            rv.push(count.to_string().into_bytes());
+        }
+        if let Some(block) = self.block {
+            rv.push(b"BLOCK".to_vec());
+            rv.push(block.to_string().into_bytes());
+        }
+        if self.noack {
+            rv.push(b"NOACK".to_vec());
+        }
+        rv
+    }
+}
+
+
+/// An entry of a stream as returned by `XRANGE`, `XREAD` and friends.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamEntry {
+    /// The id of the entry, for instance `1526919030474-55`.
+    pub id: String,
+    /// The fields and values of the entry.
+    pub fields: HashMap<String, Value>,
+}
+
+impl StreamEntry {
+    /// Returns the value of a field converted to `T`.  Returns `None` if
+    /// the field is missing or cannot be converted.
+    pub fn get<T: FromRedisValue>(&self, field: &str) -> Option<T> {
+        self.fields.get(field).and_then(|v| from_redis_value(v).ok())
+    }
+}
+
+impl FromRedisValue for StreamEntry {
+    fn from_redis_value(v: &Value) -> RedisResult<StreamEntry> {
+        let (id, fields): (String, Vec<Value>) = try!(from_redis_value(v));
+        let mut map = HashMap::with_capacity(fields.len() / 2);
+        let mut iter = fields.into_iter();
+        while let (Some(field), Some(value)) = (iter.next(), iter.next()) {
+            map.insert(try!(from_redis_value(&field)), value);
+        }
+        Ok(StreamEntry {
+            id: id,
+            fields: map,
+        })
+    }
+}
+
+
+/// The entries read from one stream by `XREAD`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamKey {
+    /// The key of the stream.
+    pub key: String,
+    /// The entries read from the stream.
+    pub entries: Vec<StreamEntry>,
+}
+
+/// The reply of `XREAD`.  A blocking read that timed out has no keys.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StreamReadReply {
+    /// The streams that had new entries.
+    pub keys: Vec<StreamKey>,
+}
+
+impl FromRedisValue for StreamReadReply {
+    fn from_redis_value(v: &Value) -> RedisResult<StreamReadReply> {
+        let mut keys = vec![];
+        match *v {
+            Value::Nil => {}
+            // RESP3 replies with a map of keys to entries
+            Value::Map(ref pairs) => {
+                for &(ref key, ref entries) in pairs.iter() {
+                    keys.push(StreamKey {
+                        key: try!(from_redis_value(key)),
+                        entries: try!(from_redis_value(entries)),
+                    });
+                }
+            }
+            Value::Bulk(ref items) => {
+                for item in items.iter() {
+                    let (key, entries) = try!(from_redis_value(item));
+                    keys.push(StreamKey {
+                        key: key,
+                        entries: entries,
+                    });
+                }
+            }
+            _ => fail!((ErrorKind::TypeError, "Invalid XREAD reply", format!("{:?}", v))),
+        }
+        Ok(StreamReadReply { keys: keys })
+    }
+}
+
+
+/// The summary of the pending entries of a consumer group as returned by
+/// `XPENDING` without a range.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StreamPendingReply {
+    /// The number of pending entries.
+    pub count: usize,
+    /// The lowest id of the pending entries.
+    pub min_id: Option<String>,
+    /// The highest id of the pending entries.
+    pub max_id: Option<String>,
+    /// The consumers with pending entries and how many each has.
+    pub consumers: Vec<(String, usize)>,
+}
+
+impl FromRedisValue for StreamPendingReply {
+    fn from_redis_value(v: &Value) -> RedisResult<StreamPendingReply> {
+        let (count, min_id, max_id, items): (usize, Option<String>, Option<String>, Vec<Value>) =
+            try!(from_redis_value(v));
+        // the consumers are nested pairs which the tuple conversion of
+        // vectors would expect to be flattened.
+        let mut consumers = Vec::with_capacity(items.len());
+        for item in items.iter() {
+            consumers.push(try!(from_redis_value(item)));
+        }
+        Ok(StreamPendingReply {
+            count: count,
+            min_id: min_id,
+            max_id: max_id,
+            consumers: consumers,
+        })
+    }
+}
+
+/// A pending entry as returned by `XPENDING` with a range.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamPendingEntry {
+    /// The id of the entry.
+    pub id: String,
+    /// The consumer the entry was delivered to.
+    pub consumer: String,
+    /// The milliseconds since the entry was last delivered.
+    pub idle_ms: usize,
+    /// How many times the entry was delivered.
+    pub times_delivered: usize,
+}
+
+impl FromRedisValue for StreamPendingEntry {
+    fn from_redis_value(v: &Value) -> RedisResult<StreamPendingEntry> {
+        let (id, consumer, idle_ms, times_delivered) = try!(from_redis_value(v));
+        Ok(StreamPendingEntry {
+            id: id,
+            consumer: consumer,
+            idle_ms: idle_ms,
+            times_delivered: times_delivered,
+        })
+    }
+}
+
+/// The reply of `XAUTOCLAIM`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StreamAutoClaimReply {
+    /// The id to continue the scan with, `0-0` once the whole pending
+    /// list was scanned.
+    pub next_id: String,
+    /// The entries that were claimed.
+    pub claimed: Vec<StreamEntry>,
+    /// The ids of pending entries that no longer exist in the stream and
+    /// were removed from the pending list.  Servers before 7.0 do not
+    /// report them.
+    pub deleted_ids: Vec<String>,
+}
+
+impl FromRedisValue for StreamAutoClaimReply {
+    fn from_redis_value(v: &Value) -> RedisResult<StreamAutoClaimReply> {
+        let items: Vec<Value> = try!(from_redis_value(v));
+        if items.len() < 2 {
+            fail!((ErrorKind::TypeError, "Invalid XAUTOCLAIM reply", format!("{:?}", v)));
+        }
+        Ok(StreamAutoClaimReply {
+            next_id: try!(from_redis_value(&items[0])),
+            claimed: try!(from_redis_value(&items[1])),
+            deleted_ids: match items.get(2) {
+                Some(ids) => try!(from_redis_value(ids)),
+                None => vec![],
+            },
+        })
+    }
+}
+
+
+fn get_field<T: FromRedisValue>(map: &HashMap<String, Value>, key: &str) -> RedisResult<T> {
+    from_redis_value(map.get(key).unwrap_or(&Value::Nil))
+}
+
+/// The state of a stream as reported by `XINFO STREAM`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamInfo {
+    /// The number of entries in the stream.
+    pub length: usize,
+    /// The number of consumer groups of the stream.
+    pub groups: usize,
+    /// The id of the last entry that was added.
+    pub last_generated_id: String,
+    /// The first entry of the stream if there is one.
+    pub first_entry: Option<StreamEntry>,
+    /// The last entry of the stream if there is one.
+    pub last_entry: Option<StreamEntry>,
+}
+
+impl FromRedisValue for StreamInfo {
+    fn from_redis_value(v: &Value) -> RedisResult<StreamInfo> {
+        let map: HashMap<String, Value> = try!(from_redis_value(v));
+        Ok(StreamInfo {
+            length: try!(get_field(&map, "length")),
+            groups: try!(get_field(&map, "groups")),
+            last_generated_id: try!(get_field(&map, "last-generated-id")),
+            first_entry: try!(get_field(&map, "first-entry")),
+            last_entry: try!(get_field(&map, "last-entry")),
+        })
+    }
+}
+
+/// A consumer group as reported by `XINFO GROUPS`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamGroupInfo {
+    /// The name of the group.
+    pub name: String,
+    /// The number of consumers in the group.
+    pub consumers: usize,
+    /// The number of entries delivered but not yet acknowledged.
+    pub pending: usize,
+    /// The id of the last entry delivered to the group.
+    pub last_delivered_id: String,
+    /// The number of entries the group has read.  Only reported by
+    /// servers since 7.0.
+    pub entries_read: Option<usize>,
+    /// The number of entries that were not yet delivered to the group.
+    /// Servers before 7.0 or that cannot tell report nothing.
+    pub lag: Option<usize>,
+}
+
+impl FromRedisValue for StreamGroupInfo {
+    fn from_redis_value(v: &Value) -> RedisResult<StreamGroupInfo> {
+        let map: HashMap<String, Value> = try!(from_redis_value(v));
+        Ok(StreamGroupInfo {
+            name: try!(get_field(&map, "name")),
+            consumers: try!(get_field(&map, "consumers")),
+            pending: try!(get_field(&map, "pending")),
+            last_delivered_id: try!(get_field(&map, "last-delivered-id")),
+            entries_read: try!(get_field(&map, "entries-read")),
+            lag: try!(get_field(&map, "lag")),
+        })
+    }
+}
+
+/// A consumer of a group as reported by `XINFO CONSUMERS`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamConsumerInfo {
+    /// The name of the consumer.
+    pub name: String,
+    /// The number of entries delivered to the consumer but not yet
+    /// acknowledged.
+    pub pending: usize,
+    /// The milliseconds since the consumer last tried to read.
+    pub idle_ms: usize,
+    /// The milliseconds since the consumer last read successfully.  Only
+    /// reported by servers since 7.2.
+    pub inactive_ms: Option<i64>,
+}
+
+impl FromRedisValue for StreamConsumerInfo {
+    fn from_redis_value(v: &Value) -> RedisResult<StreamConsumerInfo> {
+        let map: HashMap<String, Value> = try!(from_redis_value(v));
+        Ok(StreamConsumerInfo {
+            name: try!(get_field(&map, "name")),
+            pending: try!(get_field(&map, "pending")),
+            idle_ms: try!(get_field(&map, "idle")),
+            inactive_ms: try!(get_field(&map, "inactive")),
+        })
+    }
+}
+
+
+/// Consumes a stream as a member of a consumer group.
+///
+/// Every poll reads new entries with `XREADGROUP`, hands them to a
+/// handler and acknowledges the ones the handler succeeded on.  Entries
+/// the handler failed on stay pending and are delivered again once they
+/// were idle for the claim time: from time to time the consumer claims
+/// stale pending entries of the whole group with `XAUTOCLAIM`, which
+/// also picks up the work of consumers that went away.
+///
+/// The first polls deliver the entries that are still pending for this
+/// consumer from an earlier run before any new ones.
+///
+/// ```rust,no_run
+/// let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+/// let mut consumer = redis::StreamConsumer::new(&client, "jobs", "workers", "worker-1")
+///     .unwrap();
+/// consumer.run(|entry| {
+///     let task: String = entry.get("task").unwrap_or_default();
+///     println!("running {}", task);
+///     Ok::<(), String>(())
+/// }).unwrap();
+/// ```
+pub struct StreamConsumer {
+    con: Connection,
+    key: String,
+    group: String,
+    consumer: String,
+    count: usize,
+    block_ms: usize,
+    claim_idle: Duration,
+    claim_interval: Duration,
+    next_claim: Instant,
+    claim_cursor: String,
+    pending_cursor: Option<String>,
+}
+
+impl StreamConsumer {
+    /// Connects to the server and creates the group, and the stream if
+    /// needed, unless the group already exists.  A new group starts
+    /// with the entries added from now on.
+    pub fn new(client: &Client,
+               key: &str,
+               group: &str,
+               consumer: &str)
+               -> RedisResult<StreamConsumer> {
+        let con = try!(client.get_connection());
+        match con.xgroup_create_mkstream::<_, _, _, ()>(key, group, "$") {
+            Ok(()) => {}
+            Err(ref err) if err.extension_error_code() == Some("BUSYGROUP") => {}
+            Err(err) => return Err(err),
+        }
+        Ok(StreamConsumer {
+            con: con,
+            key: key.to_string(),
+            group: group.to_string(),
+            consumer: consumer.to_string(),
+            count: 10,
+            block_ms: 1000,
+            claim_idle: Duration::from_secs(60),
+            claim_interval: Duration::from_secs(10),
+            next_claim: Instant::now(),
+            claim_cursor: "0-0".to_string(),
+            pending_cursor: Some("0-0".to_string()),
+        })
+    }
+
+    /// Sets how many entries are read at most per poll.  The default
+    /// is 10.
+    pub fn set_count(&mut self, count: usize) {
+        self.count = count;
+    }
+
+    /// Sets how long a poll waits for new entries.  The default is one
+    /// second.  The read timeout of the connection must be longer.
+    pub fn set_block_timeout(&mut self, timeout: Duration) {
+        self.block_ms = duration_ms(timeout);
+    }
+
+    /// Sets how long an entry has to be pending before it is claimed
+    /// and delivered again.  The default is one minute.
+    pub fn set_claim_idle_time(&mut self, idle: Duration) {
+        self.claim_idle = idle;
+    }
+
+    /// Sets how often stale pending entries are claimed.  The default is
+    /// every ten seconds.
+    pub fn set_claim_interval(&mut self, interval: Duration) {
+        self.claim_interval = interval;
+    }
+
+    /// Reads one batch of entries and passes them to the handler.  Stale
+    /// pending entries are claimed first if the claim interval passed.
+    /// Entries the handler returns `Ok` for are acknowledged.  Returns
+    /// the number of entries that were handed to the handler.
+    pub fn poll<F, E>(&mut self, handler: &mut F) -> RedisResult<usize>
+        where F: FnMut(&StreamEntry) -> Result<(), E>
+    {
+        let mut entries = vec![];
+        // own pending entries are delivered first, claiming before that
+        // would deliver them twice.
+        if self.pending_cursor.is_none() && Instant::now() >= self.next_claim {
+            entries = try!(self.claim());
+        }
+        if entries.is_empty() {
+            entries = try!(self.read());
+        }
+        let mut done = vec![];
+        for entry in entries.iter() {
+            if handler(entry).is_ok() {
+                done.push(&entry.id[..]);
+            }
+        }
+        if !done.is_empty() {
+            let _: usize = try!(self.con.xack(&*self.key, &*self.group, &done));
+        }
+        Ok(entries.len())
+    }
+
+    /// Polls and handles entries until an error occurs.
+    pub fn run<F, E>(&mut self, mut handler: F) -> RedisResult<()>
+        where F: FnMut(&StreamEntry) -> Result<(), E>
+    {
+        loop {
+            try!(self.poll(&mut handler));
+        }
+    }
+
+    fn read(&mut self) -> RedisResult<Vec<StreamEntry>> {
+        let id = self.pending_cursor.clone().unwrap_or_else(|| ">".to_string());
+        let mut opts = StreamReadOptions::default().count(self.count);
+        if self.pending_cursor.is_none() {
+            opts = opts.block(self.block_ms);
+        }
+        let reply: StreamReadReply = try!(self.con.xreadgroup_options(
+            &*self.group, &*self.consumer, &[&*self.key], &[id], opts));
+        let entries = reply.keys.into_iter().next().map_or(vec![], |key| key.entries);
+        if self.pending_cursor.is_some() {
+            // switch over to new entries once the own pending ones are
+            // all delivered.
+            self.pending_cursor = entries.last().map(|entry| entry.id.clone());
+        }
+        Ok(entries)
+    }
+
+    fn claim(&mut self) -> RedisResult<Vec<StreamEntry>> {
+        let reply: StreamAutoClaimReply = try!(self.con.xautoclaim_count(
+            &*self.key, &*self.group, &*self.consumer, duration_ms(self.claim_idle),
+            &*self.claim_cursor, self.count));
+        if reply.next_id == "0-0" {
+            self.next_claim = Instant::now() + self.claim_interval;
+        }
+        self.claim_cursor = reply.next_id;
+        Ok(reply.claimed)
+    }
+}
+
+fn duration_ms(dur: Duration) -> usize {
+    (dur.as_secs() * 1000 + dur.subsec_nanos() as u64 / 1_000_000) as usize
+}
@@ -0,0 +1,253 @@
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+
+use connection::TlsCertificates;
+use types::{RedisResult, ErrorKind};
+
+#[cfg(all(feature="tls", not(feature="tls-rustls")))]
+use native_tls;
+
+#[cfg(feature="tls-rustls")]
+use std::sync::Arc;
+#[cfg(feature="tls-rustls")]
+use std::time::SystemTime;
+#[cfg(feature="tls-rustls")]
+use std::convert::TryFrom;
+#[cfg(feature="tls-rustls")]
+use rustls;
+#[cfg(feature="tls-rustls")]
+use rustls_pemfile;
+#[cfg(feature="tls-rustls")]
+use webpki_roots;
+
+
+/// An encrypted stream as produced by one of the TLS backends.  Apart
+/// from reading and writing the connection needs access to the socket
+/// underneath so that timeouts can be configured.
+pub trait TlsStream: Read + Write + Send {
+    /// Returns the TCP socket the TLS session runs over.
+    fn get_ref(&self) -> &TcpStream;
+
+    /// Shuts down the TLS session.
+    fn shutdown(&mut self) -> io::Result<()>;
+}
+
+/// Wraps an already connected TCP socket in a TLS session.  This is what
+/// the different TLS backends implement so that they can share the rest
+/// of the connection setup.
+pub trait TlsConnector {
+    /// Performs the TLS handshake with `host` over the given socket.
+    fn connect(&self, host: &str, stream: TcpStream) -> RedisResult<Box<TlsStream>>;
+}
+
+/// Returns the connector of the TLS backend that was compiled in.  If
+/// both backends are enabled rustls is preferred.
+#[cfg(feature="tls-rustls")]
+pub fn default_connector(insecure: bool,
+                         certs: Option<&TlsCertificates>)
+                         -> RedisResult<Box<TlsConnector>> {
+    Ok(Box::new(try!(RustlsConnector::new(insecure, certs))))
+}
+
+#[cfg(not(feature="tls-rustls"))]
+pub fn default_connector(insecure: bool,
+                         certs: Option<&TlsCertificates>)
+                         -> RedisResult<Box<TlsConnector>> {
+    Ok(Box::new(try!(NativeTlsConnector::new(insecure, certs))))
+}
+
+/// Returns the client certificate and key if both are present.
+fn client_identity(certs: Option<&TlsCertificates>) -> RedisResult<Option<(&[u8], &[u8])>> {
+    match certs {
+        Some(certs) => {
+            match (certs.client_cert.as_ref(), certs.client_key.as_ref()) {
+                (Some(cert), Some(key)) => Ok(Some((cert, key))),
+                (None, None) => Ok(None),
+                _ => {
+                    fail!((ErrorKind::InvalidClientConfig,
+                           "A client certificate requires a key and vice versa"))
+                }
+            }
+        }
+        None => Ok(None),
+    }
+}
+
+
+#[cfg(all(feature="tls", not(feature="tls-rustls")))]
+struct NativeTlsConnector {
+    connector: native_tls::TlsConnector,
+}
+
+#[cfg(all(feature="tls", not(feature="tls-rustls")))]
+impl NativeTlsConnector {
+    fn new(insecure: bool, certs: Option<&TlsCertificates>) -> RedisResult<NativeTlsConnector> {
+        let mut builder = native_tls::TlsConnector::builder();
+        if insecure {
+            builder.danger_accept_invalid_certs(true)
+                .danger_accept_invalid_hostnames(true);
+        }
+        if let Some((cert, key)) = try!(client_identity(certs)) {
+            if String::from_utf8_lossy(key).contains("BEGIN RSA PRIVATE KEY") {
+                fail!((ErrorKind::InvalidClientConfig,
+                       "The client key is not in PKCS#8 format",
+                       "convert it with `openssl pkcs8 -topk8 -nocrypt`".to_string()));
+            }
+            builder.identity(try!(native_tls::Identity::from_pkcs8(cert, key)));
+        }
+        if let Some(root) = certs.and_then(|certs| certs.root_cert.as_ref()) {
+            builder.add_root_certificate(try!(native_tls::Certificate::from_pem(root)))
+                .disable_built_in_roots(true);
+        }
+        Ok(NativeTlsConnector { connector: try!(builder.build()) })
+    }
+}
+
+#[cfg(all(feature="tls", not(feature="tls-rustls")))]
+impl TlsStream for native_tls::TlsStream<TcpStream> {
+    fn get_ref(&self) -> &TcpStream {
+        native_tls::TlsStream::get_ref(self)
+    }
+
+    fn shutdown(&mut self) -> io::Result<()> {
+        native_tls::TlsStream::shutdown(self)
+    }
+}
+
+#[cfg(all(feature="tls", not(feature="tls-rustls")))]
+impl TlsConnector for NativeTlsConnector {
+    fn connect(&self, host: &str, stream: TcpStream) -> RedisResult<Box<TlsStream>> {
+        match self.connector.connect(host, stream) {
+            Ok(tls) => Ok(Box::new(tls)),
+            Err(err) => fail!((ErrorKind::IoError, "TLS handshake failed", err.to_string())),
+        }
+    }
+}
+
+
+#[cfg(feature="tls-rustls")]
+struct RustlsConnector {
+    config: Arc<rustls::ClientConfig>,
+}
+
+/// Accepts any certificate the server presents.  Only used for
+/// connections that were explicitly marked as insecure.
+#[cfg(feature="tls-rustls")]
+struct NoCertificateVerification;
+
+#[cfg(feature="tls-rustls")]
+impl rustls::client::ServerCertVerifier for NoCertificateVerification {
+    fn verify_server_cert(&self,
+                          _end_entity: &rustls::Certificate,
+                          _intermediates: &[rustls::Certificate],
+                          _server_name: &rustls::ServerName,
+                          _scts: &mut Iterator<Item = &[u8]>,
+                          _ocsp_response: &[u8],
+                          _now: SystemTime)
+                          -> Result<rustls::client::ServerCertVerified, rustls::Error> {
+        Ok(rustls::client::ServerCertVerified::assertion())
+    }
+}
+
+#[cfg(feature="tls-rustls")]
+impl RustlsConnector {
+    fn new(insecure: bool, certs: Option<&TlsCertificates>) -> RedisResult<RustlsConnector> {
+        let mut roots = rustls::RootCertStore::empty();
+        match certs.and_then(|certs| certs.root_cert.as_ref()) {
+            Some(root) => {
+                for cert in try!(read_pem_certs(root)) {
+                    if roots.add(&cert).is_err() {
+                        fail!((ErrorKind::InvalidClientConfig, "Invalid root certificate"));
+                    }
+                }
+            }
+            None => {
+                roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|ta| {
+                    rustls::OwnedTrustAnchor::from_subject_spki_name_constraints(ta.subject,
+                                                                                 ta.spki,
+                                                                                 ta.name_constraints)
+                }));
+            }
+        }
+        let builder = rustls::ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots);
+        let mut config = match try!(client_identity(certs)) {
+            Some((cert, key)) => {
+                let chain = try!(read_pem_certs(cert));
+                let key = try!(read_pem_key(key));
+                match builder.with_client_auth_cert(chain, key) {
+                    Ok(config) => config,
+                    Err(err) => {
+                        fail!((ErrorKind::InvalidClientConfig,
+                               "Invalid client certificate",
+                               err.to_string()))
+                    }
+                }
+            }
+            None => builder.with_no_client_auth(),
+        };
+        if insecure {
+            config.dangerous().set_certificate_verifier(Arc::new(NoCertificateVerification));
+        }
+        Ok(RustlsConnector { config: Arc::new(config) })
+    }
+}
+
+#[cfg(feature="tls-rustls")]
+fn read_pem_certs(mut pem: &[u8]) -> RedisResult<Vec<rustls::Certificate>> {
+    let certs = try!(rustls_pemfile::certs(&mut pem));
+    if certs.is_empty() {
+        fail!((ErrorKind::InvalidClientConfig, "No certificate found in PEM data"));
+    }
+    Ok(certs.into_iter().map(rustls::Certificate).collect())
+}
+
+#[cfg(feature="tls-rustls")]
+fn read_pem_key(mut pem: &[u8]) -> RedisResult<rustls::PrivateKey> {
+    for item in try!(rustls_pemfile::read_all(&mut pem)) {
+        match item {
+            rustls_pemfile::Item::RSAKey(key) |
+            rustls_pemfile::Item::PKCS8Key(key) |
+            rustls_pemfile::Item::ECKey(key) => return Ok(rustls::PrivateKey(key)),
+            _ => {}
+        }
+    }
+    fail!((ErrorKind::InvalidClientConfig, "No private key found in PEM data"));
+}
+
+#[cfg(feature="tls-rustls")]
+impl TlsStream for rustls::StreamOwned<rustls::ClientConnection, TcpStream> {
+    fn get_ref(&self) -> &TcpStream {
+        &self.sock
+    }
+
+    fn shutdown(&mut self) -> io::Result<()> {
+        self.conn.send_close_notify();
+        self.flush()
+    }
+}
+
+#[cfg(feature="tls-rustls")]
+impl TlsConnector for RustlsConnector {
+    fn connect(&self, host: &str, stream: TcpStream) -> RedisResult<Box<TlsStream>> {
+        let server_name = match rustls::ServerName::try_from(host) {
+            Ok(name) => name,
+            Err(_) => fail!((ErrorKind::InvalidClientConfig, "Invalid TLS server name")),
+        };
+        let conn = match rustls::ClientConnection::new(self.config.clone(), server_name) {
+            Ok(conn) => conn,
+            Err(err) => fail!((ErrorKind::IoError, "TLS handshake failed", err.to_string())),
+        };
+        let mut tls = rustls::StreamOwned::new(conn, stream);
+        // drive the handshake to completion so that errors surface here
+        // rather than on the first command.
+        while tls.conn.is_handshaking() {
+            match tls.conn.complete_io(&mut tls.sock) {
+                Ok(_) => {}
+                Err(err) => fail!((ErrorKind::IoError, "TLS handshake failed", err.to_string())),
+            }
+        }
+        Ok(Box::new(tls))
+    }
+}
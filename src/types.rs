@@ -6,9 +6,18 @@ use std::str::{from_utf8, Utf8Error};
 use std::collections::{HashMap, HashSet};
 use std::collections::{BTreeSet,BTreeMap};
 use std::convert::From;
+use std::borrow::Cow;
 
 #[cfg(feature="with-rustc-json")]
 use serialize::json;
+#[cfg(feature="tls")]
+use native_tls;
+#[cfg(feature="uuid")]
+use uuid::Uuid;
+#[cfg(feature="bytes")]
+use bytes::Bytes;
+#[cfg(feature="json")]
+use serde_json;
 
 
 /// Helper enum that is used in some situations to describe
@@ -39,6 +48,9 @@ pub enum ErrorKind {
     /// An error that was caused because the parameter to the
     /// client were wrong.
     InvalidClientConfig,
+    /// The keys of a command or transaction belong to different cluster
+    /// slots.
+    CrossSlot,
     /// This kind is returned if the redis error is one that is
     /// not native to the system.  This is usually the case if
     /// the cause is another error.
@@ -68,6 +80,28 @@ pub enum Value {
     Status(String),
     /// A status response which represents the string "OK".
     Okay,
+    /// A RESP3 double.  It is kept in its textual form so values stay
+    /// comparable, the numeric conversions parse it.
+    Double(String),
+    /// A RESP3 boolean.
+    Boolean(bool),
+    /// A RESP3 big number.  As there is no native type for it the number
+    /// is kept in its textual form.
+    BigNumber(String),
+    /// A RESP3 verbatim string.  The format is a three letter hint how
+    /// the text is to be interpreted (for instance `txt` or `mkd`).
+    VerbatimString {
+        format: String,
+        text: String,
+    },
+    /// A RESP3 map of key value pairs.
+    Map(Vec<(Value, Value)>),
+    /// A RESP3 set.
+    Set(Vec<Value>),
+    /// A RESP3 push message.  These are sent by the server out of band,
+    /// for instance for pubsub messages.  The first item usually is the
+    /// kind of the message.
+    Push(Vec<Value>),
 }
 
 /// Values are generally not used directly unless you are using the
@@ -134,6 +168,48 @@ impl fmt::Debug for Value {
             }
             Value::Okay => write!(fmt, "ok"),
             Value::Status(ref s) => write!(fmt, "status({:?})", s),
+            Value::Double(ref s) => write!(fmt, "double({})", s),
+            Value::Boolean(val) => write!(fmt, "boolean({:?})", val),
+            Value::BigNumber(ref s) => write!(fmt, "big-number({})", s),
+            Value::VerbatimString { ref format, ref text } => {
+                write!(fmt, "verbatim-string({:?}, {:?})", format, text)
+            }
+            Value::Map(ref pairs) => {
+                try!(write!(fmt, "map("));
+                let mut is_first = true;
+                for &(ref key, ref val) in pairs.iter() {
+                    if !is_first {
+                        try!(write!(fmt, ", "));
+                    }
+                    try!(write!(fmt, "{:?}: {:?}", key, val));
+                    is_first = false;
+                }
+                write!(fmt, ")")
+            }
+            Value::Set(ref values) => {
+                try!(write!(fmt, "set("));
+                let mut is_first = true;
+                for val in values.iter() {
+                    if !is_first {
+                        try!(write!(fmt, ", "));
+                    }
+                    try!(write!(fmt, "{:?}", val));
+                    is_first = false;
+                }
+                write!(fmt, ")")
+            }
+            Value::Push(ref values) => {
+                try!(write!(fmt, "push("));
+                let mut is_first = true;
+                for val in values.iter() {
+                    if !is_first {
+                        try!(write!(fmt, ", "));
+                    }
+                    try!(write!(fmt, "{:?}", val));
+                    is_first = false;
+                }
+                write!(fmt, ")")
+            }
         }
     }
 }
@@ -151,9 +227,12 @@ enum ErrorRepr {
     WithDescription(ErrorKind, &'static str),
     WithDescriptionAndDetail(ErrorKind, &'static str, String),
     ExtensionError(String, String),
+    ServerError(ErrorKind, Option<String>),
     IoError(io::Error),
 }
 
+const SERVER_ERROR: &'static str = "An error was signalled by the server";
+
 impl PartialEq for RedisError {
     fn eq(&self, other: &RedisError) -> bool {
         match (&self.repr, &other.repr) {
@@ -165,6 +244,17 @@ impl PartialEq for RedisError {
             (&ErrorRepr::ExtensionError(ref a, _), &ErrorRepr::ExtensionError(ref b, _)) => {
                 *a == *b
             }
+            (&ErrorRepr::ServerError(kind_a, ref a), &ErrorRepr::ServerError(kind_b, ref b)) => {
+                kind_a == kind_b && a.is_some() == b.is_some()
+            }
+            (&ErrorRepr::ServerError(kind_a, None), &ErrorRepr::WithDescription(kind_b, _)) |
+            (&ErrorRepr::WithDescription(kind_a, _), &ErrorRepr::ServerError(kind_b, None)) => {
+                kind_a == kind_b
+            }
+            (&ErrorRepr::ServerError(kind_a, Some(_)),
+             &ErrorRepr::WithDescriptionAndDetail(kind_b, _, _)) |
+            (&ErrorRepr::WithDescriptionAndDetail(kind_a, _, _),
+             &ErrorRepr::ServerError(kind_b, Some(_))) => kind_a == kind_b,
             _ => false,
         }
     }
@@ -182,6 +272,17 @@ impl From<Utf8Error> for RedisError {
     }
 }
 
+#[cfg(feature="tls")]
+impl From<native_tls::Error> for RedisError {
+    fn from(err: native_tls::Error) -> RedisError {
+        RedisError {
+            repr: ErrorRepr::WithDescriptionAndDetail(ErrorKind::IoError,
+                                                      "TLS error",
+                                                      err.to_string()),
+        }
+    }
+}
+
 impl From<(ErrorKind, &'static str)> for RedisError {
     fn from((kind, desc): (ErrorKind, &'static str)) -> RedisError {
         RedisError { repr: ErrorRepr::WithDescription(kind, desc) }
@@ -200,6 +301,7 @@ impl error::Error for RedisError {
             ErrorRepr::WithDescription(_, desc) => desc,
             ErrorRepr::WithDescriptionAndDetail(_, desc, _) => desc,
             ErrorRepr::ExtensionError(_, _) => "extension error",
+            ErrorRepr::ServerError(_, _) => SERVER_ERROR,
             ErrorRepr::IoError(ref err) => err.description(),
         }
     }
@@ -226,6 +328,12 @@ impl fmt::Display for RedisError {
                 try!(f.write_str(": "));
                 detail.fmt(f)
             }
+            ErrorRepr::ServerError(_, None) => SERVER_ERROR.fmt(f),
+            ErrorRepr::ServerError(_, Some(ref detail)) => {
+                try!(SERVER_ERROR.fmt(f));
+                try!(f.write_str(": "));
+                detail.fmt(f)
+            }
             ErrorRepr::IoError(ref err) => err.fmt(f),
         }
     }
@@ -245,6 +353,7 @@ impl RedisError {
             ErrorRepr::WithDescription(kind, _) => kind,
             ErrorRepr::WithDescriptionAndDetail(kind, _, _) => kind,
             ErrorRepr::ExtensionError(_, _) => ErrorKind::ExtensionError,
+            ErrorRepr::ServerError(kind, _) => kind,
             ErrorRepr::IoError(_) => ErrorKind::IoError,
         }
     }
@@ -259,6 +368,7 @@ impl RedisError {
             ErrorKind::BusyLoadingError => "busy loading",
             ErrorKind::NoScriptError => "no script",
             ErrorKind::InvalidClientConfig => "invalid client config",
+            ErrorKind::CrossSlot => "cross slot",
             ErrorKind::IoError => "I/O error",
             ErrorKind::ExtensionError => "extension error",
         }
@@ -310,6 +420,23 @@ impl RedisError {
         }
     }
 
+    /// Returns true if the error means that the connection to the server
+    /// was lost, for instance because the server closed it.
+    pub fn is_connection_dropped(&self) -> bool {
+        match self.repr {
+            ErrorRepr::IoError(ref err) => {
+                match err.kind() {
+                    io::ErrorKind::BrokenPipe |
+                    io::ErrorKind::ConnectionReset |
+                    io::ErrorKind::ConnectionAborted |
+                    io::ErrorKind::UnexpectedEof => true,
+                    _ => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
     /// Returns the extension error code
     pub fn extension_error_code(&self) -> Option<&str> {
         match self.repr {
@@ -317,6 +444,17 @@ impl RedisError {
             _ => None,
         }
     }
+
+    /// Returns the detail of the error if there is one.  For errors sent
+    /// by the server this is the message after the error code.
+    pub fn detail(&self) -> Option<&str> {
+        match self.repr {
+            ErrorRepr::WithDescriptionAndDetail(_, _, ref detail) |
+            ErrorRepr::ServerError(_, Some(ref detail)) |
+            ErrorRepr::ExtensionError(_, ref detail) => Some(detail),
+            _ => None,
+        }
+    }
 }
 
 pub fn make_extension_error(code: &str, detail: Option<&str>) -> RedisError {
@@ -331,13 +469,47 @@ pub fn make_extension_error(code: &str, detail: Option<&str>) -> RedisError {
     }
 }
 
+pub fn make_server_error(kind: ErrorKind, detail: Option<&str>) -> RedisError {
+    RedisError { repr: ErrorRepr::ServerError(kind, detail.map(|x| x.to_string())) }
+}
+
+/// Checks if the error is a reply of the server rather than an invalid
+/// response, after which the connection can still be used.
+pub fn is_server_error(err: &RedisError) -> bool {
+    match err.repr {
+        ErrorRepr::ServerError(_, _) |
+        ErrorRepr::ExtensionError(_, _) => true,
+        _ => false,
+    }
+}
+
+
+/// Adds the command a reply belongs to to a type error, as conversions
+/// only see the reply.
+pub fn add_command_to_error(err: RedisError, command: &str) -> RedisError {
+    let repr = match err.repr {
+        ErrorRepr::WithDescription(ErrorKind::TypeError, desc) => {
+            ErrorRepr::WithDescriptionAndDetail(ErrorKind::TypeError,
+                                                desc,
+                                                format!("in reply to {}", command))
+        }
+        ErrorRepr::WithDescriptionAndDetail(ErrorKind::TypeError, desc, detail) => {
+            ErrorRepr::WithDescriptionAndDetail(ErrorKind::TypeError,
+                                                desc,
+                                                format!("{} in reply to {}", detail, command))
+        }
+        repr => repr,
+    };
+    RedisError { repr: repr }
+}
+
 
 /// Library generic result type.
 pub type RedisResult<T> = Result<T, RedisError>;
 
 
 /// An info dictionary type.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct InfoDict {
     map: HashMap<String, Value>,
 }
@@ -400,6 +572,120 @@ impl InfoDict {
 }
 
 
+/// A binary safe string.  Unlike `String` it can be read from any reply
+/// that carries bytes and leaves it to the caller whether invalid UTF-8
+/// is an error or gets replaced:
+///
+/// ```rust,no_run
+/// # let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+/// # let con = client.get_connection().unwrap();
+/// let value: redis::RedisString = redis::cmd("GET").arg("foo").query(&con).unwrap();
+/// match value.to_str() {
+///     Ok(s) => println!("text: {}", s),
+///     Err(_) => println!("binary: {}", value.to_string_lossy()),
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct RedisString {
+    bytes: Vec<u8>,
+}
+
+impl RedisString {
+    /// Creates a string from bytes.
+    pub fn new<T: Into<Vec<u8>>>(bytes: T) -> RedisString {
+        RedisString { bytes: bytes.into() }
+    }
+
+    /// Returns the raw bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the raw bytes.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Returns the string if it is valid UTF-8.
+    pub fn to_str(&self) -> Result<&str, Utf8Error> {
+        from_utf8(&self.bytes)
+    }
+
+    /// Returns the string with invalid UTF-8 sequences replaced by
+    /// `U+FFFD REPLACEMENT CHARACTER`.
+    pub fn to_string_lossy(&self) -> Cow<str> {
+        String::from_utf8_lossy(&self.bytes)
+    }
+
+    /// Converts into a `String` if it is valid UTF-8.
+    pub fn into_string(self) -> RedisResult<String> {
+        match String::from_utf8(self.bytes) {
+            Ok(s) => Ok(s),
+            Err(err) => Err(From::from(err.utf8_error())),
+        }
+    }
+
+    /// Converts into a `String`, replacing invalid UTF-8 sequences.
+    pub fn into_string_lossy(self) -> String {
+        match String::from_utf8(self.bytes) {
+            Ok(s) => s,
+            Err(err) => String::from_utf8_lossy(err.as_bytes()).into_owned(),
+        }
+    }
+}
+
+impl fmt::Display for RedisString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.to_string_lossy(), f)
+    }
+}
+
+impl From<Vec<u8>> for RedisString {
+    fn from(bytes: Vec<u8>) -> RedisString {
+        RedisString::new(bytes)
+    }
+}
+
+impl From<String> for RedisString {
+    fn from(s: String) -> RedisString {
+        RedisString::new(s)
+    }
+}
+
+impl<'a> From<&'a str> for RedisString {
+    fn from(s: &'a str) -> RedisString {
+        RedisString::new(s)
+    }
+}
+
+/// Marks the items of a reply as arrays of their own.  Collections of
+/// tuples read flat replies like `HGETALL` while some replies put every
+/// item in an array, like the members and scores of `ZMPOP` or of
+/// `ZRANGE ... WITHSCORES` with RESP3:
+///
+/// ```rust,no_run
+/// use redis::{Commands, Nested, ZPopSide};
+/// # let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+/// # let con = client.get_connection().unwrap();
+/// let popped: Option<(String, Vec<Nested<(String, f64)>>)> =
+///     con.zmpop(&["highscores"], ZPopSide::Max, 10).unwrap();
+/// if let Some((_, scores)) = popped {
+///     for Nested((member, score)) in scores {
+///         println!("{}: {}", member, score);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Nested<T>(pub T);
+
+impl<T> Nested<T> {
+    /// Returns the wrapped item.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+
 /// Used to convert a value into one or multiple redis argument
 /// strings.  Most values will produce exactly one item but in
 /// some cases it might make sense to produce more than one.
@@ -462,7 +748,8 @@ macro_rules! invalid_type_error {
     ($v:expr, $det:expr) => ({
         fail!((ErrorKind::TypeError,
                "Response was of incompatible type",
-               format!("{:?} (response was {:?})", $det, $v)));
+               format!("{:?} (response was {:?}, expected {})",
+                       $det, $v, ::std::any::type_name::<Self>())));
     })
 }
 
@@ -504,6 +791,8 @@ string_based_to_redis_impl!(i32, NumericBehavior::NumberIsInteger);
 string_based_to_redis_impl!(u32, NumericBehavior::NumberIsInteger);
 string_based_to_redis_impl!(i64, NumericBehavior::NumberIsInteger);
 string_based_to_redis_impl!(u64, NumericBehavior::NumberIsInteger);
+string_based_to_redis_impl!(i128, NumericBehavior::NumberIsInteger);
+string_based_to_redis_impl!(u128, NumericBehavior::NumberIsInteger);
 string_based_to_redis_impl!(f32, NumericBehavior::NumberIsFloat);
 string_based_to_redis_impl!(f64, NumericBehavior::NumberIsFloat);
 string_based_to_redis_impl!(isize, NumericBehavior::NumberIsInteger);
@@ -633,6 +922,23 @@ impl ToRedisArgs for json::Json {
     }
 }
 
+#[cfg(feature="json")]
+impl ToRedisArgs for serde_json::Value {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        // XXX: encoding a JSON value only fails if the writer fails
+        vec![serde_json::to_vec(self).expect("JSON value cannot be encoded")]
+    }
+}
+
+/// UUIDs are written in their hyphenated form.  Pass `Uuid::as_bytes` to
+/// store the 16 raw bytes instead.
+#[cfg(feature="uuid")]
+impl ToRedisArgs for Uuid {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        vec![self.hyphenated().to_string().into_bytes()]
+    }
+}
+
 macro_rules! to_redis_args_for_tuple {
     () => ();
     ($($name:ident,)+) => (
@@ -709,6 +1015,13 @@ pub trait FromRedisValue: Sized {
     /// appropriate error is generated.
     fn from_redis_value(v: &Value) -> RedisResult<Self>;
 
+    /// Like `from_redis_value` but takes over the value.  Types holding
+    /// binary data use this to keep the buffer the reply was read into
+    /// instead of copying it.  Queries convert their replies this way.
+    fn from_owned_redis_value(v: Value) -> RedisResult<Self> {
+        FromRedisValue::from_redis_value(&v)
+    }
+
     /// Similar to `from_redis_value` but constructs a vector of objects
     /// from another vector of values.  This primarily exists internally
     /// to customize the behavior for vectors of tuples.
@@ -729,6 +1042,13 @@ pub trait FromRedisValue: Sized {
     fn from_byte_vec(_vec: &[u8]) -> Option<Vec<Self>> {
         None
     }
+
+    /// Like `from_byte_vec` but takes over the bytes, they are handed back
+    /// if the type does not support it.
+    #[doc(hidden)]
+    fn from_owned_byte_vec(vec: Vec<u8>) -> Result<Vec<Self>, Vec<u8>> {
+        Err(vec)
+    }
 }
 
 macro_rules! from_redis_value_for_num_internal {
@@ -737,6 +1057,26 @@ macro_rules! from_redis_value_for_num_internal {
             let v = $v;
             match *v {
                 Value::Int(val) => Ok(val as $t),
+                Value::Double(ref s) => {
+                    match s.parse::<$t>() {
+                        Ok(rv) => Ok(rv),
+                        // integral doubles such as `1e3` still convert to
+                        // integers, anything with a fraction does not.
+                        Err(_) => match s.parse::<f64>() {
+                            Ok(val) if val.fract() == 0.0 && (val as $t) as f64 == val => {
+                                Ok(val as $t)
+                            }
+                            _ => invalid_type_error!(v, "Could not convert from double."),
+                        },
+                    }
+                },
+                Value::BigNumber(ref s) => {
+                    match s.parse::<$t>() {
+                        Ok(rv) => Ok(rv),
+                        Err(_) => invalid_type_error!(v,
+                            "Could not convert from big number.")
+                    }
+                },
                 Value::Status(ref s) => {
                     match s.parse::<$t>() {
                         Ok(rv) => Ok(rv),
@@ -776,6 +1116,10 @@ impl FromRedisValue for u8 {
     fn from_byte_vec(vec: &[u8]) -> Option<Vec<u8>> {
         Some(vec.to_vec())
     }
+
+    fn from_owned_byte_vec(vec: Vec<u8>) -> Result<Vec<u8>, Vec<u8>> {
+        Ok(vec)
+    }
 }
 
 from_redis_value_for_num!(i8);
@@ -785,27 +1129,32 @@ from_redis_value_for_num!(i32);
 from_redis_value_for_num!(u32);
 from_redis_value_for_num!(i64);
 from_redis_value_for_num!(u64);
+from_redis_value_for_num!(i128);
+from_redis_value_for_num!(u128);
 from_redis_value_for_num!(f32);
 from_redis_value_for_num!(f64);
 from_redis_value_for_num!(isize);
 from_redis_value_for_num!(usize);
 
+/// Booleans are read from integers, where anything but `0` is true, from
+/// the strings `"1"`, `"0"` and `"OK"` and from RESP3 booleans.  `OK` is
+/// true and nil is false, so `SET` with `NX` or `XX` tells whether the key
+/// was set.  Use `Option<bool>` to tell nil apart from false.
 impl FromRedisValue for bool {
     fn from_redis_value(v: &Value) -> RedisResult<bool> {
-        match *v {
-            Value::Nil => Ok(false),
-            Value::Int(val) => Ok(val != 0),
-            Value::Status(ref s) => {
-                if &s[..] == "1" {
-                    Ok(true)
-                } else if &s[..] == "0" {
-                    Ok(false)
-                } else {
-                    invalid_type_error!(v, "Response status not valid boolean");
-                }
-            }
-            Value::Okay => Ok(true),
+        let s = match *v {
+            Value::Nil => return Ok(false),
+            Value::Int(val) => return Ok(val != 0),
+            Value::Okay => return Ok(true),
+            Value::Boolean(val) => return Ok(val),
+            Value::Status(ref s) => s.as_bytes(),
+            Value::Data(ref bytes) => &bytes[..],
             _ => invalid_type_error!(v, "Response type not bool compatible."),
+        };
+        match s {
+            b"1" | b"OK" => Ok(true),
+            b"0" => Ok(false),
+            _ => invalid_type_error!(v, "Response string not valid boolean"),
         }
     }
 }
@@ -813,12 +1162,27 @@ impl FromRedisValue for bool {
 impl FromRedisValue for String {
     fn from_redis_value(v: &Value) -> RedisResult<String> {
         match *v {
-            Value::Data(ref bytes) => Ok(try!(from_utf8(bytes)).to_string()),
+            Value::Data(ref bytes) => {
+                match from_utf8(bytes) {
+                    Ok(s) => Ok(s.to_string()),
+                    Err(err) => Err(From::from(err)),
+                }
+            }
             Value::Okay => Ok("OK".to_string()),
             Value::Status(ref val) => Ok(val.to_string()),
+            Value::VerbatimString { ref text, .. } => Ok(text.to_string()),
+            Value::BigNumber(ref val) => Ok(val.to_string()),
+            Value::Double(ref val) => Ok(val.to_string()),
             _ => invalid_type_error!(v, "Response type not string compatible."),
         }
     }
+
+    fn from_owned_redis_value(v: Value) -> RedisResult<String> {
+        match v {
+            Value::Data(bytes) => RedisString::new(bytes).into_string(),
+            v => from_redis_value(&v),
+        }
+    }
 }
 
 impl<T: FromRedisValue> FromRedisValue for Vec<T> {
@@ -832,11 +1196,49 @@ impl<T: FromRedisValue> FromRedisValue for Vec<T> {
                     None => invalid_type_error!(v, "Response type not vector compatible."),
                 }
             }
+            // RESP3 replies some texts like CLIENT LIST as verbatim strings
+            Value::VerbatimString { ref text, .. } => {
+                match FromRedisValue::from_byte_vec(text.as_bytes()) {
+                    Some(x) => Ok(x),
+                    None => invalid_type_error!(v, "Response type not vector compatible."),
+                }
+            }
             Value::Bulk(ref items) => FromRedisValue::from_redis_values(items),
+            Value::Set(ref items) => FromRedisValue::from_redis_values(items),
+            Value::Push(ref items) => FromRedisValue::from_redis_values(items),
+            Value::Map(ref pairs) => {
+                let mut items = Vec::with_capacity(pairs.len() * 2);
+                for &(ref key, ref val) in pairs.iter() {
+                    items.push(key.clone());
+                    items.push(val.clone());
+                }
+                FromRedisValue::from_redis_values(&items)
+            }
             Value::Nil => Ok(vec![]),
             _ => invalid_type_error!(v, "Response type not vector compatible."),
         }
     }
+
+    fn from_owned_redis_value(v: Value) -> RedisResult<Vec<T>> {
+        match v {
+            Value::Data(bytes) => {
+                match FromRedisValue::from_owned_byte_vec(bytes) {
+                    Ok(x) => Ok(x),
+                    Err(bytes) => from_redis_value(&Value::Data(bytes)),
+                }
+            }
+            v => from_redis_value(&v),
+        }
+    }
+}
+
+/// Returns the key and value pairs of a flat reply like `HGETALL`.  An odd
+/// item at the end is ignored.
+fn pairs(items: &[Value]) -> Vec<(&Value, &Value)> {
+    items.chunks(2)
+        .filter(|chunk| chunk.len() == 2)
+        .map(|chunk| (&chunk[0], &chunk[1]))
+        .collect()
 }
 
 impl<K: FromRedisValue + Eq + Hash, V: FromRedisValue> FromRedisValue for HashMap<K, V> {
@@ -844,14 +1246,19 @@ impl<K: FromRedisValue + Eq + Hash, V: FromRedisValue> FromRedisValue for HashMa
         match *v {
             Value::Bulk(ref items) => {
                 let mut rv = HashMap::new();
-                let mut iter = items.iter();
-                loop {
-                    let k = unwrap_or!(iter.next(), break);
-                    let v = unwrap_or!(iter.next(), break);
+                for (k, v) in pairs(items) {
+                    rv.insert(try!(from_redis_value(k)), try!(from_redis_value(v)));
+                }
+                Ok(rv)
+            }
+            Value::Map(ref pairs) => {
+                let mut rv = HashMap::with_capacity(pairs.len());
+                for &(ref k, ref v) in pairs.iter() {
                     rv.insert(try!(from_redis_value(k)), try!(from_redis_value(v)));
                 }
                 Ok(rv)
             }
+            Value::Nil => Ok(HashMap::new()),
             _ => invalid_type_error!(v, "Response type not hashmap compatible"),
         }
     }
@@ -864,14 +1271,19 @@ impl<K: FromRedisValue + Eq + Hash, V: FromRedisValue> FromRedisValue for BTreeM
         match *v {
             Value::Bulk(ref items) => {
                 let mut rv = BTreeMap::new();
-                let mut iter = items.iter();
-                loop {
-                    let k = unwrap_or!(iter.next(), break);
-                    let v = unwrap_or!(iter.next(), break);
+                for (k, v) in pairs(items) {
+                    rv.insert(try!(from_redis_value(k)), try!(from_redis_value(v)));
+                }
+                Ok(rv)
+            }
+            Value::Map(ref pairs) => {
+                let mut rv = BTreeMap::new();
+                for &(ref k, ref v) in pairs.iter() {
                     rv.insert(try!(from_redis_value(k)), try!(from_redis_value(v)));
                 }
                 Ok(rv)
             }
+            Value::Nil => Ok(BTreeMap::new()),
             _ => invalid_type_error!(v, "Response type not btreemap compatible"),
         }
     }
@@ -880,7 +1292,7 @@ impl<K: FromRedisValue + Eq + Hash, V: FromRedisValue> FromRedisValue for BTreeM
 impl<T: FromRedisValue + Eq + Hash> FromRedisValue for HashSet<T> {
     fn from_redis_value(v: &Value) -> RedisResult<HashSet<T>> {
         match *v {
-            Value::Bulk(ref items) => {
+            Value::Bulk(ref items) | Value::Set(ref items) => {
                 let mut rv = HashSet::new();
                 for item in items.iter() {
                     rv.insert(try!(from_redis_value(item)));
@@ -897,7 +1309,7 @@ impl<T: FromRedisValue + Eq + Hash> FromRedisValue for BTreeSet<T>
 {
     fn from_redis_value(v: &Value) -> RedisResult<BTreeSet<T>> {
         match *v {
-            Value::Bulk(ref items) => {
+            Value::Bulk(ref items) | Value::Set(ref items) => {
                 let mut rv = BTreeSet::new();
                 for item in items.iter() {
                     rv.insert(try!(from_redis_value(item)));
@@ -909,10 +1321,59 @@ impl<T: FromRedisValue + Eq + Hash> FromRedisValue for BTreeSet<T>
     }
 }
 
+impl ToRedisArgs for RedisString {
+    fn to_redis_args(&self) -> Vec<Vec<u8>> {
+        vec![self.bytes.clone()]
+    }
+}
+
+impl FromRedisValue for RedisString {
+    fn from_redis_value(v: &Value) -> RedisResult<RedisString> {
+        match *v {
+            Value::Data(ref bytes) => Ok(RedisString::new(&bytes[..])),
+            _ => {
+                let s: String = try!(from_redis_value(v));
+                Ok(RedisString::new(s))
+            }
+        }
+    }
+
+    fn from_owned_redis_value(v: Value) -> RedisResult<RedisString> {
+        match v {
+            Value::Data(bytes) => Ok(RedisString::new(bytes)),
+            v => from_redis_value(&v),
+        }
+    }
+}
+
+impl<T: FromRedisValue> FromRedisValue for Nested<T> {
+    fn from_redis_value(v: &Value) -> RedisResult<Nested<T>> {
+        Ok(Nested(try!(from_redis_value(v))))
+    }
+
+    fn from_owned_redis_value(v: Value) -> RedisResult<Nested<T>> {
+        Ok(Nested(try!(from_owned_redis_value(v))))
+    }
+
+    fn from_redis_values(items: &[Value]) -> RedisResult<Vec<Nested<T>>> {
+        // unlike other items these fail loudly, the wrapper is only used
+        // for replies that are known to be nested.
+        let mut rv = Vec::with_capacity(items.len());
+        for item in items.iter() {
+            rv.push(try!(from_redis_value(item)));
+        }
+        Ok(rv)
+    }
+}
+
 impl FromRedisValue for Value {
     fn from_redis_value(v: &Value) -> RedisResult<Value> {
         Ok(v.clone())
     }
+
+    fn from_owned_redis_value(v: Value) -> RedisResult<Value> {
+        Ok(v)
+    }
 }
 
 impl FromRedisValue for () {
@@ -1009,6 +1470,59 @@ impl FromRedisValue for json::Json {
     }
 }
 
+/// JSON values are parsed from strings.  Integers, doubles and booleans
+/// of the reply are taken as the JSON value of the same type.
+#[cfg(feature="json")]
+impl FromRedisValue for serde_json::Value {
+    fn from_redis_value(v: &Value) -> RedisResult<serde_json::Value> {
+        let rv = match *v {
+            Value::Data(ref b) => serde_json::from_slice(b),
+            Value::Status(ref s) => serde_json::from_str(s),
+            Value::VerbatimString { ref text, .. } => serde_json::from_str(text),
+            Value::Int(val) => return Ok(serde_json::Value::from(val)),
+            Value::Double(_) => return Ok(serde_json::Value::from(try!(f64::from_redis_value(v)))),
+            Value::Boolean(val) => return Ok(serde_json::Value::Bool(val)),
+            _ => invalid_type_error!(v, "Not JSON compatible"),
+        };
+        match rv {
+            Ok(value) => Ok(value),
+            Err(_) => invalid_type_error!(v, "Not valid JSON"),
+        }
+    }
+}
+
+/// Binary data is read like `Vec<u8>`.  A query hands the buffer the reply
+/// was read into over to the `Bytes` without copying it.
+#[cfg(feature="bytes")]
+impl FromRedisValue for Bytes {
+    fn from_redis_value(v: &Value) -> RedisResult<Bytes> {
+        let vec: Vec<u8> = try!(from_redis_value(v));
+        Ok(Bytes::from(vec))
+    }
+
+    fn from_owned_redis_value(v: Value) -> RedisResult<Bytes> {
+        let vec: Vec<u8> = try!(from_owned_redis_value(v));
+        Ok(Bytes::from(vec))
+    }
+}
+
+/// Reads UUIDs in any of their textual forms or as 16 raw bytes.
+#[cfg(feature="uuid")]
+impl FromRedisValue for Uuid {
+    fn from_redis_value(v: &Value) -> RedisResult<Uuid> {
+        let rv = match *v {
+            Value::Data(ref b) if b.len() == 16 => Uuid::from_slice(b),
+            Value::Data(ref b) => Uuid::try_parse_ascii(b),
+            Value::Status(ref s) => Uuid::try_parse(s),
+            _ => invalid_type_error!(v, "Response type not UUID compatible."),
+        };
+        match rv {
+            Ok(value) => Ok(value),
+            Err(_) => invalid_type_error!(v, "Not a valid UUID"),
+        }
+    }
+}
+
 
 impl<T: FromRedisValue> FromRedisValue for Option<T> {
     fn from_redis_value(v: &Value) -> RedisResult<Option<T>> {
@@ -1020,6 +1534,13 @@ impl<T: FromRedisValue> FromRedisValue for Option<T> {
         }
         Ok(Some(try!(from_redis_value(v))))
     }
+
+    fn from_owned_redis_value(v: Value) -> RedisResult<Option<T>> {
+        match v {
+            Value::Nil => Ok(None),
+            v => Ok(Some(try!(from_owned_redis_value(v)))),
+        }
+    }
 }
 
 /// A shortcut function to invoke `FromRedisValue::from_redis_value`
@@ -1027,3 +1548,8 @@ impl<T: FromRedisValue> FromRedisValue for Option<T> {
 pub fn from_redis_value<T: FromRedisValue>(v: &Value) -> RedisResult<T> {
     FromRedisValue::from_redis_value(v)
 }
+
+/// A shortcut function to invoke `FromRedisValue::from_owned_redis_value`.
+pub fn from_owned_redis_value<T: FromRedisValue>(v: Value) -> RedisResult<T> {
+    FromRedisValue::from_owned_redis_value(v)
+}
@@ -1,4 +1,23 @@
+#[macro_use]
 extern crate redis;
+#[cfg(feature="serde")]
+#[macro_use]
+extern crate serde_derive;
+#[cfg(feature="chrono")]
+extern crate chrono;
+#[cfg(feature="time")]
+extern crate time;
+#[cfg(feature="uuid")]
+extern crate uuid;
+#[cfg(feature="bytes")]
+extern crate bytes;
+#[cfg(feature="json")]
+extern crate serde_json;
+
+/// Returns a bulk string reply with the given text.
+fn data(s: &str) -> redis::Value {
+    redis::Value::Data(s.as_bytes().to_vec())
+}
 
 
 #[test]
@@ -63,6 +82,63 @@ fn test_u32() {
     assert_eq!(bad_i.unwrap_err().kind(), ErrorKind::TypeError);
 }
 
+#[test]
+fn test_128_bit_integers() {
+    use redis::{FromRedisValue, ToRedisArgs, Value};
+
+    let big = "170141183460469231731687303715884105727";
+    let i = FromRedisValue::from_redis_value(&Value::BigNumber(big.into()));
+    assert_eq!(i, Ok(i128::max_value()));
+    assert_eq!(i128::max_value().to_redis_args(), vec![big.as_bytes().to_vec()]);
+
+    let i = FromRedisValue::from_redis_value(&Value::Data(u128::max_value().to_string().into()));
+    assert_eq!(i, Ok(u128::max_value()));
+
+    let i = FromRedisValue::from_redis_value(&Value::Int(-42));
+    assert_eq!(i, Ok(-42i128));
+}
+
+#[test]
+fn test_non_finite_floats() {
+    use redis::{FromRedisValue, ToRedisArgs, Value};
+    use std::f64;
+
+    let f = FromRedisValue::from_redis_value(&Value::Data("inf".into()));
+    assert_eq!(f, Ok(f64::INFINITY));
+    let f = FromRedisValue::from_redis_value(&Value::Data("-inf".into()));
+    assert_eq!(f, Ok(f64::NEG_INFINITY));
+    let f = FromRedisValue::from_redis_value(&Value::Status("+inf".into()));
+    assert_eq!(f, Ok(f64::INFINITY));
+    let f: f64 = FromRedisValue::from_redis_value(&Value::Data("nan".into())).unwrap();
+    assert!(f.is_nan());
+    let f: f64 = FromRedisValue::from_redis_value(&Value::Double("nan".into())).unwrap();
+    assert!(f.is_nan());
+
+    let v = redis::parse_redis_value(b",-inf\r\n").unwrap();
+    assert_eq!(f64::from_redis_value(&v), Ok(f64::NEG_INFINITY));
+
+    assert_eq!(i64::from_redis_value(&Value::Double("42".into())), Ok(42));
+    assert_eq!(i64::from_redis_value(&Value::Double("1e3".into())), Ok(1000));
+    assert_eq!(f32::from_redis_value(&Value::Double("2.5".into())), Ok(2.5));
+    let err = i64::from_redis_value(&Value::Double("2.5".into())).unwrap_err();
+    assert_eq!(err.kind(), redis::ErrorKind::TypeError);
+    assert!(u8::from_redis_value(&Value::Double("1e10".into())).is_err());
+    assert!(i64::from_redis_value(&Value::Double("inf".into())).is_err());
+
+    assert_eq!(f64::NEG_INFINITY.to_redis_args(), vec![b"-inf".to_vec()]);
+    assert_eq!(f64::INFINITY.to_redis_args(), vec![b"inf".to_vec()]);
+}
+
+#[test]
+fn test_type_error_detail() {
+    use redis::{FromRedisValue, Value};
+
+    let err = i32::from_redis_value(&Value::Bulk(vec![Value::Int(1)])).unwrap_err();
+    let msg = err.to_string();
+    assert!(msg.contains("response was bulk(int(1))"), "{}", msg);
+    assert!(msg.contains("expected i32"), "{}", msg);
+}
+
 #[test]
 fn test_vec() {
     use redis::{FromRedisValue, Value};
@@ -98,6 +174,27 @@ fn test_bool() {
 
     let v = FromRedisValue::from_redis_value(&Value::Int(42));
     assert_eq!(v, Ok(true));
+
+    let v = FromRedisValue::from_redis_value(&Value::Data(b"1".to_vec()));
+    assert_eq!(v, Ok(true));
+
+    let v = FromRedisValue::from_redis_value(&Value::Data(b"0".to_vec()));
+    assert_eq!(v, Ok(false));
+
+    let v = FromRedisValue::from_redis_value(&Value::Data(b"OK".to_vec()));
+    assert_eq!(v, Ok(true));
+
+    let v: Result<bool, _> = FromRedisValue::from_redis_value(&Value::Data(b"yes".to_vec()));
+    assert_eq!(v.unwrap_err().kind(), ErrorKind::TypeError);
+
+    let v = FromRedisValue::from_redis_value(&Value::Boolean(false));
+    assert_eq!(v, Ok(false));
+
+    let v: Result<Option<bool>, _> = FromRedisValue::from_redis_value(&Value::Nil);
+    assert_eq!(v, Ok(None));
+
+    let v: Result<Option<bool>, _> = FromRedisValue::from_redis_value(&Value::Int(0));
+    assert_eq!(v, Ok(Some(false)));
 }
 
 #[test]
@@ -123,3 +220,1109 @@ fn test_types_to_redis_args() {
             .collect::<BTreeMap<_,_>>()
             .to_redis_args().len() > 0);
 }
+
+#[test]
+fn test_error_in_bulk_is_read_to_the_end() {
+    use redis::{ErrorKind, Parser, Value};
+
+    let mut bytes: &[u8] = b"*3\r\n+OK\r\n-NOSCRIPT No matching script\r\n:1\r\n+PONG\r\n";
+    let mut parser = Parser::new(&mut bytes);
+    assert_eq!(parser.parse_value().unwrap_err().kind(), ErrorKind::NoScriptError);
+    assert_eq!(parser.parse_value(), Ok(Value::Status("PONG".into())));
+}
+
+#[test]
+fn test_error_in_map_is_read_to_the_end() {
+    use redis::{ErrorKind, Parser, Value};
+
+    let mut bytes: &[u8] = b"%2\r\n+a\r\n-ERR ERR in the map\r\n+b\r\n:1\r\n+PONG\r\n";
+    let mut parser = Parser::new(&mut bytes);
+    let err = parser.parse_value().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ResponseError);
+    assert_eq!(err.detail(), Some("ERR in the map"));
+    assert_eq!(parser.parse_value(), Ok(Value::Status("PONG".into())));
+}
+
+#[test]
+fn test_server_errors_in_bulk() {
+    use redis::{Parser, Value};
+
+    let mut bytes: &[u8] = b"*2\r\n-ERR first\r\n-BUSY busy\r\n+PONG\r\n";
+    let mut parser = Parser::new(&mut bytes);
+    let err = parser.parse_value().unwrap_err();
+    assert_eq!(err.to_string(), "An error was signalled by the server: first");
+    assert_eq!(parser.parse_value(), Ok(Value::Status("PONG".into())));
+}
+
+#[test]
+fn test_resp3_parsing() {
+    use redis::{parse_redis_value, FromRedisValue, Value};
+    use std::collections::HashMap;
+
+    let v = parse_redis_value(b"%2\r\n+a\r\n:1\r\n+b\r\n,1.5\r\n").unwrap();
+    assert_eq!(v,
+               Value::Map(vec![(Value::Status("a".into()), Value::Int(1)),
+                               (Value::Status("b".into()), Value::Double("1.5".into()))]));
+    let m: HashMap<String, f64> = FromRedisValue::from_redis_value(&v).unwrap();
+    assert_eq!(m.get("b"), Some(&1.5));
+
+    assert_eq!(parse_redis_value(b"_\r\n"), Ok(Value::Nil));
+    assert_eq!(parse_redis_value(b"#t\r\n"), Ok(Value::Boolean(true)));
+    assert_eq!(parse_redis_value(b",-inf\r\n"),
+               Ok(Value::Double("-inf".into())));
+    assert_eq!(parse_redis_value(b"(3492890328409238509324850943850943825024385\r\n"),
+               Ok(Value::BigNumber("3492890328409238509324850943850943825024385".into())));
+    assert_eq!(parse_redis_value(b"=15\r\ntxt:Some string\r\n"),
+               Ok(Value::VerbatimString {
+                   format: "txt".into(),
+                   text: "Some string".into(),
+               }));
+    assert_eq!(parse_redis_value(b"~2\r\n:1\r\n:2\r\n"),
+               Ok(Value::Set(vec![Value::Int(1), Value::Int(2)])));
+    assert_eq!(parse_redis_value(b"|1\r\n+ttl\r\n:3600\r\n:42\r\n"),
+               Ok(Value::Int(42)));
+    assert_eq!(parse_redis_value(b">2\r\n+message\r\n$3\r\nfoo\r\n"),
+               Ok(Value::Push(vec![Value::Status("message".into()), Value::Data(b"foo".to_vec())])));
+    assert!(parse_redis_value(b"!21\r\nSYNTAX invalid syntax\r\n").is_err());
+
+    for bad in &[&b"%-1\r\n"[..], b"~-1\r\n", b">-1\r\n", b"!-1\r\n"] {
+        assert_eq!(parse_redis_value(bad).unwrap_err().kind(), redis::ErrorKind::ResponseError);
+    }
+}
+
+#[test]
+fn test_key_slot() {
+    use redis::key_slot;
+
+    assert_eq!(key_slot(b"foo"), 12182);
+    assert_eq!(key_slot(b"123456789"), 0x31c3);
+    assert_eq!(key_slot(b"{user1000}.following"),
+               key_slot(b"{user1000}.followers"));
+    assert_eq!(key_slot(b"{user1000}.following"), key_slot(b"user1000"));
+    // empty hash tags are ignored
+    assert!(key_slot(b"foo{}{bar}") != key_slot(b"bar"));
+}
+
+#[test]
+fn test_cluster_types() {
+    use redis::{from_redis_value, parse_redis_value, ClusterInfo, ClusterNode, ClusterShard,
+                ClusterSlot, Value};
+
+    let nodes = "07c37dfeb235213a872192d90877d0cd55635b91 127.0.0.1:30004@31004,host-4 slave \
+                 e7d1eecce10fd6bb5eb35b9f99a514335d9ba9ca 0 1426238317239 4 connected\n\
+                 e7d1eecce10fd6bb5eb35b9f99a514335d9ba9ca 127.0.0.1:30001@31001 myself,master - \
+                 0 0 1 connected 0-5460 5462 [5461->-292f8b365bb7edb5e285caf0b7e6ddc7265d2f4f]\n";
+    let nodes: Vec<ClusterNode> = from_redis_value(&Value::Data(nodes.into())).unwrap();
+    assert_eq!(nodes.len(), 2);
+    assert_eq!(nodes[0].addr, "127.0.0.1:30004");
+    assert_eq!(nodes[0].bus_port, Some(31004));
+    assert_eq!(nodes[0].hostname, Some("host-4".to_string()));
+    assert_eq!(nodes[0].master_id, Some("e7d1eecce10fd6bb5eb35b9f99a514335d9ba9ca".to_string()));
+    assert!(!nodes[0].is_master());
+    assert!(nodes[1].is_master() && nodes[1].is_myself());
+    assert_eq!(nodes[1].master_id, None);
+    assert_eq!(nodes[1].slots, vec![(0, 5460), (5462, 5462)]);
+    assert!(ClusterNode::parse("garbage").is_err());
+
+    let slots = parse_redis_value(b"*1\r\n*4\r\n:0\r\n:5460\r\n\
+                                    *3\r\n$9\r\n127.0.0.1\r\n:30001\r\n$2\r\nm1\r\n\
+                                    *2\r\n$9\r\n127.0.0.1\r\n:30004\r\n").unwrap();
+    let slots: Vec<ClusterSlot> = from_redis_value(&slots).unwrap();
+    assert_eq!((slots[0].start, slots[0].end), (0, 5460));
+    assert_eq!(slots[0].master.port, 30001);
+    assert_eq!(slots[0].master.id, Some("m1".to_string()));
+    assert_eq!(slots[0].replicas.len(), 1);
+    assert_eq!(slots[0].replicas[0].id, None);
+
+    let shards = parse_redis_value(b"*1\r\n*4\r\n\
+                                     $5\r\nslots\r\n*4\r\n:0\r\n:5460\r\n:5462\r\n:5462\r\n\
+                                     $5\r\nnodes\r\n*1\r\n*14\r\n\
+                                     $2\r\nid\r\n$2\r\nm1\r\n$4\r\nport\r\n:30001\r\n\
+                                     $2\r\nip\r\n$9\r\n127.0.0.1\r\n\
+                                     $8\r\nendpoint\r\n$9\r\n127.0.0.1\r\n\
+                                     $4\r\nrole\r\n$6\r\nmaster\r\n\
+                                     $18\r\nreplication-offset\r\n:72156\r\n\
+                                     $6\r\nhealth\r\n$6\r\nonline\r\n").unwrap();
+    let shards: Vec<ClusterShard> = from_redis_value(&shards).unwrap();
+    assert_eq!(shards[0].slots, vec![(0, 5460), (5462, 5462)]);
+    assert_eq!(shards[0].nodes[0].port, Some(30001));
+    assert_eq!(shards[0].nodes[0].tls_port, None);
+    assert_eq!(shards[0].nodes[0].replication_offset, 72156);
+    assert_eq!(shards[0].nodes[0].role, "master");
+
+    let info = "cluster_state:ok\r\ncluster_slots_assigned:16384\r\ncluster_slots_ok:16384\r\n\
+                cluster_slots_pfail:0\r\ncluster_slots_fail:0\r\ncluster_known_nodes:6\r\n\
+                cluster_size:3\r\ncluster_current_epoch:6\r\ncluster_my_epoch:2\r\n";
+    let info: ClusterInfo = from_redis_value(&Value::Data(info.into())).unwrap();
+    assert!(info.state_ok);
+    assert_eq!((info.known_nodes, info.size, info.my_epoch), (6, 3, 2));
+}
+
+#[test]
+fn test_retry_policy_delay() {
+    use redis::RetryPolicy;
+    use std::time::Duration;
+
+    let policy = RetryPolicy::default();
+    assert_eq!(policy.delay(0), Duration::from_millis(50));
+    assert_eq!(policy.delay(2), Duration::from_millis(200));
+    assert_eq!(policy.delay(5), Duration::from_secs(1));
+    assert_eq!(policy.delay(100), Duration::from_secs(1));
+    assert_eq!(RetryPolicy::none().max_retries, 0);
+}
+
+#[test]
+fn test_keyspace_event_parsing() {
+    use redis::{KeyspaceEvent, KeyspaceEventType};
+
+    assert_eq!(KeyspaceEvent::parse("__keyspace@0__:foo", "set"),
+               Some(KeyspaceEvent {
+                   db: 0,
+                   key: "foo".to_string(),
+                   event: KeyspaceEventType::Set,
+               }));
+    assert_eq!(KeyspaceEvent::parse("__keyevent@12__:expired", "user:1:session"),
+               Some(KeyspaceEvent {
+                   db: 12,
+                   key: "user:1:session".to_string(),
+                   event: KeyspaceEventType::Expired,
+               }));
+    let event = KeyspaceEvent::parse("__keyspace@3__:a__:b", "lpush");
+    assert_eq!(event.map(|e| (e.key, e.event)),
+               Some(("a__:b".to_string(), KeyspaceEventType::Other("lpush".to_string()))));
+    assert_eq!(KeyspaceEvent::parse("news", "hello"), None);
+    assert_eq!(KeyspaceEvent::parse("__keyspace@x__:foo", "set"), None);
+}
+
+#[test]
+fn test_stream_read_reply() {
+    use redis::{Value, StreamReadReply};
+
+    let entry = Value::Bulk(vec![Value::Data(b"1-0".to_vec()),
+                                 Value::Bulk(vec![Value::Data(b"kind".to_vec()),
+                                                  Value::Data(b"login".to_vec())])]);
+    let resp2 = Value::Bulk(vec![Value::Bulk(vec![Value::Data(b"events".to_vec()),
+                                                  Value::Bulk(vec![entry.clone()])])]);
+    let resp3 = Value::Map(vec![(Value::Data(b"events".to_vec()), Value::Bulk(vec![entry]))]);
+
+    for value in &[resp2, resp3] {
+        let reply: StreamReadReply = redis::from_redis_value(value).unwrap();
+        assert_eq!(reply.keys.len(), 1);
+        assert_eq!(reply.keys[0].key, "events");
+        assert_eq!(reply.keys[0].entries[0].id, "1-0");
+        assert_eq!(reply.keys[0].entries[0].get("kind"), Some("login".to_string()));
+    }
+
+    let reply: StreamReadReply = redis::from_redis_value(&Value::Nil).unwrap();
+    assert!(reply.keys.is_empty());
+}
+
+#[test]
+fn test_stream_pending_replies() {
+    use redis::{Value, StreamPendingReply, StreamAutoClaimReply};
+
+    let empty = Value::Bulk(vec![Value::Int(0), Value::Nil, Value::Nil, Value::Nil]);
+    let reply: StreamPendingReply = redis::from_redis_value(&empty).unwrap();
+    assert_eq!(reply, StreamPendingReply::default());
+
+    // servers before 7.0 do not report deleted ids
+    let old = Value::Bulk(vec![Value::Data(b"0-0".to_vec()), Value::Bulk(vec![])]);
+    let reply: StreamAutoClaimReply = redis::from_redis_value(&old).unwrap();
+    assert_eq!(reply.next_id, "0-0");
+    assert!(reply.claimed.is_empty() && reply.deleted_ids.is_empty());
+}
+
+#[test]
+fn test_stream_info() {
+    use redis::{Value, StreamInfo, StreamGroupInfo, StreamConsumerInfo};
+
+    let entry = Value::Bulk(vec![data("1-0"), Value::Bulk(vec![data("task"), data("a")])]);
+    let info: StreamInfo = redis::from_redis_value(&Value::Bulk(vec![
+        data("length"), Value::Int(1),
+        data("radix-tree-keys"), Value::Int(1),
+        data("groups"), Value::Int(1),
+        data("last-generated-id"), data("1-0"),
+        data("first-entry"), entry.clone(),
+        data("last-entry"), entry,
+    ])).unwrap();
+    assert_eq!(info.length, 1);
+    assert_eq!(info.last_generated_id, "1-0");
+    assert_eq!(info.first_entry.unwrap().get("task"), Some("a".to_string()));
+
+    let groups: Vec<StreamGroupInfo> = redis::from_redis_value(&Value::Bulk(vec![
+        Value::Map(vec![(data("name"), data("workers")),
+                        (data("consumers"), Value::Int(2)),
+                        (data("pending"), Value::Int(1)),
+                        (data("last-delivered-id"), data("1-0")),
+                        (data("entries-read"), Value::Int(1)),
+                        (data("lag"), Value::Nil)]),
+    ])).unwrap();
+    assert_eq!(groups[0].name, "workers");
+    assert_eq!(groups[0].entries_read, Some(1));
+    assert_eq!(groups[0].lag, None);
+
+    let consumers: Vec<StreamConsumerInfo> = redis::from_redis_value(&Value::Bulk(vec![
+        Value::Bulk(vec![data("name"), data("alice"),
+                         data("pending"), Value::Int(1),
+                         data("idle"), Value::Int(250)]),
+    ])).unwrap();
+    assert_eq!(consumers[0].idle_ms, 250);
+    assert_eq!(consumers[0].inactive_ms, None);
+}
+
+#[test]
+fn test_geo_search() {
+    use redis::{Value, ToRedisArgs, Coord, GeoOrder, GeoSearch, GeoSearchResult, GeoShape, Unit};
+
+    let search = GeoSearch::from_coord(Coord::lon_lat(15.0, 37.0),
+                                       GeoShape::Box(400.0, 200.0, Unit::Kilometers))
+        .order(GeoOrder::Asc)
+        .count_any(2)
+        .with_dist()
+        .with_coord();
+    let args: Vec<String> = search.to_redis_args()
+        .into_iter()
+        .map(|arg| String::from_utf8(arg).unwrap())
+        .collect();
+    assert_eq!(args,
+               vec!["FROMLONLAT", "15", "37", "BYBOX", "400", "200", "km", "ASC", "COUNT", "2",
+                    "ANY", "WITHCOORD", "WITHDIST"]);
+
+    let result: GeoSearchResult = redis::from_redis_value(&Value::Bulk(vec![
+        Value::Data(b"Palermo".to_vec()),
+        Value::Data(b"190.4424".to_vec()),
+        Value::Int(3479099956230698),
+        Value::Bulk(vec![Value::Data(b"13.5".to_vec()), Value::Data(b"38.25".to_vec())]),
+    ])).unwrap();
+    assert_eq!(result.name, "Palermo");
+    assert_eq!(result.dist, Some(190.4424));
+    assert_eq!(result.hash, Some(3479099956230698));
+    assert_eq!(result.coord, Some(Coord::lon_lat(13.5, 38.25)));
+
+    let names: Vec<GeoSearchResult> =
+        redis::from_redis_value(&Value::Bulk(vec![Value::Data(b"Catania".to_vec())])).unwrap();
+    assert_eq!(names[0].name, "Catania");
+    assert_eq!(names[0].dist, None);
+}
+
+#[test]
+fn test_bitfield_operations() {
+    use redis::{ToRedisArgs, BitFieldOffset, BitFieldOperations, BitFieldOverflow, BitFieldType};
+
+    let ops = BitFieldOperations::new()
+        .get(BitFieldType::Unsigned(4), 0)
+        .overflow(BitFieldOverflow::Sat)
+        .set(BitFieldType::Signed(8), BitFieldOffset::Index(1), -5)
+        .incr_by(BitFieldType::Unsigned(2), 100, 1);
+    let args: Vec<String> = ops.to_redis_args()
+        .into_iter()
+        .map(|arg| String::from_utf8(arg).unwrap())
+        .collect();
+    assert_eq!(args,
+               vec!["GET", "u4", "0", "OVERFLOW", "SAT", "SET", "i8", "#1", "-5", "INCRBY", "u2",
+                    "100", "1"]);
+}
+
+#[test]
+fn test_zadd_options() {
+    use redis::{ToRedisArgs, ExistenceCheck, ZAddComparison, ZAddOptions};
+
+    let opts = ZAddOptions::default()
+        .conditional_set(ExistenceCheck::Xx)
+        .update_if(ZAddComparison::Lt)
+        .changed()
+        .incr();
+    assert_eq!(opts.to_redis_args(),
+               vec![b"XX".to_vec(), b"LT".to_vec(), b"CH".to_vec(), b"INCR".to_vec()]);
+    assert!(ZAddOptions::default().to_redis_args().is_empty());
+}
+
+#[test]
+fn test_nested_tuples() {
+    use redis::{Nested, RedisResult, Value};
+
+    let pairs = Value::Bulk(vec![
+        Value::Bulk(vec![Value::Data(b"a".to_vec()), Value::Data(b"1".to_vec())]),
+        Value::Bulk(vec![Value::Data(b"b".to_vec()), Value::Double("2.5".into())]),
+    ]);
+    let v: Vec<Nested<(String, f64)>> = redis::from_redis_value(&pairs).unwrap();
+    assert_eq!(v, vec![Nested(("a".to_string(), 1.0)), Nested(("b".to_string(), 2.5))]);
+
+    // the shape of the reply is not guessed
+    let v: RedisResult<Vec<(String, f64)>> = redis::from_redis_value(&pairs);
+    assert!(v.is_err());
+
+    let flat = Value::Bulk(vec![Value::Data(b"a".to_vec()), Value::Data(b"1".to_vec())]);
+    let v: Vec<(String, f64)> = redis::from_redis_value(&flat).unwrap();
+    assert_eq!(v, vec![("a".to_string(), 1.0)]);
+    let v: RedisResult<Vec<Nested<(String, f64)>>> = redis::from_redis_value(&flat);
+    assert!(v.is_err());
+}
+
+#[test]
+fn test_range_query() {
+    use redis::{ToRedisArgs, RangeQuery};
+
+    fn args(query: RangeQuery) -> Vec<String> {
+        query.to_redis_args()
+            .into_iter()
+            .map(|arg| String::from_utf8(arg).unwrap())
+            .collect()
+    }
+
+    assert_eq!(args(RangeQuery::by_index(0, -1).rev().with_scores()),
+               vec!["0", "-1", "REV", "WITHSCORES"]);
+    assert_eq!(args(RangeQuery::by_score("(1", "+inf").rev().limit(0, 10)),
+               vec!["+inf", "(1", "BYSCORE", "REV", "LIMIT", "0", "10"]);
+    assert_eq!(args(RangeQuery::by_lex("[a", "[c")), vec!["[a", "[c", "BYLEX"]);
+}
+
+#[test]
+fn test_lcs_matches() {
+    use redis::{FromRedisValue, Value, LcsMatch, LcsMatches};
+
+    fn bulk_pair(a: i64, b: i64) -> Value {
+        Value::Bulk(vec![Value::Int(a), Value::Int(b)])
+    }
+
+    let v = Value::Bulk(vec![
+        Value::Data(b"matches".to_vec()),
+        Value::Bulk(vec![
+            Value::Bulk(vec![bulk_pair(4, 7), bulk_pair(5, 8), Value::Int(4)]),
+            Value::Bulk(vec![bulk_pair(2, 3), bulk_pair(0, 1)]),
+        ]),
+        Value::Data(b"len".to_vec()),
+        Value::Int(6),
+    ]);
+    assert_eq!(LcsMatches::from_redis_value(&v).unwrap(),
+               LcsMatches {
+                   matches: vec![LcsMatch { first: (4, 7), second: (5, 8), len: Some(4) },
+                                 LcsMatch { first: (2, 3), second: (0, 1), len: None }],
+                   len: 6,
+               });
+}
+
+#[test]
+fn test_encoding() {
+    use redis::{FromRedisValue, Value, Encoding};
+
+    assert_eq!(Encoding::from_redis_value(&Value::Data(b"listpack".to_vec())),
+               Ok(Encoding::ListPack));
+    assert_eq!(Encoding::from_redis_value(&Value::Status("embstr".into())),
+               Ok(Encoding::EmbStr));
+    assert_eq!(Encoding::from_redis_value(&Value::Data(b"unknown".to_vec())),
+               Ok(Encoding::Other("unknown".into())));
+    assert_eq!(Option::<Encoding>::from_redis_value(&Value::Nil), Ok(None));
+}
+
+#[test]
+fn test_memory_stats() {
+    use redis::{FromRedisValue, Value, MemoryStats};
+
+    let v = Value::Bulk(vec![
+        Value::Status("peak.allocated".into()),
+        Value::Int(1024),
+        Value::Status("total.allocated".into()),
+        Value::Int(900),
+        Value::Status("db.0".into()),
+        Value::Bulk(vec![Value::Status("overhead.hashtable.main".into()), Value::Int(72)]),
+        Value::Status("keys.count".into()),
+        Value::Int(3),
+        Value::Status("fragmentation".into()),
+        Value::Data(b"1.5".to_vec()),
+    ]);
+    let stats = MemoryStats::from_redis_value(&v).unwrap();
+    assert_eq!(stats.peak_allocated, 1024);
+    assert_eq!(stats.total_allocated, 900);
+    assert_eq!(stats.keys_count, 3);
+    assert_eq!(stats.dataset_bytes, 0);
+    assert_eq!(stats.fragmentation, 1.5);
+    assert_eq!(stats.get::<u64>("keys.count"), Some(3));
+    assert_eq!(stats.get::<Vec<(String, u64)>>("db.0"),
+               Some(vec![("overhead.hashtable.main".to_string(), 72)]));
+}
+
+#[test]
+fn test_client_info() {
+    use redis::{FromRedisValue, Value, ClientInfo};
+
+    let text = "id=3 addr=127.0.0.1:52555 laddr=127.0.0.1:6379 fd=8 name= age=12 idle=2 \
+                flags=N db=0 sub=0 psub=0 multi=-1 cmd=client|list user=default\n\
+                id=4 addr=127.0.0.1:52556 laddr=127.0.0.1:6379 fd=9 name=worker age=5 idle=5 \
+                flags=P db=2 sub=1 psub=0 multi=-1 cmd=subscribe user=default\n";
+    let clients: Vec<ClientInfo> = FromRedisValue::from_redis_value(&Value::Data(text.into()))
+        .unwrap();
+    assert_eq!(clients.len(), 2);
+    assert_eq!(clients[0].id, 3);
+    assert_eq!(clients[0].addr, "127.0.0.1:52555");
+    assert_eq!(clients[0].name, None);
+    assert_eq!(clients[0].age, 12);
+    assert_eq!(clients[0].cmd, "client|list");
+    assert_eq!(clients[0].get("user"), Some("default"));
+    assert_eq!(clients[1].name, Some("worker".to_string()));
+    assert_eq!(clients[1].db, 2);
+    assert!(clients[1].has_flag('P'));
+
+    // RESP3 replies with a verbatim string
+    let v = Value::VerbatimString { format: "txt".to_string(), text: text.to_string() };
+    let clients: Vec<ClientInfo> = FromRedisValue::from_redis_value(&v).unwrap();
+    assert_eq!(clients.len(), 2);
+    assert_eq!(clients[1].name, Some("worker".to_string()));
+
+    assert!(ClientInfo::parse("not a client").is_err());
+}
+
+#[test]
+fn test_client_kill_filter() {
+    use redis::{ToRedisArgs, ClientKillFilter, ClientType};
+
+    let filter = ClientKillFilter::new()
+        .id(7)
+        .addr("127.0.0.1:5000")
+        .laddr("127.0.0.1:6379")
+        .client_type(ClientType::Replica)
+        .user("default")
+        .skip_me(false);
+    let args: Vec<String> = filter.to_redis_args()
+        .into_iter()
+        .map(|arg| String::from_utf8(arg).unwrap())
+        .collect();
+    assert_eq!(args,
+               vec!["ID", "7", "ADDR", "127.0.0.1:5000", "LADDR", "127.0.0.1:6379", "TYPE",
+                    "replica", "USER", "default", "SKIPME", "no"]);
+}
+
+#[test]
+fn test_slowlog_entry() {
+    use std::time::Duration;
+    use redis::{FromRedisValue, Value, SlowLogEntry};
+
+    let v = Value::Bulk(vec![
+        Value::Int(14),
+        Value::Int(1309448221),
+        Value::Int(1500015),
+        Value::Bulk(vec![Value::Data(b"KEYS".to_vec()), Value::Data(b"*".to_vec())]),
+        Value::Data(b"127.0.0.1:58217".to_vec()),
+        Value::Data(b"".to_vec()),
+    ]);
+    assert_eq!(SlowLogEntry::from_redis_value(&v),
+               Ok(SlowLogEntry {
+                   id: 14,
+                   timestamp: 1309448221,
+                   duration: Duration::new(1, 500_015_000),
+                   args: vec!["KEYS".to_string(), "*".to_string()],
+                   client_addr: Some("127.0.0.1:58217".to_string()),
+                   client_name: None,
+               }));
+}
+
+#[test]
+fn test_server_info() {
+    use redis::{FromRedisValue, Value, ServerInfo, KeyspaceInfo};
+
+    let text = "# Server\r\nredis_version:7.2.4\r\n\r\n\
+                # Memory\r\nused_memory:1048576\r\nmaxmemory_policy:noeviction\r\n\
+                mem_fragmentation_ratio:1.25\r\n\r\n\
+                # Persistence\r\nloading:0\r\naof_enabled:1\r\n\r\n\
+                # Stats\r\nkeyspace_hits:10\r\nkeyspace_misses:2\r\n\r\n\
+                # Replication\r\nrole:slave\r\nmaster_host:10.0.0.1\r\nmaster_port:6379\r\n\r\n\
+                # Keyspace\r\ndb0:keys=3,expires=1,avg_ttl=500\r\n\
+                db2:keys=7,expires=0,avg_ttl=0\r\n";
+    let info = ServerInfo::from_redis_value(&Value::Data(text.into())).unwrap();
+    assert_eq!(info.memory.used_memory, 1048576);
+    assert_eq!(info.memory.maxmemory_policy, "noeviction");
+    assert_eq!(info.memory.mem_fragmentation_ratio, 1.25);
+    assert!(!info.persistence.loading);
+    assert!(info.persistence.aof_enabled);
+    assert_eq!(info.stats.keyspace_hits, 10);
+    assert_eq!(info.replication.role, "slave");
+    assert_eq!(info.replication.master_host, Some("10.0.0.1".to_string()));
+    assert_eq!(info.replication.master_port, Some(6379));
+    assert_eq!(info.keyspace.len(), 2);
+    assert_eq!(info.keyspace[&0], KeyspaceInfo { keys: 3, expires: 1, avg_ttl: 500 });
+    assert_eq!(info.keyspace[&2].keys, 7);
+    assert_eq!(info.get("redis_version"), Some("7.2.4".to_string()));
+}
+
+#[test]
+fn test_acl() {
+    use redis::{FromRedisValue, ToRedisArgs, Value, AclRules, AclUserInfo};
+
+    let rules = AclRules::new()
+        .reset()
+        .on()
+        .password("secret")
+        .key_pattern("app:*")
+        .read_key_pattern("shared:*")
+        .channel_pattern("events")
+        .category("read")
+        .deny_command("flushdb");
+    let args: Vec<String> = rules.to_redis_args()
+        .into_iter()
+        .map(|arg| String::from_utf8(arg).unwrap())
+        .collect();
+    assert_eq!(args,
+               vec!["reset", "on", ">secret", "~app:*", "%R~shared:*", "&events", "+@read",
+                    "-flushdb"]);
+
+    let v = Value::Bulk(vec![
+        data("flags"),
+        Value::Bulk(vec![data("on")]),
+        data("passwords"),
+        Value::Bulk(vec![data("2bb80d53")]),
+        data("commands"),
+        data("-@all +@read -flushdb"),
+        data("keys"),
+        data("~app:* %R~shared:*"),
+        data("channels"),
+        data("&events"),
+        data("selectors"),
+        Value::Bulk(vec![]),
+    ]);
+    let user = AclUserInfo::from_redis_value(&v).unwrap();
+    assert!(user.has_flag("on"));
+    assert_eq!(user.passwords, vec!["2bb80d53"]);
+    assert_eq!(user.commands, "-@all +@read -flushdb");
+    assert_eq!(user.keys, vec!["~app:*", "%R~shared:*"]);
+    assert_eq!(user.channels, vec!["&events"]);
+    assert_eq!(user.get::<Vec<String>>("selectors"), Some(vec![]));
+
+    // redis 6 replies lists of patterns
+    let v = Value::Bulk(vec![data("flags"),
+                             Value::Bulk(vec![data("off")]),
+                             data("keys"),
+                             Value::Bulk(vec![data("app:*")])]);
+    let user = AclUserInfo::from_redis_value(&v).unwrap();
+    assert_eq!(user.keys, vec!["app:*"]);
+    assert!(user.channels.is_empty());
+}
+
+#[test]
+fn test_library_info() {
+    use redis::{FromRedisValue, Value, LibraryInfo, FunctionInfo};
+
+    let v = Value::Bulk(vec![
+        data("library_name"),
+        data("mylib"),
+        data("engine"),
+        data("LUA"),
+        data("functions"),
+        Value::Bulk(vec![Value::Bulk(vec![data("name"),
+                                          data("myfunc"),
+                                          data("description"),
+                                          Value::Nil,
+                                          data("flags"),
+                                          Value::Bulk(vec![data("no-writes")])])]),
+    ]);
+    assert_eq!(LibraryInfo::from_redis_value(&v),
+               Ok(LibraryInfo {
+                   name: "mylib".to_string(),
+                   engine: "LUA".to_string(),
+                   functions: vec![FunctionInfo {
+                                       name: "myfunc".to_string(),
+                                       description: None,
+                                       flags: vec!["no-writes".to_string()],
+                                   }],
+                   code: None,
+               }));
+}
+
+#[test]
+fn test_failover_options() {
+    use std::time::Duration;
+    use redis::{ToRedisArgs, FailoverOptions};
+
+    fn args(options: FailoverOptions) -> Vec<String> {
+        options.to_redis_args()
+            .into_iter()
+            .map(|arg| String::from_utf8(arg).unwrap())
+            .collect()
+    }
+
+    assert!(args(FailoverOptions::new()).is_empty());
+    let options = FailoverOptions::new()
+        .force_to("10.0.0.2", 6380)
+        .timeout(Duration::new(1, 500_000_000));
+    assert_eq!(args(options),
+               vec!["TO", "10.0.0.2", "6380", "FORCE", "TIMEOUT", "1500"]);
+    assert_eq!(args(FailoverOptions::new().abort()), vec!["ABORT"]);
+}
+
+#[test]
+fn test_restore_options() {
+    use redis::{ToRedisArgs, RestoreOptions};
+
+    let args: Vec<String> = RestoreOptions::default()
+        .replace()
+        .absttl()
+        .idletime(60)
+        .freq(5)
+        .to_redis_args()
+        .into_iter()
+        .map(|arg| String::from_utf8(arg).unwrap())
+        .collect();
+    assert_eq!(args, vec!["REPLACE", "ABSTTL", "IDLETIME", "60", "FREQ", "5"]);
+}
+
+#[test]
+fn test_migrate() {
+    use redis::{FromRedisValue, ToRedisArgs, Value, MigrateOptions, MigrateReply};
+
+    let args: Vec<String> = MigrateOptions::default()
+        .copy()
+        .replace()
+        .auth2("admin", "secret")
+        .to_redis_args()
+        .into_iter()
+        .map(|arg| String::from_utf8(arg).unwrap())
+        .collect();
+    assert_eq!(args, vec!["COPY", "REPLACE", "AUTH2", "admin", "secret"]);
+
+    assert_eq!(MigrateReply::from_redis_value(&Value::Okay), Ok(MigrateReply::Ok));
+    assert_eq!(MigrateReply::from_redis_value(&Value::Status("NOKEY".into())),
+               Ok(MigrateReply::NoKey));
+    assert!(MigrateReply::from_redis_value(&Value::Int(1)).is_err());
+}
+
+#[test]
+fn test_latency() {
+    use std::time::Duration;
+    use redis::{FromRedisValue, Value, LatencySample, LatencyEvent};
+
+    let v = Value::Bulk(vec![Value::Bulk(vec![Value::Int(1405067822), Value::Int(251)]),
+                             Value::Bulk(vec![Value::Int(1405067941), Value::Int(1001)])]);
+    let samples: Vec<LatencySample> = FromRedisValue::from_redis_value(&v).unwrap();
+    assert_eq!(samples,
+               vec![LatencySample {
+                        timestamp: 1405067822,
+                        latency: Duration::from_millis(251),
+                    },
+                    LatencySample {
+                        timestamp: 1405067941,
+                        latency: Duration::from_millis(1001),
+                    }]);
+
+    let v = Value::Bulk(vec![Value::Bulk(vec![Value::Data(b"command".to_vec()),
+                                              Value::Int(1405067976),
+                                              Value::Int(251),
+                                              Value::Int(1001)])]);
+    let events: Vec<LatencyEvent> = FromRedisValue::from_redis_value(&v).unwrap();
+    assert_eq!(events,
+               vec![LatencyEvent {
+                        name: "command".to_string(),
+                        timestamp: 1405067976,
+                        latest: Duration::from_millis(251),
+                        max: Duration::from_millis(1001),
+                    }]);
+}
+
+#[test]
+fn test_command_introspection() {
+    use std::collections::HashMap;
+    use redis::{FromRedisValue, Value, CommandInfo, CommandDocs};
+
+    let v = Value::Bulk(vec![
+        Value::Bulk(vec![data("get"),
+                         Value::Int(2),
+                         Value::Bulk(vec![Value::Status("readonly".into()),
+                                          Value::Status("fast".into())]),
+                         Value::Int(1),
+                         Value::Int(1),
+                         Value::Int(1),
+                         Value::Bulk(vec![data("@read"), data("@string")])]),
+        Value::Nil,
+    ]);
+    let infos: Vec<Option<CommandInfo>> = FromRedisValue::from_redis_value(&v).unwrap();
+    assert_eq!(infos,
+               vec![Some(CommandInfo {
+                        name: "get".to_string(),
+                        arity: 2,
+                        flags: vec!["readonly".to_string(), "fast".to_string()],
+                        first_key: 1,
+                        last_key: 1,
+                        step: 1,
+                        acl_categories: vec!["@read".to_string(), "@string".to_string()],
+                    }),
+                    None]);
+
+    let v = Value::Bulk(vec![
+        data("get"),
+        Value::Bulk(vec![data("summary"),
+                         data("Returns the string value of a key."),
+                         data("since"),
+                         data("1.0.0"),
+                         data("group"),
+                         data("string"),
+                         data("complexity"),
+                         data("O(1)"),
+                         data("arguments"),
+                         Value::Bulk(vec![])]),
+    ]);
+    let docs: HashMap<String, CommandDocs> = FromRedisValue::from_redis_value(&v).unwrap();
+    let get = &docs["get"];
+    assert_eq!(get.summary, "Returns the string value of a key.");
+    assert_eq!(get.since, "1.0.0");
+    assert_eq!(get.group, "string");
+    assert_eq!(get.complexity, Some("O(1)".to_string()));
+    assert_eq!(get.get::<Vec<Value>>("arguments"), Some(vec![]));
+}
+
+#[test]
+fn test_tracking_options() {
+    use redis::{ToRedisArgs, TrackingOptions};
+
+    let args: Vec<String> = TrackingOptions::default()
+        .redirect(12)
+        .bcast()
+        .prefix("user:")
+        .prefix("session:")
+        .noloop()
+        .to_redis_args()
+        .into_iter()
+        .map(|arg| String::from_utf8(arg).unwrap())
+        .collect();
+    assert_eq!(args,
+               vec!["REDIRECT", "12", "PREFIX", "user:", "PREFIX", "session:", "BCAST", "NOLOOP"]);
+}
+
+#[cfg(feature="serde")]
+#[test]
+fn test_serde() {
+    use redis::{FromRedisValue, ToRedisArgs, Value, Serde, SerdeJson};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct User {
+        name: String,
+        tags: Vec<String>,
+        age: Option<u32>,
+    }
+
+    let user = User {
+        name: "Jane".to_string(),
+        tags: vec!["admin".to_string()],
+        age: Some(42),
+    };
+
+    let args = Serde(&user).to_redis_args();
+    assert_eq!(args.len(), 1);
+    let v = Value::Data(args[0].clone());
+    assert_eq!(Serde::<User>::from_redis_value(&v), Ok(Serde(user)));
+
+    let args = SerdeJson(User { name: "Joe".to_string(), tags: vec![], age: None })
+        .to_redis_args();
+    assert_eq!(args, vec![br#"{"name":"Joe","tags":[],"age":null}"#.to_vec()]);
+    let v = Value::Data(br#"{"name":"Joe","tags":["x"],"age":7}"#.to_vec());
+    let SerdeJson(user): SerdeJson<User> = FromRedisValue::from_redis_value(&v).unwrap();
+    assert_eq!(user.tags, vec!["x".to_string()]);
+    assert_eq!(user.age, Some(7));
+
+    let v = Value::Data(b"{".to_vec());
+    let rv: redis::RedisResult<SerdeJson<User>> = FromRedisValue::from_redis_value(&v);
+    assert_eq!(rv.unwrap_err().kind(), redis::ErrorKind::TypeError);
+}
+
+#[cfg(feature="derive")]
+#[test]
+fn test_redis_hash_derive() {
+    use redis::{FromRedisValue, RedisHash, ToRedisArgs, Value};
+
+    #[derive(RedisHash, Debug, PartialEq)]
+    struct User {
+        name: String,
+        age: u32,
+        #[redis(rename = "mail")]
+        email: Option<String>,
+    }
+
+    let user = User {
+        name: "Jane".to_string(),
+        age: 42,
+        email: None,
+    };
+    let args: Vec<String> = user.to_redis_args()
+        .into_iter()
+        .map(|arg| String::from_utf8(arg).unwrap())
+        .collect();
+    assert_eq!(args, vec!["name", "Jane", "age", "42"]);
+    assert!(!user.is_single_arg());
+
+    let v = Value::Bulk(vec![Value::Data(b"age".to_vec()),
+                             Value::Data(b"42".to_vec()),
+                             Value::Data(b"mail".to_vec()),
+                             Value::Data(b"jane@example.com".to_vec()),
+                             Value::Data(b"name".to_vec()),
+                             Value::Data(b"Jane".to_vec())]);
+    assert_eq!(User::from_redis_value(&v),
+               Ok(User {
+                   name: "Jane".to_string(),
+                   age: 42,
+                   email: Some("jane@example.com".to_string()),
+               }));
+
+    let v = Value::Bulk(vec![Value::Data(b"name".to_vec()), Value::Data(b"Jane".to_vec())]);
+    let err = User::from_redis_value(&v).unwrap_err();
+    assert_eq!(err.kind(), redis::ErrorKind::TypeError);
+}
+
+#[cfg(feature="chrono")]
+#[test]
+fn test_chrono() {
+    use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+    use redis::{FromRedisValue, ToRedisArgs, Value};
+
+    let dt = Utc.with_ymd_and_hms(2024, 3, 1, 12, 30, 0).unwrap();
+    assert_eq!(dt.to_redis_args(), vec![b"2024-03-01T12:30:00Z".to_vec()]);
+    let v = Value::Data(b"2024-03-01T13:30:00+01:00".to_vec());
+    assert_eq!(DateTime::<Utc>::from_redis_value(&v), Ok(dt));
+    assert_eq!(DateTime::<Utc>::from_redis_value(&Value::Int(1709296200)), Ok(dt));
+    assert_eq!(DateTime::<Utc>::from_redis_value(&Value::Data(b"1709296200".to_vec())),
+               Ok(dt));
+
+    let naive = dt.naive_utc();
+    assert_eq!(naive.to_redis_args(), vec![b"2024-03-01T12:30:00".to_vec()]);
+    let v = Value::Data(b"2024-03-01T12:30:00".to_vec());
+    assert_eq!(NaiveDateTime::from_redis_value(&v), Ok(naive));
+
+    let v = Value::Data(b"yesterday".to_vec());
+    assert_eq!(DateTime::<Utc>::from_redis_value(&v).unwrap_err().kind(),
+               redis::ErrorKind::TypeError);
+}
+
+#[cfg(feature="time")]
+#[test]
+fn test_time() {
+    use redis::{FromRedisValue, ToRedisArgs, Value};
+    use time::OffsetDateTime;
+
+    let dt = OffsetDateTime::from_unix_timestamp(1709296200).unwrap();
+    assert_eq!(dt.to_redis_args(), vec![b"2024-03-01T12:30:00Z".to_vec()]);
+    let v = Value::Data(b"2024-03-01T13:30:00+01:00".to_vec());
+    assert_eq!(OffsetDateTime::from_redis_value(&v), Ok(dt));
+    assert_eq!(OffsetDateTime::from_redis_value(&Value::Int(1709296200)), Ok(dt));
+}
+
+#[cfg(feature="uuid")]
+#[test]
+fn test_uuid() {
+    use redis::{FromRedisValue, ToRedisArgs, Value};
+    use uuid::Uuid;
+
+    let text = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+    let id = Uuid::parse_str(text).unwrap();
+    assert_eq!(id.to_redis_args(), vec![text.as_bytes().to_vec()]);
+    assert_eq!(Uuid::from_redis_value(&Value::Data(text.as_bytes().to_vec())), Ok(id));
+    assert_eq!(Uuid::from_redis_value(&Value::Data(id.as_bytes().to_vec())), Ok(id));
+
+    let v = Value::Data(b"67e55044".to_vec());
+    assert_eq!(Uuid::from_redis_value(&v).unwrap_err().kind(), redis::ErrorKind::TypeError);
+}
+
+#[test]
+fn test_owned_conversion() {
+    use redis::{from_owned_redis_value, Value};
+
+    let data = b"binary\xff".to_vec();
+    let ptr = data.as_ptr();
+    let v: Vec<u8> = from_owned_redis_value(Value::Data(data)).unwrap();
+    assert_eq!(v.as_ptr(), ptr);
+
+    let v: Option<String> = from_owned_redis_value(Value::Data(b"foo".to_vec())).unwrap();
+    assert_eq!(v, Some("foo".to_string()));
+    let v: Option<String> = from_owned_redis_value(Value::Nil).unwrap();
+    assert_eq!(v, None);
+    let v: Vec<String> = from_owned_redis_value(Value::Bulk(vec![Value::Data(b"a".to_vec())]))
+        .unwrap();
+    assert_eq!(v, vec!["a".to_string()]);
+
+    let v: redis::RedisResult<String> = from_owned_redis_value(Value::Data(vec![0xff]));
+    assert_eq!(v.unwrap_err().kind(), redis::ErrorKind::TypeError);
+}
+
+#[cfg(feature="bytes")]
+#[test]
+fn test_bytes() {
+    use bytes::Bytes;
+    use redis::{from_owned_redis_value, FromRedisValue, Value};
+
+    let data = vec![0u8, 1, 2, 255];
+    let ptr = data.as_ptr();
+    let v: Bytes = from_owned_redis_value(Value::Data(data)).unwrap();
+    assert_eq!(&v[..], &[0u8, 1, 2, 255][..]);
+    assert_eq!(v.as_ptr(), ptr);
+
+    assert_eq!(Bytes::from_redis_value(&Value::Data(b"foo".to_vec())),
+               Ok(Bytes::from_static(b"foo")));
+    assert_eq!(Bytes::from_redis_value(&Value::Nil), Ok(Bytes::new()));
+}
+
+#[test]
+fn test_redis_string() {
+    use redis::{from_owned_redis_value, FromRedisValue, RedisString, ToRedisArgs, Value};
+
+    let s = RedisString::from_redis_value(&Value::Data(b"caf\xe9".to_vec())).unwrap();
+    assert_eq!(s.as_bytes(), &b"caf\xe9"[..]);
+    assert!(s.to_str().is_err());
+    assert_eq!(s.to_string_lossy(), "caf\u{FFFD}");
+    assert_eq!(s.to_string(), "caf\u{FFFD}");
+    assert_eq!(s.clone().into_string_lossy(), "caf\u{FFFD}");
+    assert_eq!(s.clone().into_string().unwrap_err().kind(), redis::ErrorKind::TypeError);
+    assert_eq!(s.to_redis_args(), vec![b"caf\xe9".to_vec()]);
+
+    let s: RedisString = from_owned_redis_value(Value::Status("OK".to_string())).unwrap();
+    assert_eq!(s.to_str(), Ok("OK"));
+    assert_eq!(RedisString::from_redis_value(&Value::Okay), Ok(RedisString::from("OK")));
+}
+
+#[test]
+fn test_invalid_utf8_is_explicit() {
+    use redis::{from_owned_redis_value, FromRedisValue, RedisString, Value};
+
+    // `String` always is strict, lossy decoding is asked for per value
+    let invalid = Value::Data(b"caf\xe9".to_vec());
+    let err = String::from_redis_value(&invalid).unwrap_err();
+    assert_eq!(err.kind(), redis::ErrorKind::TypeError);
+    let err = from_owned_redis_value::<String>(invalid.clone()).unwrap_err();
+    assert_eq!(err.kind(), redis::ErrorKind::TypeError);
+
+    let v = Value::Bulk(vec![invalid, Value::Data(b"tea".to_vec())]);
+
+    let items: Vec<RedisString> = from_owned_redis_value(v).unwrap();
+    let lossy: Vec<String> = items.into_iter().map(|s| s.into_string_lossy()).collect();
+    assert_eq!(lossy, vec!["caf\u{FFFD}".to_string(), "tea".to_string()]);
+}
+
+#[test]
+fn test_nested_collections() {
+    use redis::{FromRedisValue, Nested, RedisResult, Value};
+    use std::collections::{BTreeMap, HashMap};
+
+    let v = Value::Bulk(vec![Value::Bulk(vec![data("a"), data("1"), data("b"), data("2")]),
+                             Value::Map(vec![(data("c"), data("3"))])]);
+    let maps: Vec<HashMap<String, String>> = FromRedisValue::from_redis_value(&v).unwrap();
+    assert_eq!(maps.len(), 2);
+    assert_eq!(maps[0].get("b"), Some(&"2".to_string()));
+    assert_eq!(maps[1].get("c"), Some(&"3".to_string()));
+
+    let v = Value::Bulk(vec![data("k1"),
+                             Value::Bulk(vec![data("m"), data("1.5"), data("n"), data("2")]),
+                             data("k2"),
+                             Value::Bulk(vec![])]);
+    let scores: Vec<(String, Vec<(String, f64)>)> = FromRedisValue::from_redis_value(&v)
+        .unwrap();
+    assert_eq!(scores,
+               vec![("k1".to_string(), vec![("m".to_string(), 1.5), ("n".to_string(), 2.0)]),
+                    ("k2".to_string(), vec![])]);
+
+    let v = Value::Bulk(vec![data("k1"), data("\x00\x01"), data("k2"), Value::Nil]);
+    let blobs: BTreeMap<String, Vec<u8>> = FromRedisValue::from_redis_value(&v).unwrap();
+    assert_eq!(blobs.get("k1"), Some(&vec![0u8, 1]));
+    assert_eq!(blobs.get("k2"), Some(&vec![]));
+
+    // RESP3 replies WITHSCORES as nested pairs which are only read as such
+    // when asked for
+    let v = Value::Bulk(vec![Value::Bulk(vec![data("m"), Value::Double("1.5".into())]),
+                             Value::Bulk(vec![data("n"), Value::Double("2.0".into())])]);
+    let scores: Vec<Nested<(String, f64)>> = FromRedisValue::from_redis_value(&v).unwrap();
+    let scores: HashMap<String, f64> = scores.into_iter().map(Nested::into_inner).collect();
+    assert_eq!(scores.get("n"), Some(&2.0));
+    let scores: RedisResult<HashMap<String, f64>> = FromRedisValue::from_redis_value(&v);
+    assert!(scores.is_err());
+    let v = Value::Bulk(vec![data("m"), data("1.5")]);
+    let scores: RedisResult<Vec<Nested<(String, f64)>>> = FromRedisValue::from_redis_value(&v);
+    assert!(scores.is_err());
+
+    // deleted stream entries have no fields
+    let v = Value::Bulk(vec![Value::Bulk(vec![data("1-0"), Value::Nil]),
+                             Value::Bulk(vec![data("2-0"),
+                                              Value::Bulk(vec![data("f"), data("v")])])]);
+    let entries: Vec<Nested<(String, HashMap<String, String>)>> =
+        FromRedisValue::from_redis_value(&v).unwrap();
+    assert!((entries[0].0).1.is_empty());
+    assert_eq!((entries[1].0).1.get("f"), Some(&"v".to_string()));
+}
+
+#[cfg(feature="json")]
+#[test]
+fn test_serde_json_value() {
+    use redis::{FromRedisValue, ToRedisArgs, Value};
+
+    let json: serde_json::Value = serde_json::from_str(r#"{"a":[1,2.5,null],"b":"x"}"#)
+        .unwrap();
+    assert_eq!(json.to_redis_args(), vec![br#"{"a":[1,2.5,null],"b":"x"}"#.to_vec()]);
+    let v = Value::Data(br#"{"b": "x", "a": [1, 2.5, null]}"#.to_vec());
+    assert_eq!(serde_json::Value::from_redis_value(&v), Ok(json));
+
+    assert_eq!(serde_json::Value::from_redis_value(&Value::Int(7)),
+               Ok(serde_json::Value::from(7)));
+    assert_eq!(serde_json::Value::from_redis_value(&Value::Boolean(true)),
+               Ok(serde_json::Value::Bool(true)));
+
+    let v = Value::Bulk(vec![Value::Data(b"[1]".to_vec()), Value::Nil]);
+    let values: Vec<Option<serde_json::Value>> = FromRedisValue::from_redis_value(&v).unwrap();
+    assert_eq!(values, vec![Some(serde_json::Value::from(vec![1])), None]);
+
+    let v = Value::Data(b"{".to_vec());
+    assert_eq!(serde_json::Value::from_redis_value(&v).unwrap_err().kind(),
+               redis::ErrorKind::TypeError);
+}
+
+#[test]
+fn test_ttl() {
+    use redis::Ttl;
+    use std::time::Duration;
+
+    assert_eq!(Ttl::from_secs(-1), Ttl::NoExpiry);
+    assert_eq!(Ttl::from_secs(-2), Ttl::Missing);
+    assert_eq!(Ttl::from_secs(90), Ttl::Expires(Duration::from_secs(90)));
+    assert_eq!(Ttl::from_millis(-2), Ttl::Missing);
+    assert_eq!(Ttl::from_millis(1500), Ttl::Expires(Duration::from_millis(1500)));
+}
+
+
+#[derive(Debug, PartialEq)]
+enum Status {
+    Active,
+    Suspended,
+}
+
+redis_string_enum!(Status {
+    Active => "active",
+    Suspended => "suspended",
+});
+
+#[test]
+fn test_string_enum() {
+    use redis::{FromRedisValue, ToRedisArgs, Value, ErrorKind};
+
+    assert_eq!(Status::Active.to_redis_args(), vec![b"active".to_vec()]);
+    assert_eq!(Status::Suspended.to_redis_args(), vec![b"suspended".to_vec()]);
+
+    let v = FromRedisValue::from_redis_value(&Value::Data(b"suspended".to_vec()));
+    assert_eq!(v, Ok(Status::Suspended));
+    let v = FromRedisValue::from_redis_value(&Value::Status("active".into()));
+    assert_eq!(v, Ok(Status::Active));
+
+    let v: Result<Status, _> = FromRedisValue::from_redis_value(&Value::Data(b"gone".to_vec()));
+    assert_eq!(v.unwrap_err().kind(), ErrorKind::TypeError);
+    let v: Result<Status, _> = FromRedisValue::from_redis_value(&Value::Nil);
+    assert_eq!(v.unwrap_err().kind(), ErrorKind::TypeError);
+}
+
+
+#[test]
+fn test_script_builder() {
+    use redis::{Script, ScriptBuilder};
+
+    let script = ScriptBuilder::new()
+        .helper("\n  local function a() return 1 end  \n\n")
+        .helper("local function b() return 2 end")
+        .helper("  local function a() return 1 end\n")
+        .build("return a() + b()");
+    let expected = Script::new("  local function a() return 1 end\n\
+                                local function b() return 2 end\n\
+                                return a() + b()");
+    assert_eq!(script.get_hash(), expected.get_hash());
+
+    let plain = ScriptBuilder::new().build("\nreturn 1\n");
+    assert_eq!(plain.get_hash(), Script::new("return 1").get_hash());
+}